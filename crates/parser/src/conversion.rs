@@ -1,6 +1,11 @@
 use crate::Result;
 use crate::{module::Code, visit::process_operators_and_validate};
-use alloc::{boxed::Box, format, string::ToString, vec::Vec};
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
 use tinywasm_types::*;
 use wasmparser::{FuncValidator, FuncValidatorAllocations, OperatorsReader, ValidatorResources};
 
@@ -34,11 +39,12 @@ pub(crate) fn convert_module_element(element: wasmparser::Element<'_>) -> Result
         wasmparser::ElementItems::Expressions(ty, exprs) => {
             let items = exprs
                 .into_iter()
-                .map(|expr| Ok(ElementItem::Expr(process_const_operators(expr?.get_operators_reader())?)))
+                .map(|expr| Ok(ElementItem::Expr(process_single_const_operator(expr?.get_operators_reader())?)))
                 .collect::<Result<Vec<_>>>()?
                 .into_boxed_slice();
 
-            Ok(tinywasm_types::Element { kind, items, ty: convert_reftype(ty), range: element.range })
+            let ty = convert_reftype(ty, element.range.start)?;
+            Ok(tinywasm_types::Element { kind, items, ty, range: element.range })
         }
     }
 }
@@ -63,36 +69,29 @@ pub(crate) fn convert_module_data(data: wasmparser::Data<'_>) -> Result<tinywasm
     })
 }
 
-pub(crate) fn convert_module_imports<'a, T: IntoIterator<Item = wasmparser::Result<wasmparser::Import<'a>>>>(
-    imports: T,
-) -> Result<Vec<Import>> {
-    imports.into_iter().map(|import| convert_module_import(import?)).collect::<Result<Vec<_>>>()
+pub(crate) fn convert_module_imports(imports: wasmparser::ImportSectionReader<'_>) -> Result<Vec<Import>> {
+    imports
+        .into_iter_with_offsets()
+        .map(|import| {
+            let (offset, import) = import?;
+            convert_module_import(import, offset)
+        })
+        .collect::<Result<Vec<_>>>()
 }
 
-pub(crate) fn convert_module_import(import: wasmparser::Import<'_>) -> Result<Import> {
+pub(crate) fn convert_module_import(import: wasmparser::Import<'_>, offset: usize) -> Result<Import> {
     Ok(Import {
         module: import.module.to_string().into_boxed_str(),
         name: import.name.to_string().into_boxed_str(),
         kind: match import.ty {
             wasmparser::TypeRef::Func(ty) => ImportKind::Function(ty),
-            wasmparser::TypeRef::Table(ty) => ImportKind::Table(TableType {
-                element_type: convert_reftype(ty.element_type),
-                size_initial: ty.initial.try_into().map_err(|_| {
-                    crate::ParseError::UnsupportedOperator(format!("Table size initial is too large: {}", ty.initial))
-                })?,
-                size_max: match ty.maximum {
-                    Some(max) => Some(max.try_into().map_err(|_| {
-                        crate::ParseError::UnsupportedOperator(format!("Table size max is too large: {max}"))
-                    })?),
-                    None => None,
-                },
-            }),
+            wasmparser::TypeRef::Table(ty) => ImportKind::Table(convert_table_type(ty, offset)?),
             wasmparser::TypeRef::Memory(ty) => ImportKind::Memory(convert_module_memory(ty)),
             wasmparser::TypeRef::Global(ty) => {
-                ImportKind::Global(GlobalType { mutable: ty.mutable, ty: convert_valtype(&ty.content_type) })
+                ImportKind::Global(GlobalType { mutable: ty.mutable, ty: convert_valtype(&ty.content_type, offset)? })
             }
             wasmparser::TypeRef::Tag(ty) => {
-                return Err(crate::ParseError::UnsupportedOperator(format!("Unsupported import kind: {ty:?}")))
+                return Err(unsupported(format!("Unsupported import kind: {ty:?}"), offset))
             }
         },
     })
@@ -111,38 +110,43 @@ pub(crate) fn convert_module_memory(memory: wasmparser::MemoryType) -> MemoryTyp
         memory.maximum,
         memory.page_size_log2.map(|x| 1 << x),
     )
+    .with_shared(memory.shared)
 }
 
-pub(crate) fn convert_module_tables<'a, T: IntoIterator<Item = wasmparser::Result<wasmparser::Table<'a>>>>(
-    table_types: T,
-) -> Result<Vec<TableType>> {
-    table_types.into_iter().map(|table| convert_module_table(table?)).collect::<Result<Vec<_>>>()
+pub(crate) fn convert_module_tables(table_types: wasmparser::TableSectionReader<'_>) -> Result<Vec<TableType>> {
+    table_types
+        .into_iter_with_offsets()
+        .map(|table| {
+            let (offset, table) = table?;
+            convert_table_type(table.ty, offset)
+        })
+        .collect::<Result<Vec<_>>>()
 }
 
-pub(crate) fn convert_module_table(table: wasmparser::Table<'_>) -> Result<TableType> {
-    let size_initial = table.ty.initial.try_into().map_err(|_| {
-        crate::ParseError::UnsupportedOperator(format!("Table size initial is too large: {}", table.ty.initial))
-    })?;
+pub(crate) fn convert_table_type(ty: wasmparser::TableType, offset: usize) -> Result<TableType> {
+    let size_initial = ty
+        .initial
+        .try_into()
+        .map_err(|_| unsupported(format!("Table size initial is too large: {}", ty.initial), offset))?;
 
-    let size_max = match table.ty.maximum {
-        Some(max) => Some(
-            max.try_into()
-                .map_err(|_| crate::ParseError::UnsupportedOperator(format!("Table size max is too large: {max}")))?,
-        ),
+    let size_max = match ty.maximum {
+        Some(max) => {
+            Some(max.try_into().map_err(|_| unsupported(format!("Table size max is too large: {max}"), offset))?)
+        }
         None => None,
     };
 
-    Ok(TableType { element_type: convert_reftype(table.ty.element_type), size_initial, size_max })
+    Ok(TableType { element_type: convert_reftype(ty.element_type, offset)?, size_initial, size_max })
 }
 
 pub(crate) fn convert_module_globals(
     globals: wasmparser::SectionLimited<'_, wasmparser::Global<'_>>,
 ) -> Result<Vec<Global>> {
     let globals = globals
-        .into_iter()
+        .into_iter_with_offsets()
         .map(|global| {
-            let global = global?;
-            let ty = convert_valtype(&global.ty.content_type);
+            let (offset, global) = global?;
+            let ty = convert_valtype(&global.ty.content_type, offset)?;
             let ops = global.init_expr.get_operators_reader();
             Ok(Global { init: process_const_operators(ops)?, ty: GlobalType { mutable: global.ty.mutable, ty } })
         })
@@ -150,14 +154,30 @@ pub(crate) fn convert_module_globals(
     Ok(globals)
 }
 
-pub(crate) fn convert_module_export(export: wasmparser::Export<'_>) -> Result<Export> {
+pub(crate) fn convert_func_names(names: wasmparser::NameSectionReader<'_>) -> Result<Vec<(FuncAddr, Box<str>)>> {
+    let mut func_names = Vec::new();
+    for name in names {
+        if let wasmparser::Name::Function(map) = name? {
+            for naming in map {
+                let naming = naming?;
+                func_names.push((naming.index, naming.name.into()));
+            }
+        }
+    }
+
+    func_names.sort_by_key(|(idx, _)| *idx);
+    func_names.dedup_by_key(|(idx, _)| *idx);
+    Ok(func_names)
+}
+
+pub(crate) fn convert_module_export(export: wasmparser::Export<'_>, offset: usize) -> Result<Export> {
     let kind = match export.kind {
         wasmparser::ExternalKind::Func => ExternalKind::Func,
         wasmparser::ExternalKind::Table => ExternalKind::Table,
         wasmparser::ExternalKind::Memory => ExternalKind::Memory,
         wasmparser::ExternalKind::Global => ExternalKind::Global,
         wasmparser::ExternalKind::Tag => {
-            return Err(crate::ParseError::UnsupportedOperator(format!("Unsupported export kind: {:?}", export.kind)))
+            return Err(unsupported(format!("Unsupported export kind: {:?}", export.kind), offset))
         }
     };
 
@@ -166,7 +186,9 @@ pub(crate) fn convert_module_export(export: wasmparser::Export<'_>) -> Result<Ex
 
 pub(crate) fn convert_module_code(
     func: wasmparser::FunctionBody<'_>,
+    func_idx: FuncAddr,
     mut validator: FuncValidator<ValidatorResources>,
+    instruction_offsets: bool,
 ) -> Result<(Code, FuncValidatorAllocations)> {
     let locals_reader = func.get_locals_reader()?;
     let count = locals_reader.get_count();
@@ -199,79 +221,111 @@ pub(crate) fn convert_module_code(
                 local_addr_map.push(local_counts.cref);
                 local_counts.cref += 1;
             }
-            None => return Err(crate::ParseError::UnsupportedOperator("Unknown local type".to_string())),
+            None => return Err(unsupported("Unknown local type".to_string(), pos)),
         }
     }
 
-    let (body, data, allocations) = process_operators_and_validate(validator, func, local_addr_map)?;
+    let (body, data, allocations) =
+        process_operators_and_validate(validator, func, func_idx, local_addr_map, instruction_offsets)?;
     Ok(((body, data, local_counts), allocations))
 }
 
-pub(crate) fn convert_module_type(ty: wasmparser::RecGroup) -> Result<FuncType> {
+pub(crate) fn convert_module_type(ty: wasmparser::RecGroup, offset: usize) -> Result<FuncType> {
     let mut types = ty.types();
 
+    // structs and arrays can be part of any recursion group, so check them first
+    if ty.types().any(|ty| !matches!(ty.composite_type.inner, wasmparser::CompositeInnerType::Func(_))) {
+        return Err(unsupported_gc(offset));
+    }
+
     if types.len() != 1 {
-        return Err(crate::ParseError::UnsupportedOperator(
-            "Expected exactly one type in the type section".to_string(),
-        ));
+        return Err(unsupported("Expected exactly one type in the type section".to_string(), offset));
     }
 
     let ty = types.next().unwrap().unwrap_func();
-    let params = ty.params().iter().map(convert_valtype).collect::<Vec<ValType>>().into_boxed_slice();
-    let results = ty.results().iter().map(convert_valtype).collect::<Vec<ValType>>().into_boxed_slice();
+    let convert = |ty| convert_valtype(ty, offset);
+    let params = ty.params().iter().map(convert).collect::<Result<Vec<ValType>>>()?.into_boxed_slice();
+    let results = ty.results().iter().map(convert).collect::<Result<Vec<ValType>>>()?.into_boxed_slice();
 
     Ok(FuncType { params, results })
 }
 
-pub(crate) fn convert_reftype(reftype: wasmparser::RefType) -> ValType {
-    match reftype {
-        _ if reftype.is_func_ref() => ValType::RefFunc,
-        _ if reftype.is_extern_ref() => ValType::RefExtern,
-        _ => unimplemented!("Unsupported reference type: {:?}, {:?}", reftype, reftype.heap_type()),
-    }
+pub(crate) fn convert_reftype(reftype: wasmparser::RefType, offset: usize) -> Result<ValType> {
+    convert_heaptype(reftype.heap_type(), offset)
 }
 
-pub(crate) fn convert_valtype(valtype: &wasmparser::ValType) -> ValType {
-    match valtype {
+pub(crate) fn convert_valtype(valtype: &wasmparser::ValType, offset: usize) -> Result<ValType> {
+    Ok(match valtype {
         wasmparser::ValType::I32 => ValType::I32,
         wasmparser::ValType::I64 => ValType::I64,
         wasmparser::ValType::F32 => ValType::F32,
         wasmparser::ValType::F64 => ValType::F64,
         wasmparser::ValType::V128 => ValType::V128,
-        wasmparser::ValType::Ref(r) => convert_reftype(*r),
-    }
+        wasmparser::ValType::Ref(r) => convert_reftype(*r, offset)?,
+    })
+}
+
+/// The error for the parts of the GC proposal other than i31 references
+pub(crate) fn unsupported_gc(offset: usize) -> crate::ParseError {
+    crate::ParseError::UnsupportedFeature { feature: "gc: struct/array".to_string(), offset }
+}
+
+/// The error for an unsupported operator outside of a function body
+fn unsupported(operator: String, offset: usize) -> crate::ParseError {
+    crate::ParseError::UnsupportedOperator { operator, func: None, offset }
 }
 
-pub(crate) fn process_const_operators(ops: OperatorsReader<'_>) -> Result<ConstInstruction> {
-    let ops = ops.into_iter().collect::<wasmparser::Result<Vec<_>>>()?;
-    // In practice, the len can never be something other than 2,
-    // but we'll keep this here since it's part of the spec
+pub(crate) fn process_const_operators(ops: OperatorsReader<'_>) -> Result<Box<[ConstInstruction]>> {
+    let ops = ops.into_iter_with_offsets().collect::<wasmparser::Result<Vec<_>>>()?;
     // Invalid modules will be rejected by the validator anyway (there are also tests for this in the testsuite)
     assert!(ops.len() >= 2);
-    assert!(matches!(ops[ops.len() - 1], wasmparser::Operator::End));
+    assert!(matches!(ops[ops.len() - 1].0, wasmparser::Operator::End));
 
-    match &ops[ops.len() - 2] {
-        wasmparser::Operator::RefNull { hty } => match convert_heaptype(*hty) {
+    ops[..ops.len() - 1].iter().map(|(op, offset)| process_const_operator(op, *offset)).collect()
+}
+
+/// Convert a constant expression that has to be a single instruction, e.g. a reference in an element segment
+pub(crate) fn process_single_const_operator(ops: OperatorsReader<'_>) -> Result<ConstInstruction> {
+    let offset = ops.original_position();
+    match *process_const_operators(ops)? {
+        [instr] => Ok(instr),
+        ref instrs => Err(unsupported(format!("Unsupported const expression: {instrs:?}"), offset)),
+    }
+}
+
+fn process_const_operator(op: &wasmparser::Operator<'_>, offset: usize) -> Result<ConstInstruction> {
+    match op {
+        wasmparser::Operator::RefNull { hty } => match convert_heaptype(*hty, offset)? {
             ValType::RefFunc => Ok(ConstInstruction::RefFunc(None)),
             ValType::RefExtern => Ok(ConstInstruction::RefExtern(None)),
-            _ => unimplemented!("Unsupported heap type: {:?}", hty),
+            ValType::RefI31 => Ok(ConstInstruction::RefNullI31),
+            ty => Err(unsupported(format!("Unsupported ref.null type: {ty:?}"), offset)),
         },
+        wasmparser::Operator::RefI31 => Ok(ConstInstruction::RefI31),
         wasmparser::Operator::RefFunc { function_index } => Ok(ConstInstruction::RefFunc(Some(*function_index))),
         wasmparser::Operator::I32Const { value } => Ok(ConstInstruction::I32Const(*value)),
         wasmparser::Operator::I64Const { value } => Ok(ConstInstruction::I64Const(*value)),
         wasmparser::Operator::F32Const { value } => Ok(ConstInstruction::F32Const(f32::from_bits(value.bits()))),
         wasmparser::Operator::F64Const { value } => Ok(ConstInstruction::F64Const(f64::from_bits(value.bits()))),
+        wasmparser::Operator::V128Const { value } => Ok(ConstInstruction::V128Const(value.i128() as u128)),
         wasmparser::Operator::GlobalGet { global_index } => Ok(ConstInstruction::GlobalGet(*global_index)),
-        op => Err(crate::ParseError::UnsupportedOperator(format!("Unsupported const instruction: {op:?}"))),
+        wasmparser::Operator::I32Add => Ok(ConstInstruction::I32Add),
+        wasmparser::Operator::I32Sub => Ok(ConstInstruction::I32Sub),
+        wasmparser::Operator::I32Mul => Ok(ConstInstruction::I32Mul),
+        wasmparser::Operator::I64Add => Ok(ConstInstruction::I64Add),
+        wasmparser::Operator::I64Sub => Ok(ConstInstruction::I64Sub),
+        wasmparser::Operator::I64Mul => Ok(ConstInstruction::I64Mul),
+        op => Err(unsupported(format!("Unsupported const instruction: {op:?}"), offset)),
     }
 }
 
-pub(crate) fn convert_heaptype(heap: wasmparser::HeapType) -> ValType {
+pub(crate) fn convert_heaptype(heap: wasmparser::HeapType, offset: usize) -> Result<ValType> {
+    use wasmparser::AbstractHeapType::{Array, Extern, Func, Struct, I31};
     match heap {
-        wasmparser::HeapType::Abstract { shared: false, ty: wasmparser::AbstractHeapType::Func } => ValType::RefFunc,
-        wasmparser::HeapType::Abstract { shared: false, ty: wasmparser::AbstractHeapType::Extern } => {
-            ValType::RefExtern
-        }
-        _ => unimplemented!("Unsupported heap type: {:?}", heap),
+        wasmparser::HeapType::Abstract { shared: false, ty: Func } => Ok(ValType::RefFunc),
+        wasmparser::HeapType::Abstract { shared: false, ty: Extern } => Ok(ValType::RefExtern),
+        wasmparser::HeapType::Abstract { shared: false, ty: I31 } => Ok(ValType::RefI31),
+        wasmparser::HeapType::Abstract { shared: false, ty: Struct | Array } => Err(unsupported_gc(offset)),
+        _ => Err(crate::ParseError::UnsupportedFeature { feature: format!("heap type {heap:?}"), offset }),
     }
 }
@@ -9,13 +9,37 @@ pub enum ParseError {
     /// An invalid type was encountered
     InvalidType,
     /// An unsupported section was encountered
-    UnsupportedSection(String),
+    UnsupportedSection {
+        /// The name of the section
+        section: String,
+        /// The offset of the section in the module
+        offset: usize,
+    },
     /// A duplicate section was encountered
-    DuplicateSection(String),
+    DuplicateSection {
+        /// The name of the section
+        section: String,
+        /// The offset of the second occurrence of the section in the module
+        offset: usize,
+    },
     /// An empty section was encountered
     EmptySection(String),
     /// An unsupported operator was encountered
-    UnsupportedOperator(String),
+    UnsupportedOperator {
+        /// The name of the operator
+        operator: String,
+        /// The index of the function containing the operator, `None` outside of function bodies
+        func: Option<u32>,
+        /// The offset of the operator in the module
+        offset: usize,
+    },
+    /// A module uses a part of a proposal that is not supported, e.g. `gc: struct/array`
+    UnsupportedFeature {
+        /// The unsupported part of the proposal
+        feature: String,
+        /// The offset in the module where it is used
+        offset: usize,
+    },
     /// An error occurred while parsing the module
     ParseError {
         /// The error message
@@ -33,7 +57,10 @@ pub enum ParseError {
         actual: u32,
     },
     /// The end of the module was not reached
-    EndNotReached,
+    EndNotReached {
+        /// The offset up to which the module was read
+        offset: usize,
+    },
     /// An unknown error occurred
     Other(String),
 }
@@ -42,23 +69,50 @@ impl Display for ParseError {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::InvalidType => write!(f, "invalid type"),
-            Self::UnsupportedSection(section) => write!(f, "unsupported section: {section}"),
-            Self::DuplicateSection(section) => write!(f, "duplicate section: {section}"),
+            Self::UnsupportedSection { section, offset } => {
+                write!(f, "unsupported section: {section} at offset {offset}")
+            }
+            Self::DuplicateSection { section, offset } => write!(f, "duplicate section: {section} at offset {offset}"),
             Self::EmptySection(section) => write!(f, "empty section: {section}"),
-            Self::UnsupportedOperator(operator) => write!(f, "unsupported operator: {operator}"),
-            Self::ParseError { message, offset } => {
-                write!(f, "error parsing module: {message} at offset {offset}")
+            Self::UnsupportedOperator { operator, func: Some(func), offset } => {
+                write!(f, "unsupported operator: {operator} in function {func} at offset {offset}")
+            }
+            Self::UnsupportedOperator { operator, func: None, offset } => {
+                write!(f, "unsupported operator: {operator} at offset {offset}")
             }
+            Self::UnsupportedFeature { feature, offset } => {
+                write!(f, "unsupported feature: {feature} at offset {offset}")
+            }
+            Self::ParseError { message, offset } => write!(f, "{message} at offset {offset}"),
             Self::InvalidEncoding(encoding) => write!(f, "invalid encoding: {encoding:?}"),
             Self::InvalidLocalCount { expected, actual } => {
                 write!(f, "invalid local count: expected {expected}, actual {actual}")
             }
-            Self::EndNotReached => write!(f, "end of module not reached"),
+            Self::EndNotReached { offset } => write!(f, "end of module not reached, stopped at offset {offset}"),
             Self::Other(message) => write!(f, "unknown error: {message}"),
         }
     }
 }
 
+impl ParseError {
+    /// The offset in the module where the error occurred, if known
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Self::UnsupportedSection { offset, .. }
+            | Self::DuplicateSection { offset, .. }
+            | Self::UnsupportedOperator { offset, .. }
+            | Self::UnsupportedFeature { offset, .. }
+            | Self::ParseError { offset, .. }
+            | Self::EndNotReached { offset } => Some(*offset),
+            Self::InvalidType
+            | Self::EmptySection(_)
+            | Self::InvalidEncoding(_)
+            | Self::InvalidLocalCount { .. }
+            | Self::Other(_) => None,
+        }
+    }
+}
+
 impl core::error::Error for ParseError {}
 
 impl From<wasmparser::BinaryReaderError> for ParseError {
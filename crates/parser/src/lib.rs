@@ -41,15 +41,44 @@ pub use tinywasm_types::TinyWasmModule;
 
 /// A WebAssembly parser
 #[derive(Default, Debug)]
-pub struct Parser {}
+pub struct Parser {
+    instruction_offsets: bool,
+    gc: bool,
+}
 
 impl Parser {
     /// Create a new parser instance
     pub fn new() -> Self {
-        Self {}
+        Self::default()
+    }
+
+    /// Record the byte offset of every instruction in the wasm binary
+    ///
+    /// The offsets end up in [`WasmFunctionData::instruction_offsets`](tinywasm_types::WasmFunctionData)
+    /// and can be used to map traps back to the source, e.g. with DWARF debug info.
+    /// Disabled by default, since it adds 4 bytes of memory per instruction.
+    pub fn with_instruction_offsets(mut self, enabled: bool) -> Self {
+        self.instruction_offsets = enabled;
+        self
+    }
+
+    /// Accept `i31ref` and its instructions from the GC proposal
+    ///
+    /// Disabled by default: this turns on validation of the whole GC proposal, which also accepts some
+    /// modules earlier versions of the spec reject (e.g. data segment offsets reading module-defined globals).
+    /// With it enabled, GC types and instructions other than `i31ref` fail with [`ParseError::UnsupportedFeature`].
+    pub fn with_gc(mut self, enabled: bool) -> Self {
+        self.gc = enabled;
+        self
     }
 
-    fn create_validator() -> Validator {
+    fn create_reader(&self) -> ModuleReader {
+        let mut reader = ModuleReader::new();
+        reader.instruction_offsets = self.instruction_offsets;
+        reader
+    }
+
+    fn create_validator(&self) -> Validator {
         let features = WasmFeaturesInflated {
             bulk_memory: true,
             floats: true,
@@ -64,8 +93,8 @@ impl Parser {
             simd: true,
             memory64: true,
             custom_page_sizes: true,
+            extended_const: true,
 
-            extended_const: false,
             wide_arithmetic: false,
             gc_types: true,
             stack_switching: false,
@@ -74,10 +103,10 @@ impl Parser {
             component_model_values: false,
             component_model_more_flags: false,
             exceptions: false,
-            gc: false,
+            gc: self.gc,
             memory_control: false,
             relaxed_simd: false,
-            threads: false,
+            threads: true,
             shared_everything_threads: false,
             component_model_multiple_returns: false,
             legacy_exceptions: false,
@@ -89,15 +118,15 @@ impl Parser {
     /// Parse a [`TinyWasmModule`] from bytes
     pub fn parse_module_bytes(&self, wasm: impl AsRef<[u8]>) -> Result<TinyWasmModule> {
         let wasm = wasm.as_ref();
-        let mut validator = Self::create_validator();
-        let mut reader = ModuleReader::new();
+        let mut validator = self.create_validator();
+        let mut reader = self.create_reader();
 
         for payload in wasmparser::Parser::new(0).parse_all(wasm) {
             reader.process_payload(payload?, &mut validator)?;
         }
 
         if !reader.end_reached {
-            return Err(ParseError::EndNotReached);
+            return Err(ParseError::EndNotReached { offset: reader.offset });
         }
 
         reader.into_module()
@@ -119,8 +148,8 @@ impl Parser {
     pub fn parse_module_stream(&self, mut stream: impl std::io::Read) -> Result<TinyWasmModule> {
         use alloc::format;
 
-        let mut validator = Self::create_validator();
-        let mut reader = ModuleReader::new();
+        let mut validator = self.create_validator();
+        let mut reader = self.create_reader();
         let mut buffer = alloc::vec::Vec::new();
         let mut parser = wasmparser::Parser::new(0);
         let mut eof = false;
@@ -1,10 +1,10 @@
 use crate::log::debug;
 use crate::{conversion, ParseError, Result};
 use alloc::string::ToString;
-use alloc::{boxed::Box, format, vec::Vec};
+use alloc::{boxed::Box, format, sync::Arc, vec::Vec};
 use tinywasm_types::{
-    Data, Element, Export, FuncType, Global, Import, Instruction, MemoryType, TableType, TinyWasmModule, ValueCounts,
-    ValueCountsSmall, WasmFunction, WasmFunctionData,
+    Data, Element, Export, FuncType, Global, Import, ImportKind, Instruction, MemoryType, TableType, TinyWasmModule,
+    ValueCounts, ValueCountsSmall, WasmFunction, WasmFunctionData,
 };
 use wasmparser::{FuncValidatorAllocations, Payload, Validator};
 
@@ -26,7 +26,11 @@ pub(crate) struct ModuleReader {
     pub(crate) imports: Vec<Import>,
     pub(crate) data: Vec<Data>,
     pub(crate) elements: Vec<Element>,
+    pub(crate) func_names: Vec<(u32, Box<str>)>,
+    pub(crate) instruction_offsets: bool,
     pub(crate) end_reached: bool,
+    /// The offset up to which the module has been read
+    pub(crate) offset: usize,
 }
 
 impl ModuleReader {
@@ -37,6 +41,14 @@ impl ModuleReader {
     pub(crate) fn process_payload(&mut self, payload: Payload<'_>, validator: &mut Validator) -> Result<()> {
         use wasmparser::Payload::*;
 
+        self.offset = match &payload {
+            Version { range, .. } => range.end,
+            CodeSectionStart { range, .. } => range.start,
+            CodeSectionEntry(body) => body.range().end,
+            CustomSection(reader) => reader.range().end,
+            payload => payload.as_section().map_or(self.offset, |(_, range)| range.end),
+        };
+
         match payload {
             Version { num, encoding, range } => {
                 validator.version(num, encoding, &range)?;
@@ -48,7 +60,7 @@ impl ModuleReader {
             }
             StartSection { func, range } => {
                 if self.start_func.is_some() {
-                    return Err(ParseError::DuplicateSection("Start section".into()));
+                    return Err(ParseError::DuplicateSection { section: "Start section".into(), offset: range.start });
                 }
 
                 debug!("Found start section");
@@ -57,20 +69,29 @@ impl ModuleReader {
             }
             TypeSection(reader) => {
                 if !self.func_types.is_empty() {
-                    return Err(ParseError::DuplicateSection("Type section".into()));
+                    return Err(ParseError::DuplicateSection {
+                        section: "Type section".into(),
+                        offset: reader.range().start,
+                    });
                 }
 
                 debug!("Found type section");
                 validator.type_section(&reader)?;
                 self.func_types = reader
-                    .into_iter()
-                    .map(|t| conversion::convert_module_type(t?))
+                    .into_iter_with_offsets()
+                    .map(|t| {
+                        let (offset, t) = t?;
+                        conversion::convert_module_type(t, offset)
+                    })
                     .collect::<Result<Vec<FuncType>>>()?;
             }
 
             GlobalSection(reader) => {
                 if !self.globals.is_empty() {
-                    return Err(ParseError::DuplicateSection("Global section".into()));
+                    return Err(ParseError::DuplicateSection {
+                        section: "Global section".into(),
+                        offset: reader.range().start,
+                    });
                 }
 
                 debug!("Found global section");
@@ -79,7 +100,10 @@ impl ModuleReader {
             }
             TableSection(reader) => {
                 if !self.table_types.is_empty() {
-                    return Err(ParseError::DuplicateSection("Table section".into()));
+                    return Err(ParseError::DuplicateSection {
+                        section: "Table section".into(),
+                        offset: reader.range().start,
+                    });
                 }
                 debug!("Found table section");
                 validator.table_section(&reader)?;
@@ -87,7 +111,10 @@ impl ModuleReader {
             }
             MemorySection(reader) => {
                 if !self.memory_types.is_empty() {
-                    return Err(ParseError::DuplicateSection("Memory section".into()));
+                    return Err(ParseError::DuplicateSection {
+                        section: "Memory section".into(),
+                        offset: reader.range().start,
+                    });
                 }
 
                 debug!("Found memory section");
@@ -101,7 +128,10 @@ impl ModuleReader {
             }
             DataSection(reader) => {
                 if !self.data.is_empty() {
-                    return Err(ParseError::DuplicateSection("Data section".into()));
+                    return Err(ParseError::DuplicateSection {
+                        section: "Data section".into(),
+                        offset: reader.range().start,
+                    });
                 }
 
                 debug!("Found data section");
@@ -111,13 +141,19 @@ impl ModuleReader {
             DataCountSection { count, range } => {
                 debug!("Found data count section");
                 if !self.data.is_empty() {
-                    return Err(ParseError::DuplicateSection("Data count section".into()));
+                    return Err(ParseError::DuplicateSection {
+                        section: "Data count section".into(),
+                        offset: range.start,
+                    });
                 }
                 validator.data_count_section(count, &range)?;
             }
             FunctionSection(reader) => {
                 if !self.code_type_addrs.is_empty() {
-                    return Err(ParseError::DuplicateSection("Function section".into()));
+                    return Err(ParseError::DuplicateSection {
+                        section: "Function section".into(),
+                        offset: reader.range().start,
+                    });
                 }
 
                 debug!("Found function section");
@@ -127,7 +163,7 @@ impl ModuleReader {
             CodeSectionStart { count, range, .. } => {
                 debug!("Found code section ({} functions)", count);
                 if !self.code.is_empty() {
-                    return Err(ParseError::DuplicateSection("Code section".into()));
+                    return Err(ParseError::DuplicateSection { section: "Code section".into(), offset: range.start });
                 }
                 self.code.reserve(count as usize);
                 validator.code_section_start(count, &range)?;
@@ -136,13 +172,18 @@ impl ModuleReader {
                 debug!("Found code section entry");
                 let v = validator.code_section_entry(&function)?;
                 let func_validator = v.into_validator(self.func_validator_allocations.take().unwrap_or_default());
-                let (code, allocations) = conversion::convert_module_code(function, func_validator)?;
+                let func_idx = self.imported_func_count() + self.code.len() as u32;
+                let (code, allocations) =
+                    conversion::convert_module_code(function, func_idx, func_validator, self.instruction_offsets)?;
                 self.code.push(code);
                 self.func_validator_allocations = Some(allocations);
             }
             ImportSection(reader) => {
                 if !self.imports.is_empty() {
-                    return Err(ParseError::DuplicateSection("Import section".into()));
+                    return Err(ParseError::DuplicateSection {
+                        section: "Import section".into(),
+                        offset: reader.range().start,
+                    });
                 }
 
                 debug!("Found import section");
@@ -151,37 +192,65 @@ impl ModuleReader {
             }
             ExportSection(reader) => {
                 if !self.exports.is_empty() {
-                    return Err(ParseError::DuplicateSection("Export section".into()));
+                    return Err(ParseError::DuplicateSection {
+                        section: "Export section".into(),
+                        offset: reader.range().start,
+                    });
                 }
 
                 debug!("Found export section");
                 validator.export_section(&reader)?;
-                self.exports =
-                    reader.into_iter().map(|e| conversion::convert_module_export(e?)).collect::<Result<Vec<_>>>()?;
+                self.exports = reader
+                    .into_iter_with_offsets()
+                    .map(|e| {
+                        let (offset, e) = e?;
+                        conversion::convert_module_export(e, offset)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
             }
             End(offset) => {
                 debug!("Reached end of module");
                 if self.end_reached {
-                    return Err(ParseError::DuplicateSection("End section".into()));
+                    return Err(ParseError::DuplicateSection { section: "End section".into(), offset });
                 }
 
                 validator.end(offset)?;
                 self.end_reached = true;
             }
-            CustomSection(_reader) => {
+            CustomSection(reader) => {
                 debug!("Found custom section");
-                debug!("Skipping custom section: {:?}", _reader.name());
+                match reader.as_known() {
+                    // names are only used for debugging, so a malformed name section is ignored
+                    wasmparser::KnownCustom::Name(names) => match conversion::convert_func_names(names) {
+                        Ok(func_names) => self.func_names = func_names,
+                        Err(_err) => debug!("Skipping malformed name section: {:?}", _err),
+                    },
+                    _ => debug!("Skipping custom section: {:?}", reader.name()),
+                }
+            }
+            UnknownSection { range, .. } => {
+                return Err(ParseError::UnsupportedSection { section: "Unknown section".into(), offset: range.start })
+            }
+            section => {
+                let offset = section.as_section().map_or(self.offset, |(_, range)| range.start);
+                return Err(ParseError::UnsupportedSection {
+                    section: format!("Unsupported section: {section:?}"),
+                    offset,
+                });
             }
-            UnknownSection { .. } => return Err(ParseError::UnsupportedSection("Unknown section".into())),
-            section => return Err(ParseError::UnsupportedSection(format!("Unsupported section: {section:?}"))),
         };
 
         Ok(())
     }
 
+    /// The number of imported functions, which come before the module's own functions in the function index space
+    fn imported_func_count(&self) -> u32 {
+        self.imports.iter().filter(|import| matches!(import.kind, ImportKind::Function(_))).count() as u32
+    }
+
     pub(crate) fn into_module(self) -> Result<TinyWasmModule> {
         if !self.end_reached {
-            return Err(ParseError::EndNotReached);
+            return Err(ParseError::EndNotReached { offset: self.offset });
         }
 
         if self.code_type_addrs.len() != self.code.len() {
@@ -195,7 +264,7 @@ impl ModuleReader {
             .map(|((instructions, data, locals), ty_idx)| {
                 let ty = self.func_types.get(ty_idx as usize).expect("No func type for func, this is a bug").clone();
                 let params = ValueCountsSmall::from(&ty.params);
-                WasmFunction { instructions, data, locals, params, ty }
+                Arc::new(WasmFunction { instructions, data, locals, params, ty })
             })
             .collect::<Vec<_>>()
             .into_boxed_slice();
@@ -214,6 +283,7 @@ impl ModuleReader {
             exports: self.exports.into_boxed_slice(),
             elements: self.elements.into_boxed_slice(),
             memory_types: self.memory_types.into_boxed_slice(),
+            func_names: self.func_names.into_boxed_slice(),
         })
     }
 }
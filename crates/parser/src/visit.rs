@@ -1,9 +1,9 @@
 use crate::Result;
 
-use crate::conversion::{convert_heaptype, convert_valtype};
-use alloc::string::ToString;
+use crate::conversion::{convert_heaptype, convert_valtype, unsupported_gc};
 use alloc::{boxed::Box, vec::Vec};
-use tinywasm_types::{Instruction, MemoryArg, SimdInstruction, WasmFunctionData};
+use alloc::{format, string::ToString};
+use tinywasm_types::{AtomicInstruction, Instruction, MemoryArg, SimdInstruction, ValType, WasmFunctionData};
 use wasmparser::{
     FuncValidator, FuncValidatorAllocations, FunctionBody, VisitOperator, VisitSimdOperator, WasmModuleResources,
 };
@@ -13,7 +13,9 @@ struct ValidateThenVisit<'a, R: WasmModuleResources>(usize, &'a mut FunctionBuil
 macro_rules! validate_then_visit {
     ($( @$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident ($($ann:tt)*))*) => {$(
         fn $visit(&mut self $($(,$arg: $argty)*)?) -> Self::Output {
+            self.1.offset = self.0;
             self.1.$visit($($($arg.clone()),*)?);
+            self.1.record_offset(self.0);
             self.1.validator_visitor(self.0).$visit($($($arg),*)?)?;
             Ok(())
         }
@@ -36,11 +38,16 @@ impl<R: WasmModuleResources> VisitSimdOperator<'_> for ValidateThenVisit<'_, R>
 pub(crate) fn process_operators_and_validate<R: WasmModuleResources>(
     validator: FuncValidator<R>,
     body: FunctionBody<'_>,
+    func_idx: u32,
     local_addr_map: Vec<u32>,
+    instruction_offsets: bool,
 ) -> Result<(Box<[Instruction]>, WasmFunctionData, FuncValidatorAllocations)> {
     let mut reader = body.get_operators_reader()?;
     let remaining = reader.get_binary_reader().bytes_remaining();
-    let mut builder = FunctionBuilder::new(remaining, validator, local_addr_map);
+    let mut builder = FunctionBuilder::new(remaining, validator, func_idx, local_addr_map);
+    if instruction_offsets {
+        builder.instruction_offsets = Some(Vec::with_capacity(remaining));
+    }
 
     while !reader.eof() {
         reader.visit_operator(&mut ValidateThenVisit(reader.original_position(), &mut builder))??;
@@ -53,7 +60,10 @@ pub(crate) fn process_operators_and_validate<R: WasmModuleResources>(
 
     Ok((
         builder.instructions.into_boxed_slice(),
-        WasmFunctionData { v128_constants: builder.v128_constants.into_boxed_slice() },
+        WasmFunctionData {
+            v128_constants: builder.v128_constants.into_boxed_slice(),
+            instruction_offsets: builder.instruction_offsets.unwrap_or_default().into_boxed_slice(),
+        },
         builder.validator.into_allocations(),
     ))
 }
@@ -106,6 +116,14 @@ macro_rules! define_mem_operands_simd {
     )*};
 }
 
+macro_rules! define_mem_operands_atomic {
+    ($($name:ident($instr:ident)),*) => {$(
+        fn $name(&mut self, memarg: wasmparser::MemArg) -> Self::Output {
+            self.instructions.push(AtomicInstruction::$instr(MemoryArg::new(memarg.offset, memarg.memory)).into());
+        }
+    )*};
+}
+
 macro_rules! define_mem_operands_simd_lane {
     ($($name:ident($instr:ident)),*) => {$(
         fn $name(&mut self, memarg: wasmparser::MemArg, lane: u8) -> Self::Output {
@@ -120,7 +138,11 @@ pub(crate) struct FunctionBuilder<R: WasmModuleResources> {
     v128_constants: Vec<u128>,
     label_ptrs: Vec<usize>,
     local_addr_map: Vec<u32>,
+    instruction_offsets: Option<Vec<u32>>,
     errors: Vec<crate::ParseError>,
+    func_idx: u32,
+    /// The offset of the operator that is currently being visited
+    offset: usize,
 }
 
 impl<R: WasmModuleResources> FunctionBuilder<R> {
@@ -137,19 +159,55 @@ impl<R: WasmModuleResources> FunctionBuilder<R> {
 }
 
 impl<R: WasmModuleResources> FunctionBuilder<R> {
-    pub(crate) fn new(instr_capacity: usize, validator: FuncValidator<R>, local_addr_map: Vec<u32>) -> Self {
+    pub(crate) fn new(
+        instr_capacity: usize,
+        validator: FuncValidator<R>,
+        func_idx: u32,
+        local_addr_map: Vec<u32>,
+    ) -> Self {
         Self {
             validator,
             local_addr_map,
             instructions: Vec::with_capacity(instr_capacity),
             v128_constants: Vec::new(),
             label_ptrs: Vec::with_capacity(256),
+            instruction_offsets: None,
             errors: Vec::new(),
+            func_idx,
+            offset: 0,
+        }
+    }
+
+    /// Attribute the instructions emitted by the last operator to its offset
+    fn record_offset(&mut self, offset: usize) {
+        if let Some(offsets) = &mut self.instruction_offsets {
+            offsets.resize(self.instructions.len(), offset as u32);
         }
     }
 
     fn unsupported(&mut self, name: &str) {
-        self.errors.push(crate::ParseError::UnsupportedOperator(name.to_string()));
+        self.errors.push(crate::ParseError::UnsupportedOperator {
+            operator: name.to_string(),
+            func: Some(self.func_idx),
+            offset: self.offset,
+        });
+    }
+
+    fn unsupported_gc(&mut self, op: &str) {
+        if op.starts_with("Struct") || op.starts_with("Array") {
+            self.errors.push(unsupported_gc(self.offset));
+        } else {
+            self.errors
+                .push(crate::ParseError::UnsupportedFeature { feature: format!("gc: {op}"), offset: self.offset });
+        }
+    }
+
+    /// Convert a type used by an instruction, an unsupported type fails the parse so the placeholder is never executed
+    fn convert_type(&mut self, ty: Result<ValType>) -> ValType {
+        ty.unwrap_or_else(|err| {
+            self.errors.push(err);
+            ValType::I32
+        })
     }
 }
 
@@ -164,6 +222,18 @@ macro_rules! impl_visit_operator {
     (@@saturating_float_to_int $($rest:tt)* ) => {};
     (@@bulk_memory $($rest:tt)* ) => {};
     (@@simd $($rest:tt)* ) => {};
+    (@@tail_call $($rest:tt)* ) => {};
+    (@@threads $($rest:tt)* ) => {};
+    (@@gc RefI31 $($rest:tt)* ) => {};
+    (@@gc I31GetS $($rest:tt)* ) => {};
+    (@@gc I31GetU $($rest:tt)* ) => {};
+
+    (@@gc $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident ($($ann:tt)*)) => {
+        #[cold]
+        fn $visit(&mut self $($(,$arg: $argty)*)?) {
+            self.unsupported_gc(stringify!($op))
+        }
+    };
 
     (@@$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident ($($ann:tt)*)) => {
         #[cold]
@@ -199,7 +269,13 @@ impl<'a, R: WasmModuleResources> wasmparser::VisitOperator<'a> for FunctionBuild
         visit_ref_func(RefFunc, u32), visit_table_fill(TableFill, u32), visit_table_get(TableGet, u32), visit_table_set(TableSet, u32), visit_table_grow(TableGrow, u32), visit_table_size(TableSize, u32),
 
         // Bulk Memory
-        visit_memory_init(MemoryInit, u32, u32), visit_memory_copy(MemoryCopy, u32, u32), visit_table_init(TableInit, u32, u32), visit_memory_fill(MemoryFill, u32), visit_data_drop(DataDrop, u32), visit_elem_drop(ElemDrop, u32)
+        visit_memory_init(MemoryInit, u32, u32), visit_memory_copy(MemoryCopy, u32, u32), visit_table_init(TableInit, u32, u32), visit_memory_fill(MemoryFill, u32), visit_data_drop(DataDrop, u32), visit_elem_drop(ElemDrop, u32),
+
+        // Tail Calls
+        visit_return_call(ReturnCall, u32),
+
+        // GC (i31 references only)
+        visit_ref_i31(RefI31), visit_i31_get_s(I31GetS), visit_i31_get_u(I31GetU)
     }
 
     fn visit_global_set(&mut self, global_index: u32) -> Self::Output {
@@ -239,9 +315,7 @@ impl<'a, R: WasmModuleResources> wasmparser::VisitOperator<'a> for FunctionBuild
 
     fn visit_local_get(&mut self, idx: u32) -> Self::Output {
         let Ok(resolved_idx) = self.local_addr_map[idx as usize].try_into() else {
-            self.errors.push(crate::ParseError::UnsupportedOperator(
-                "Local index is too large, tinywasm does not support local indexes that large".to_string(),
-            ));
+            self.unsupported("Local index is too large, tinywasm does not support local indexes that large");
             return;
         };
 
@@ -260,9 +334,7 @@ impl<'a, R: WasmModuleResources> wasmparser::VisitOperator<'a> for FunctionBuild
 
     fn visit_local_set(&mut self, idx: u32) -> Self::Output {
         let Ok(resolved_idx) = self.local_addr_map[idx as usize].try_into() else {
-            self.errors.push(crate::ParseError::UnsupportedOperator(
-                "Local index is too large, tinywasm does not support local indexes that large".to_string(),
-            ));
+            self.unsupported("Local index is too large, tinywasm does not support local indexes that large");
             return;
         };
 
@@ -305,9 +377,7 @@ impl<'a, R: WasmModuleResources> wasmparser::VisitOperator<'a> for FunctionBuild
 
     fn visit_local_tee(&mut self, idx: u32) -> Self::Output {
         let Ok(resolved_idx) = self.local_addr_map[idx as usize].try_into() else {
-            self.errors.push(crate::ParseError::UnsupportedOperator(
-                "Local index is too large, tinywasm does not support local indexes that large".to_string(),
-            ));
+            self.unsupported("Local index is too large, tinywasm does not support local indexes that large");
             return;
         };
 
@@ -325,30 +395,39 @@ impl<'a, R: WasmModuleResources> wasmparser::VisitOperator<'a> for FunctionBuild
     }
 
     fn visit_block(&mut self, blockty: wasmparser::BlockType) -> Self::Output {
-        self.label_ptrs.push(self.instructions.len());
-        self.instructions.push(match blockty {
+        let instr = match blockty {
             wasmparser::BlockType::Empty => Instruction::Block(0),
             wasmparser::BlockType::FuncType(idx) => Instruction::BlockWithFuncType(idx, 0),
-            wasmparser::BlockType::Type(ty) => Instruction::BlockWithType(convert_valtype(&ty), 0),
-        });
+            wasmparser::BlockType::Type(ty) => {
+                Instruction::BlockWithType(self.convert_type(convert_valtype(&ty, self.offset)), 0)
+            }
+        };
+        self.label_ptrs.push(self.instructions.len());
+        self.instructions.push(instr);
     }
 
     fn visit_loop(&mut self, ty: wasmparser::BlockType) -> Self::Output {
-        self.label_ptrs.push(self.instructions.len());
-        self.instructions.push(match ty {
+        let instr = match ty {
             wasmparser::BlockType::Empty => Instruction::Loop(0),
             wasmparser::BlockType::FuncType(idx) => Instruction::LoopWithFuncType(idx, 0),
-            wasmparser::BlockType::Type(ty) => Instruction::LoopWithType(convert_valtype(&ty), 0),
-        });
+            wasmparser::BlockType::Type(ty) => {
+                Instruction::LoopWithType(self.convert_type(convert_valtype(&ty, self.offset)), 0)
+            }
+        };
+        self.label_ptrs.push(self.instructions.len());
+        self.instructions.push(instr);
     }
 
     fn visit_if(&mut self, ty: wasmparser::BlockType) -> Self::Output {
-        self.label_ptrs.push(self.instructions.len());
-        self.instructions.push(match ty {
+        let instr = match ty {
             wasmparser::BlockType::Empty => Instruction::If(0, 0),
             wasmparser::BlockType::FuncType(idx) => Instruction::IfWithFuncType(idx, 0, 0),
-            wasmparser::BlockType::Type(ty) => Instruction::IfWithType(convert_valtype(&ty), 0, 0),
-        });
+            wasmparser::BlockType::Type(ty) => {
+                Instruction::IfWithType(self.convert_type(convert_valtype(&ty, self.offset)), 0, 0)
+            }
+        };
+        self.label_ptrs.push(self.instructions.len());
+        self.instructions.push(instr);
     }
 
     fn visit_else(&mut self) -> Self::Output {
@@ -370,9 +449,7 @@ impl<'a, R: WasmModuleResources> wasmparser::VisitOperator<'a> for FunctionBuild
 
                 // since we're ending an else block, we need to end the if block as well
                 let Some(if_label_pointer) = self.label_ptrs.pop() else {
-                    self.errors.push(crate::ParseError::UnsupportedOperator(
-                        "Expected to end an if block, but there was no if block to end".to_string(),
-                    ));
+                    self.unsupported("Expected to end an if block, but there was no if block to end");
 
                     return;
                 };
@@ -384,9 +461,7 @@ impl<'a, R: WasmModuleResources> wasmparser::VisitOperator<'a> for FunctionBuild
                     | Instruction::IfWithFuncType(_, else_offset, end_offset)
                     | Instruction::IfWithType(_, else_offset, end_offset) => (else_offset, end_offset),
                     _ => {
-                        self.errors.push(crate::ParseError::UnsupportedOperator(
-                            "Expected to end an if block, but the last label was not an if".to_string(),
-                        ));
+                        self.unsupported("Expected to end an if block, but the last label was not an if");
 
                         return;
                     }
@@ -438,6 +513,10 @@ impl<'a, R: WasmModuleResources> wasmparser::VisitOperator<'a> for FunctionBuild
         self.instructions.push(Instruction::CallIndirect(ty, table));
     }
 
+    fn visit_return_call_indirect(&mut self, ty: u32, table: u32) -> Self::Output {
+        self.instructions.push(Instruction::ReturnCallIndirect(ty, table));
+    }
+
     fn visit_f32_const(&mut self, val: wasmparser::Ieee32) -> Self::Output {
         self.instructions.push(Instruction::F32Const(f32::from_bits(val.bits())));
     }
@@ -452,13 +531,39 @@ impl<'a, R: WasmModuleResources> wasmparser::VisitOperator<'a> for FunctionBuild
 
     // Reference Types
     fn visit_ref_null(&mut self, ty: wasmparser::HeapType) -> Self::Output {
-        self.instructions.push(Instruction::RefNull(convert_heaptype(ty)));
+        let ty = self.convert_type(convert_heaptype(ty, self.offset));
+        self.instructions.push(Instruction::RefNull(ty));
     }
 
     fn visit_ref_is_null(&mut self) -> Self::Output {
         self.instructions.push(Instruction::RefIsNull);
     }
 
+    // threads
+    define_mem_operands_atomic! {
+        visit_memory_atomic_notify(MemoryAtomicNotify), visit_memory_atomic_wait32(MemoryAtomicWait32), visit_memory_atomic_wait64(MemoryAtomicWait64), visit_i32_atomic_load(I32AtomicLoad),
+        visit_i64_atomic_load(I64AtomicLoad), visit_i32_atomic_load8_u(I32AtomicLoad8U), visit_i32_atomic_load16_u(I32AtomicLoad16U), visit_i64_atomic_load8_u(I64AtomicLoad8U),
+        visit_i64_atomic_load16_u(I64AtomicLoad16U), visit_i64_atomic_load32_u(I64AtomicLoad32U), visit_i32_atomic_store(I32AtomicStore), visit_i64_atomic_store(I64AtomicStore),
+        visit_i32_atomic_store8(I32AtomicStore8), visit_i32_atomic_store16(I32AtomicStore16), visit_i64_atomic_store8(I64AtomicStore8), visit_i64_atomic_store16(I64AtomicStore16),
+        visit_i64_atomic_store32(I64AtomicStore32), visit_i32_atomic_rmw_add(I32AtomicRmwAdd), visit_i64_atomic_rmw_add(I64AtomicRmwAdd), visit_i32_atomic_rmw8_add_u(I32AtomicRmw8AddU),
+        visit_i32_atomic_rmw16_add_u(I32AtomicRmw16AddU), visit_i64_atomic_rmw8_add_u(I64AtomicRmw8AddU), visit_i64_atomic_rmw16_add_u(I64AtomicRmw16AddU), visit_i64_atomic_rmw32_add_u(I64AtomicRmw32AddU),
+        visit_i32_atomic_rmw_sub(I32AtomicRmwSub), visit_i64_atomic_rmw_sub(I64AtomicRmwSub), visit_i32_atomic_rmw8_sub_u(I32AtomicRmw8SubU), visit_i32_atomic_rmw16_sub_u(I32AtomicRmw16SubU),
+        visit_i64_atomic_rmw8_sub_u(I64AtomicRmw8SubU), visit_i64_atomic_rmw16_sub_u(I64AtomicRmw16SubU), visit_i64_atomic_rmw32_sub_u(I64AtomicRmw32SubU), visit_i32_atomic_rmw_and(I32AtomicRmwAnd),
+        visit_i64_atomic_rmw_and(I64AtomicRmwAnd), visit_i32_atomic_rmw8_and_u(I32AtomicRmw8AndU), visit_i32_atomic_rmw16_and_u(I32AtomicRmw16AndU), visit_i64_atomic_rmw8_and_u(I64AtomicRmw8AndU),
+        visit_i64_atomic_rmw16_and_u(I64AtomicRmw16AndU), visit_i64_atomic_rmw32_and_u(I64AtomicRmw32AndU), visit_i32_atomic_rmw_or(I32AtomicRmwOr), visit_i64_atomic_rmw_or(I64AtomicRmwOr),
+        visit_i32_atomic_rmw8_or_u(I32AtomicRmw8OrU), visit_i32_atomic_rmw16_or_u(I32AtomicRmw16OrU), visit_i64_atomic_rmw8_or_u(I64AtomicRmw8OrU), visit_i64_atomic_rmw16_or_u(I64AtomicRmw16OrU),
+        visit_i64_atomic_rmw32_or_u(I64AtomicRmw32OrU), visit_i32_atomic_rmw_xor(I32AtomicRmwXor), visit_i64_atomic_rmw_xor(I64AtomicRmwXor), visit_i32_atomic_rmw8_xor_u(I32AtomicRmw8XorU),
+        visit_i32_atomic_rmw16_xor_u(I32AtomicRmw16XorU), visit_i64_atomic_rmw8_xor_u(I64AtomicRmw8XorU), visit_i64_atomic_rmw16_xor_u(I64AtomicRmw16XorU), visit_i64_atomic_rmw32_xor_u(I64AtomicRmw32XorU),
+        visit_i32_atomic_rmw_xchg(I32AtomicRmwXchg), visit_i64_atomic_rmw_xchg(I64AtomicRmwXchg), visit_i32_atomic_rmw8_xchg_u(I32AtomicRmw8XchgU), visit_i32_atomic_rmw16_xchg_u(I32AtomicRmw16XchgU),
+        visit_i64_atomic_rmw8_xchg_u(I64AtomicRmw8XchgU), visit_i64_atomic_rmw16_xchg_u(I64AtomicRmw16XchgU), visit_i64_atomic_rmw32_xchg_u(I64AtomicRmw32XchgU), visit_i32_atomic_rmw_cmpxchg(I32AtomicRmwCmpxchg),
+        visit_i64_atomic_rmw_cmpxchg(I64AtomicRmwCmpxchg), visit_i32_atomic_rmw8_cmpxchg_u(I32AtomicRmw8CmpxchgU), visit_i32_atomic_rmw16_cmpxchg_u(I32AtomicRmw16CmpxchgU), visit_i64_atomic_rmw8_cmpxchg_u(I64AtomicRmw8CmpxchgU),
+        visit_i64_atomic_rmw16_cmpxchg_u(I64AtomicRmw16CmpxchgU), visit_i64_atomic_rmw32_cmpxchg_u(I64AtomicRmw32CmpxchgU)
+    }
+
+    fn visit_atomic_fence(&mut self) -> Self::Output {
+        self.instructions.push(AtomicInstruction::AtomicFence.into());
+    }
+
     fn visit_typed_select(&mut self, ty: wasmparser::ValType) -> Self::Output {
         self.instructions.push(match ty {
             wasmparser::ValType::I32 => Instruction::Select32,
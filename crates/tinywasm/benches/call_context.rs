@@ -0,0 +1,27 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use tinywasm::{Module, Store};
+
+const WAT: &str =
+    r#"(module (func (export "add") (param i32 i32) (result i32) (i32.add (local.get 0) (local.get 1))))"#;
+const CALLS: i32 = 1_000_000;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let module = Module::parse_bytes(&wat::parse_str(WAT).expect("wat")).expect("parse");
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, None).expect("instantiate");
+    let add = instance.exported_func::<(i32, i32), i32>(&store, "add").expect("exported_func");
+
+    let mut group = c.benchmark_group("call_add_1m");
+    group.sample_size(10);
+    group.bench_function("func_handle", |b| {
+        b.iter(|| (0..CALLS).try_fold(0, |acc, i| add.call(&mut store, (acc, i))).expect("call"))
+    });
+    group.bench_function("call_context", |b| {
+        let mut ctx = store.new_call_context();
+        b.iter(|| (0..CALLS).try_fold(0, |acc, i| ctx.call_typed(&mut store, &add, (acc, i))).expect("call"))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -1,7 +1,8 @@
+use alloc::boxed::Box;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::{fmt::Display, ops::ControlFlow};
-use tinywasm_types::FuncType;
+use tinywasm_types::{FuncAddr, FuncType, ValType};
 
 #[cfg(feature = "parser")]
 pub use tinywasm_parser::ParseError;
@@ -27,6 +28,17 @@ pub enum Error {
         expected: FuncType,
         /// The actual value
         actual: Vec<tinywasm_types::WasmValue>,
+        /// Where the returned values differ from the expected type
+        mismatch: HostFnReturnMismatch,
+    },
+
+    /// The values passed to a function or received from it don't match the expected types,
+    /// e.g. when calling a [`FuncHandle`](crate::FuncHandle) or converting values for a typed host function
+    HostFnArgMismatch {
+        /// The expected types
+        expected: Box<[ValType]>,
+        /// The types of the actual values
+        actual: Box<[ValType]>,
     },
 
     /// An invalid label type was encountered
@@ -35,15 +47,84 @@ pub enum Error {
     /// The store is not the one that the module instance was instantiated in
     InvalidStore,
 
+    /// A string read from memory is not valid UTF-8
+    InvalidUtf8(core::str::Utf8Error),
+
+    /// A host function failed with an error of its own, see [`Error::host`]
+    Host(Box<dyn core::error::Error + Send + Sync>),
+
     #[cfg(feature = "std")]
     /// An I/O error occurred
     Io(crate::std::io::Error),
 
+    #[cfg(feature = "wasi")]
+    /// The module called `proc_exit` with the given exit code, see [`crate::wasi`]
+    ProcExit(i32),
+
     #[cfg(feature = "parser")]
     /// A parsing error occurred
     ParseError(ParseError),
 }
 
+impl Error {
+    /// Wrap an error returned by a host function
+    ///
+    /// The error ends the call like any other error and is returned from it unchanged,
+    /// use [`Error::downcast_ref`] or [`Error::downcast`] to get it back.
+    pub fn host(err: impl core::error::Error + Send + Sync + 'static) -> Self {
+        Self::Host(Box::new(err))
+    }
+
+    /// Get a reference to the error wrapped with [`Error::host`], if it is of type `E`
+    pub fn downcast_ref<E: core::error::Error + 'static>(&self) -> Option<&E> {
+        match self {
+            Self::Host(err) => err.downcast_ref(),
+            _ => None,
+        }
+    }
+
+    /// Get back the error wrapped with [`Error::host`], or `self` if it isn't a host error of type `E`
+    pub fn downcast<E: core::error::Error + 'static>(self) -> Result<E, Self> {
+        match self {
+            Self::Host(err) => err.downcast().map(|err| *err).map_err(Self::Host),
+            err => Err(err),
+        }
+    }
+}
+
+/// The way the results of a host function differ from its declared type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostFnReturnMismatch {
+    /// The wrong number of values was returned
+    Arity {
+        /// The number of declared results
+        expected: usize,
+        /// The number of returned values
+        actual: usize,
+    },
+
+    /// The value at `index` has the wrong type
+    Type {
+        /// The index of the first mismatched value
+        index: usize,
+        /// The declared type
+        expected: ValType,
+        /// The type of the returned value
+        actual: ValType,
+    },
+}
+
+impl Display for HostFnReturnMismatch {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Arity { expected, actual } => write!(f, "expected {expected} results, got {actual}"),
+            Self::Type { index, expected, actual } => {
+                write!(f, "result {index} has type {actual:?}, expected {expected:?}")
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 /// Errors that can occur when linking a WebAssembly module
 pub enum LinkingError {
@@ -62,6 +143,9 @@ pub enum LinkingError {
         /// The import name
         name: String,
     },
+
+    /// More than one import failed to link, in the order the module declares them
+    Multiple(Vec<LinkingError>),
 }
 
 impl LinkingError {
@@ -112,7 +196,43 @@ pub enum Trap {
     IntegerOverflow,
 
     /// Call stack overflow
-    CallStackOverflow,
+    CallStackOverflow {
+        /// The call depth that was exceeded
+        max_depth: usize,
+        /// Whether the interpreter's internal limit was hit instead of the
+        /// one set with [`Store::set_max_call_depth`](crate::Store::set_max_call_depth)
+        internal: bool,
+    },
+
+    /// The store ran out of fuel, see [`Store::set_fuel`](crate::Store::set_fuel)
+    OutOfFuel,
+
+    /// A `memory.grow` exceeded the budget of the current call,
+    /// see [`FuncHandle::call_with_memory_budget`](crate::FuncHandle::call_with_memory_budget)
+    MemoryBudgetExceeded {
+        /// The number of bytes the memory would have grown by
+        requested: usize,
+        /// The number of bytes left in the budget
+        remaining: usize,
+    },
+
+    /// A memory access that requires natural alignment, such as an atomic access, used a misaligned address
+    UnalignedAccess {
+        /// The effective address of the access
+        addr: usize,
+        /// The alignment in bytes the address has to be a multiple of
+        required_align: usize,
+    },
+
+    /// `i31.get_s` or `i31.get_u` was called on a null reference
+    NullI31Reference,
+
+    /// `memory.atomic.wait32` or `memory.atomic.wait64` was used on a memory that isn't declared `shared`
+    NotSharedMemory,
+
+    /// `memory.atomic.wait32` or `memory.atomic.wait64` would wait forever on the expected value,
+    /// since wasm only runs on a single thread and nothing could ever wake it up
+    Deadlock,
 
     /// An undefined element was encountered
     UndefinedElement {
@@ -133,22 +253,78 @@ pub enum Trap {
         /// The actual type
         actual: FuncType,
     },
+
+    /// `table.copy` between tables of different element types, only possible in modules that weren't validated
+    TableTypeMismatch {
+        /// The element type of the destination table
+        expected: ValType,
+        /// The element type of the source table
+        actual: ValType,
+    },
+
+    /// A trap with the wasm call stack at the time it occurred,
+    /// only created when [`Store::capture_backtraces`](crate::Store::capture_backtraces) is enabled
+    WithBacktrace {
+        /// The original trap
+        trap: Box<Trap>,
+        /// See [`Trap::backtrace`]
+        backtrace: Vec<(FuncAddr, usize)>,
+    },
 }
 
 impl Trap {
+    /// Get the wasm call stack at the time of the trap, innermost frame first
+    ///
+    /// Each frame is a function address and the index of the instruction that was executing
+    /// (for callers, the call instruction) in the function's compiled instructions.
+    /// Use [`Store::func_name`](crate::Store::func_name) to resolve function names.
+    pub fn backtrace(&self) -> Option<&[(FuncAddr, usize)]> {
+        match self {
+            Self::WithBacktrace { backtrace, .. } => Some(backtrace),
+            _ => None,
+        }
+    }
+
+    /// Get the trap without its backtrace
+    pub fn inner(&self) -> &Trap {
+        match self {
+            Self::WithBacktrace { trap, .. } => trap,
+            trap => trap,
+        }
+    }
+
+    /// Append frames to the backtrace, traps passing through host functions collect the frames of every call
+    pub(crate) fn with_backtrace(self, frames: impl Iterator<Item = (FuncAddr, usize)>) -> Self {
+        match self {
+            Self::WithBacktrace { trap, mut backtrace } => {
+                backtrace.extend(frames);
+                Self::WithBacktrace { trap, backtrace }
+            }
+            trap => Self::WithBacktrace { trap: Box::new(trap), backtrace: frames.collect() },
+        }
+    }
+
     /// Get the message of the trap
     pub fn message(&self) -> &'static str {
         match self {
+            Self::WithBacktrace { trap, .. } => trap.message(),
             Self::Unreachable => "unreachable",
             Self::MemoryOutOfBounds { .. } => "out of bounds memory access",
             Self::TableOutOfBounds { .. } => "out of bounds table access",
             Self::DivisionByZero => "integer divide by zero",
             Self::InvalidConversionToInt => "invalid conversion to integer",
             Self::IntegerOverflow => "integer overflow",
-            Self::CallStackOverflow => "call stack exhausted",
+            Self::CallStackOverflow { .. } => "call stack exhausted",
+            Self::OutOfFuel => "out of fuel",
+            Self::MemoryBudgetExceeded { .. } => "memory budget exceeded",
+            Self::UnalignedAccess { .. } => "unaligned atomic",
+            Self::NullI31Reference => "null i31 reference",
+            Self::NotSharedMemory => "expected shared memory",
+            Self::Deadlock => "deadlock",
             Self::UndefinedElement { .. } => "undefined element",
             Self::UninitializedElement { .. } => "uninitialized element",
             Self::IndirectCallTypeMismatch { .. } => "indirect call type mismatch",
+            Self::TableTypeMismatch { .. } => "table type mismatch",
         }
     }
 }
@@ -159,6 +335,7 @@ impl LinkingError {
         match self {
             Self::UnknownImport { .. } => "unknown import",
             Self::IncompatibleImportType { .. } => "incompatible import type",
+            Self::Multiple(_) => "multiple linking errors",
         }
     }
 }
@@ -179,20 +356,28 @@ impl Display for Error {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             #[cfg(feature = "parser")]
-            Self::ParseError(err) => write!(f, "error parsing module: {err:?}"),
+            Self::ParseError(err) => write!(f, "error parsing module: {err}"),
 
             #[cfg(feature = "std")]
             Self::Io(err) => write!(f, "I/O error: {err}"),
 
+            #[cfg(feature = "wasi")]
+            Self::ProcExit(code) => write!(f, "process exited with code {code}"),
+
             Self::Trap(trap) => write!(f, "trap: {trap}"),
             Self::Linker(err) => write!(f, "linking error: {err}"),
             Self::InvalidLabelType => write!(f, "invalid label type"),
             Self::Other(message) => write!(f, "unknown error: {message}"),
             Self::UnsupportedFeature(feature) => write!(f, "unsupported feature: {feature}"),
-            Self::InvalidHostFnReturn { expected, actual } => {
-                write!(f, "invalid host function return: expected={expected:?}, actual={actual:?}")
+            Self::InvalidHostFnReturn { expected, actual, mismatch } => {
+                write!(f, "invalid host function return: {mismatch} (expected={expected:?}, actual={actual:?})")
+            }
+            Self::HostFnArgMismatch { expected, actual } => {
+                write!(f, "argument type mismatch: expected {expected:?}, got {actual:?}")
             }
             Self::InvalidStore => write!(f, "invalid store"),
+            Self::InvalidUtf8(err) => write!(f, "invalid UTF-8 string: {err}"),
+            Self::Host(err) => write!(f, "host error: {err}"),
         }
     }
 }
@@ -204,6 +389,10 @@ impl Display for LinkingError {
             Self::IncompatibleImportType { module, name } => {
                 write!(f, "incompatible import type: {}.{}", module, name)
             }
+            Self::Multiple(errors) => {
+                write!(f, "{} linking errors", errors.len())?;
+                errors.iter().try_for_each(|err| write!(f, "\n  {err}"))
+            }
         }
     }
 }
@@ -211,6 +400,7 @@ impl Display for LinkingError {
 impl Display for Trap {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
+            Self::WithBacktrace { trap, backtrace } => write!(f, "{trap} (backtrace: {backtrace:?})"),
             Self::Unreachable => write!(f, "unreachable"),
             Self::MemoryOutOfBounds { offset, len, max } => {
                 write!(f, "out of bounds memory access: offset={offset}, len={len}, max={max}")
@@ -221,7 +411,22 @@ impl Display for Trap {
             Self::DivisionByZero => write!(f, "integer divide by zero"),
             Self::InvalidConversionToInt => write!(f, "invalid conversion to integer"),
             Self::IntegerOverflow => write!(f, "integer overflow"),
-            Self::CallStackOverflow => write!(f, "call stack exhausted"),
+            Self::CallStackOverflow { max_depth, internal: false } => {
+                write!(f, "call stack exhausted: max_depth={max_depth}")
+            }
+            Self::CallStackOverflow { max_depth, internal: true } => {
+                write!(f, "call stack exhausted: max_depth={max_depth} (internal interpreter limit)")
+            }
+            Self::OutOfFuel => write!(f, "out of fuel"),
+            Self::MemoryBudgetExceeded { requested, remaining } => {
+                write!(f, "memory budget exceeded: requested={requested}, remaining={remaining}")
+            }
+            Self::UnalignedAccess { addr, required_align } => {
+                write!(f, "unaligned atomic: addr={addr}, required_align={required_align}")
+            }
+            Self::NullI31Reference => write!(f, "null i31 reference"),
+            Self::NotSharedMemory => write!(f, "expected shared memory"),
+            Self::Deadlock => write!(f, "deadlock: atomic wait can never be woken up"),
             Self::UndefinedElement { index } => write!(f, "undefined element: index={index}"),
             Self::UninitializedElement { index } => {
                 write!(f, "uninitialized element: index={index}")
@@ -229,11 +434,21 @@ impl Display for Trap {
             Self::IndirectCallTypeMismatch { expected, actual } => {
                 write!(f, "indirect call type mismatch: expected={expected:?}, actual={actual:?}")
             }
+            Self::TableTypeMismatch { expected, actual } => {
+                write!(f, "table type mismatch: expected={expected:?}, actual={actual:?}")
+            }
         }
     }
 }
 
-impl core::error::Error for Error {}
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Host(err) => Some(&**err),
+            _ => None,
+        }
+    }
+}
 
 #[cfg(feature = "parser")]
 impl From<tinywasm_parser::ParseError> for Error {
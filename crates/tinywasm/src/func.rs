@@ -1,26 +1,55 @@
 use crate::interpreter::stack::{CallFrame, Stack};
-use crate::{log, unlikely, Function};
+use crate::{unlikely, Function};
 use crate::{Error, FuncContext, Result, Store};
 use alloc::{boxed::Box, format, string::String, string::ToString, vec, vec::Vec};
-use tinywasm_types::{ExternRef, FuncRef, FuncType, ModuleInstanceAddr, ValType, WasmValue};
+use tinywasm_types::{ExternRef, FuncAddr, FuncRef, FuncType, I31Ref, ModuleInstanceAddr, ValType, WasmValue};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 /// A function handle
+///
+/// Handles are resolved once and can be called any number of times, e.g. in a hot loop
+/// with [`Store::call`]. They stay valid until the store is [reset](Store::reset), after which
+/// calling them returns [`Error::InvalidStore`]; calling a function of a [dropped instance](Store::drop_instance)
+/// returns an error instead of panicking.
 pub struct FuncHandle {
     pub(crate) module_addr: ModuleInstanceAddr,
     pub(crate) addr: u32,
+    pub(crate) generation: u32,
     pub(crate) ty: FuncType,
 
     /// The name of the function, if it has one
+    ///
+    /// Taken from the `name` custom section, or the export name used to look up the function.
     pub name: Option<String>,
 }
 
 impl FuncHandle {
+    /// Get the address of the function in the store
+    pub fn addr(&self) -> FuncAddr {
+        self.addr
+    }
+
+    /// Get the type of the function
+    pub fn ty(&self) -> &FuncType {
+        &self.ty
+    }
+
     /// Call a function (Invocation)
     ///
     /// See <https://webassembly.github.io/spec/core/exec/modules.html#invocation>
     #[inline]
     pub fn call(&self, store: &mut Store, params: &[WasmValue]) -> Result<Vec<WasmValue>> {
+        self.call_with_stack(store, params, &mut None)
+    }
+
+    /// Call a function, reusing the allocations of `stack` if it was used before
+    #[inline]
+    fn call_with_stack(
+        &self,
+        store: &mut Store,
+        params: &[WasmValue],
+        stack: &mut Option<Stack>,
+    ) -> Result<Vec<WasmValue>> {
         // Comments are ordered by the steps in the spec
         // In this implementation, some steps are combined and ordered differently for performance reasons
 
@@ -28,26 +57,21 @@ impl FuncHandle {
         let func_ty = &self.ty;
 
         // 4. If the length of the provided argument values is different from the number of expected arguments, then fail
-        if unlikely(func_ty.params.len() != params.len()) {
-            return Err(Error::Other(format!(
-                "param count mismatch: expected {}, got {}",
-                func_ty.params.len(),
-                params.len()
-            )));
+        // 5. For each value type and the corresponding value, check if types match
+        if unlikely(!func_ty.params.iter().copied().eq(params.iter().map(WasmValue::val_type))) {
+            return Err(arg_mismatch(func_ty.params.clone(), params));
         }
 
-        // 5. For each value type and the corresponding value, check if types match
-        if !(func_ty.params.iter().zip(params).enumerate().all(|(_i, (ty, param))| {
-            if ty != &param.val_type() {
-                log::error!("param type mismatch at index {}: expected {:?}, got {:?}", _i, ty, param);
-                false
-            } else {
-                true
-            }
-        })) {
-            return Err(Error::Other("Type mismatch".into()));
+        if unlikely(store.deterministic && store.fuel.is_none()) {
+            return Err(Error::Other("deterministic mode requires fuel, see `Store::set_fuel`".to_string()));
         }
 
+        // handles from before a reset (or from another store) could point at unrelated functions
+        if unlikely(self.generation != store.generation() || self.addr as usize >= store.data.funcs.len()) {
+            return Err(Error::InvalidStore);
+        }
+
+        store.count_call(self.addr);
         let func_inst = store.get_func(self.addr);
         let wasm_func = match &func_inst.func {
             Function::Host(host_func) => {
@@ -59,15 +83,21 @@ impl FuncHandle {
         };
 
         // 6. Let f be the dummy frame
-        let call_frame = CallFrame::new(wasm_func.clone(), func_inst.owner, params, 0);
+        let call_frame = CallFrame::new(wasm_func.clone(), self.addr, func_inst.owner, params, 0);
 
         // 7. Push the frame f to the call stack
         // & 8. Push the values to the stack (Not needed since the call frame owns the values)
-        let mut stack = Stack::new(call_frame);
+        let stack = match stack {
+            Some(stack) => {
+                stack.reset(call_frame, store.max_call_depth());
+                stack
+            }
+            None => stack.insert(Stack::new(call_frame, store.max_call_depth())),
+        };
 
         // 9. Invoke the function instance
         let runtime = store.runtime();
-        runtime.exec(store, &mut stack)?;
+        runtime.exec(store, stack)?;
 
         // Once the function returns:
         // let result_m = func_ty.results.len();
@@ -81,6 +111,28 @@ impl FuncHandle {
         // The values are returned as the results of the invocation.
         Ok(res)
     }
+
+    /// Call a function, allowing `memory.grow` to allocate at most `bytes` of new linear memory
+    ///
+    /// Growing any memory beyond the remaining budget traps with [`crate::Trap::MemoryBudgetExceeded`].
+    /// The budget only applies to this call (including nested calls through host functions),
+    /// the memory limits of the store are unaffected.
+    pub fn call_with_memory_budget(
+        &self,
+        store: &mut Store,
+        params: &[WasmValue],
+        bytes: usize,
+    ) -> Result<Vec<WasmValue>> {
+        // a nested call can't exceed the budget of the call it's part of
+        let outer = store.memory_budget;
+        let budget = outer.map_or(bytes, |outer| outer.min(bytes));
+
+        store.memory_budget = Some(budget);
+        let res = self.call(store, params);
+        let used = budget - store.memory_budget.unwrap_or(budget);
+        store.memory_budget = outer.map(|outer| outer - used);
+        res
+    }
 }
 
 #[derive(Debug)]
@@ -91,16 +143,58 @@ pub struct FuncHandleTyped<P, R> {
     pub(crate) marker: core::marker::PhantomData<(P, R)>,
 }
 
+impl<P, R> Clone for FuncHandleTyped<P, R> {
+    fn clone(&self) -> Self {
+        Self { func: self.func.clone(), marker: core::marker::PhantomData }
+    }
+}
+
+/// Types that can be passed as the params of a typed function or returned as its results
 pub trait IntoWasmValueTuple {
+    /// Convert into a list of wasm values
     fn into_wasm_value_tuple(self) -> Vec<WasmValue>;
 }
 
+/// Types that can be created from the params or results of a typed function
 pub trait FromWasmValueTuple {
+    /// Convert from a list of wasm values
     fn from_wasm_value_tuple(values: &[WasmValue]) -> Result<Self>
     where
         Self: Sized;
 }
 
+/// A reusable context for calling functions
+///
+/// Every call through [`FuncHandle::call`] allocates a new value stack. A call context keeps its
+/// stack around between calls, which can make a big difference when calling small functions many
+/// times. Created with [`Store::new_call_context`], it can be used with any function and store.
+#[derive(Debug)]
+pub struct CallContext {
+    stack: Option<Stack>, // allocated on the first call
+}
+
+impl CallContext {
+    pub(crate) fn new() -> Self {
+        Self { stack: None }
+    }
+
+    /// Call a function, see [`FuncHandle::call`]
+    pub fn call(&mut self, store: &mut Store, handle: &FuncHandle, params: &[WasmValue]) -> Result<Vec<WasmValue>> {
+        handle.call_with_stack(store, params, &mut self.stack)
+    }
+
+    /// Call a typed function, see [`FuncHandleTyped::call`]
+    pub fn call_typed<P: IntoWasmValueTuple, R: FromWasmValueTuple>(
+        &mut self,
+        store: &mut Store,
+        handle: &FuncHandleTyped<P, R>,
+        params: P,
+    ) -> Result<R> {
+        let result = self.call(store, &handle.func, &params.into_wasm_value_tuple())?;
+        R::from_wasm_value_tuple(&result)
+    }
+}
+
 impl<P: IntoWasmValueTuple, R: FromWasmValueTuple> FuncHandleTyped<P, R> {
     /// Call a typed function
     pub fn call(&self, store: &mut Store, params: P) -> Result<R> {
@@ -113,6 +207,12 @@ impl<P: IntoWasmValueTuple, R: FromWasmValueTuple> FuncHandleTyped<P, R> {
         // Convert the Vec<WasmValue> back to R
         R::from_wasm_value_tuple(&result)
     }
+
+    /// Call a typed function with a memory budget, see [`FuncHandle::call_with_memory_budget`]
+    pub fn call_with_memory_budget(&self, store: &mut Store, params: P, bytes: usize) -> Result<R> {
+        let result = self.func.call_with_memory_budget(store, &params.into_wasm_value_tuple(), bytes)?;
+        R::from_wasm_value_tuple(&result)
+    }
 }
 
 macro_rules! impl_into_wasm_value_tuple {
@@ -146,21 +246,18 @@ macro_rules! impl_from_wasm_value_tuple {
     ($($T:ident),*) => {
         impl<$($T),*> FromWasmValueTuple for ($($T,)*)
         where
-            $($T: TryFrom<WasmValue, Error = ()>),*
+            $($T: TryFrom<WasmValue, Error = ()> + ToValType),*
         {
             #[inline]
             fn from_wasm_value_tuple(values: &[WasmValue]) -> Result<Self> {
                 #[allow(unused_variables, unused_mut)]
                 let mut iter = values.iter();
+                #[allow(unused_variables)]
+                let mismatch = || arg_mismatch(Box::new([$($T::to_val_type(),)*]), values);
 
                 Ok((
                     $(
-                        $T::try_from(
-                            *iter.next()
-                            .ok_or(Error::Other("Not enough values in WasmValue vector".to_string()))?
-                        )
-                        .map_err(|e| Error::Other(format!("FromWasmValueTuple: Could not convert WasmValue to expected type: {:?}", e,
-                    )))?,
+                        $T::try_from(*iter.next().ok_or_else(mismatch)?).map_err(|_| mismatch())?,
                     )*
                 ))
             }
@@ -173,25 +270,62 @@ macro_rules! impl_from_wasm_value_tuple_single {
         impl FromWasmValueTuple for $T {
             #[inline]
             fn from_wasm_value_tuple(values: &[WasmValue]) -> Result<Self> {
-                #[allow(unused_variables, unused_mut)]
-                let mut iter = values.iter();
-                $T::try_from(*iter.next().ok_or(Error::Other("Not enough values in WasmValue vector".to_string()))?)
-                    .map_err(|e| {
-                        Error::Other(format!(
-                            "FromWasmValueTupleSingle: Could not convert WasmValue to expected type: {:?}",
-                            e
-                        ))
-                    })
+                let mismatch = || arg_mismatch(Box::new([$T::to_val_type()]), values);
+                $T::try_from(*values.first().ok_or_else(mismatch)?).map_err(|_| mismatch())
             }
         }
     };
 }
 
+#[cold]
+fn arg_mismatch(expected: Box<[ValType]>, values: &[WasmValue]) -> Error {
+    Error::HostFnArgMismatch { expected, actual: values.iter().map(WasmValue::val_type).collect() }
+}
+
+/// Convert a list of wasm values into an array of native values
+///
+/// Fails if the number of values isn't exactly `N` or if any value has the wrong type.
+///
+/// ## Example
+/// ```rust
+/// use tinywasm::types::WasmValue;
+///
+/// let values = [WasmValue::F64(1.0), WasmValue::F64(2.0)];
+/// assert_eq!(tinywasm::try_into_array::<f64, 2>(&values).unwrap(), [1.0, 2.0]);
+/// assert!(tinywasm::try_into_array::<i32, 2>(&values).is_err());
+/// assert!(tinywasm::try_into_array::<f64, 3>(&values).is_err());
+/// ```
+pub fn try_into_array<T, const N: usize>(values: &[WasmValue]) -> Result<[T; N]>
+where
+    T: TryFrom<WasmValue, Error = ()>,
+{
+    if values.len() != N {
+        return Err(Error::Other(format!("try_into_array: expected {N} values, got {}", values.len())));
+    }
+
+    let converted = values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            T::try_from(*value).map_err(|_| {
+                Error::Other(format!("try_into_array: could not convert value {i} ({value:?}) to expected type"))
+            })
+        })
+        .collect::<Result<Vec<T>>>()?;
+
+    // the length was checked above
+    converted.try_into().map_err(|_| Error::Other("try_into_array: length mismatch".to_string()))
+}
+
+/// The value types of a typed function's params or results
 pub trait ValTypesFromTuple {
+    /// Get the value types
     fn val_types() -> Box<[ValType]>;
 }
 
+/// Types that map to a single wasm value type
 pub trait ToValType {
+    /// Get the value type
     fn to_val_type() -> ValType;
 }
 
@@ -219,6 +353,20 @@ impl ToValType for f64 {
     }
 }
 
+macro_rules! impl_to_val_type {
+    ($($t:ty => $ty:ident),*) => {
+        $(
+            impl ToValType for $t {
+                fn to_val_type() -> ValType {
+                    ValType::$ty
+                }
+            }
+        )*
+    };
+}
+
+impl_to_val_type! { bool => I32, u8 => I32, u16 => I32, u32 => I32, u64 => I64, u128 => V128 }
+
 impl ToValType for FuncRef {
     fn to_val_type() -> ValType {
         ValType::RefFunc
@@ -231,6 +379,12 @@ impl ToValType for ExternRef {
     }
 }
 
+impl ToValType for I31Ref {
+    fn to_val_type() -> ValType {
+        ValType::RefI31
+    }
+}
+
 macro_rules! impl_val_types_from_tuple {
     ($($t:ident),+) => {
         impl<$($t),+> ValTypesFromTuple for ($($t,)+)
@@ -265,6 +419,12 @@ impl_from_wasm_value_tuple_single!(f32);
 impl_from_wasm_value_tuple_single!(f64);
 impl_from_wasm_value_tuple_single!(FuncRef);
 impl_from_wasm_value_tuple_single!(ExternRef);
+impl_from_wasm_value_tuple_single!(bool);
+impl_from_wasm_value_tuple_single!(u8);
+impl_from_wasm_value_tuple_single!(u16);
+impl_from_wasm_value_tuple_single!(u32);
+impl_from_wasm_value_tuple_single!(u64);
+impl_from_wasm_value_tuple_single!(u128);
 
 impl_into_wasm_value_tuple_single!(i32);
 impl_into_wasm_value_tuple_single!(i64);
@@ -272,6 +432,12 @@ impl_into_wasm_value_tuple_single!(f32);
 impl_into_wasm_value_tuple_single!(f64);
 impl_into_wasm_value_tuple_single!(FuncRef);
 impl_into_wasm_value_tuple_single!(ExternRef);
+impl_into_wasm_value_tuple_single!(bool);
+impl_into_wasm_value_tuple_single!(u8);
+impl_into_wasm_value_tuple_single!(u16);
+impl_into_wasm_value_tuple_single!(u32);
+impl_into_wasm_value_tuple_single!(u64);
+impl_into_wasm_value_tuple_single!(u128);
 
 impl_val_types_from_tuple!(T1);
 impl_val_types_from_tuple!(T1, T2);
@@ -279,6 +445,12 @@ impl_val_types_from_tuple!(T1, T2, T3);
 impl_val_types_from_tuple!(T1, T2, T3, T4);
 impl_val_types_from_tuple!(T1, T2, T3, T4, T5);
 impl_val_types_from_tuple!(T1, T2, T3, T4, T5, T6);
+impl_val_types_from_tuple!(T1, T2, T3, T4, T5, T6, T7);
+impl_val_types_from_tuple!(T1, T2, T3, T4, T5, T6, T7, T8);
+impl_val_types_from_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
+impl_val_types_from_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+impl_val_types_from_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+impl_val_types_from_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
 
 impl_from_wasm_value_tuple!();
 impl_from_wasm_value_tuple!(T1);
@@ -287,6 +459,12 @@ impl_from_wasm_value_tuple!(T1, T2, T3);
 impl_from_wasm_value_tuple!(T1, T2, T3, T4);
 impl_from_wasm_value_tuple!(T1, T2, T3, T4, T5);
 impl_from_wasm_value_tuple!(T1, T2, T3, T4, T5, T6);
+impl_from_wasm_value_tuple!(T1, T2, T3, T4, T5, T6, T7);
+impl_from_wasm_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8);
+impl_from_wasm_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
+impl_from_wasm_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+impl_from_wasm_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+impl_from_wasm_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
 
 impl_into_wasm_value_tuple!();
 impl_into_wasm_value_tuple!(T1);
@@ -295,3 +473,73 @@ impl_into_wasm_value_tuple!(T1, T2, T3);
 impl_into_wasm_value_tuple!(T1, T2, T3, T4);
 impl_into_wasm_value_tuple!(T1, T2, T3, T4, T5);
 impl_into_wasm_value_tuple!(T1, T2, T3, T4, T5, T6);
+impl_into_wasm_value_tuple!(T1, T2, T3, T4, T5, T6, T7);
+impl_into_wasm_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8);
+impl_into_wasm_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
+impl_into_wasm_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+impl_into_wasm_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+impl_into_wasm_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
+
+/// Implement the conversions needed to use newtypes around wasm values in typed functions
+///
+/// The newtype has to be a tuple struct with a single field of a type that already
+/// converts to and from [`WasmValue`], e.g. `i32` or [`ExternRef`].
+///
+/// ## Example
+/// ```rust
+/// # fn main() -> tinywasm::Result<()> {
+/// use tinywasm::{Extern, FuncContext};
+///
+/// #[derive(Debug, Clone, Copy)]
+/// struct FileId(i32);
+///
+/// #[derive(Debug, Clone, Copy)]
+/// struct FileSize(i64);
+///
+/// tinywasm::wasm_newtype!(FileId(i32), FileSize(i64));
+///
+/// let file_size = Extern::typed_func(|_: FuncContext<'_>, id: FileId| Ok(FileSize(id.0 as i64 * 1024)));
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! wasm_newtype {
+    ($($name:ident($inner:ty)),+ $(,)?) => {$(
+        impl ::core::convert::From<$name> for $crate::types::WasmValue {
+            #[inline]
+            fn from(value: $name) -> Self {
+                value.0.into()
+            }
+        }
+
+        impl ::core::convert::TryFrom<$crate::types::WasmValue> for $name {
+            type Error = ();
+
+            #[inline]
+            fn try_from(value: $crate::types::WasmValue) -> ::core::result::Result<Self, ()> {
+                <$inner>::try_from(value).map($name)
+            }
+        }
+
+        impl $crate::ToValType for $name {
+            #[inline]
+            fn to_val_type() -> $crate::types::ValType {
+                <$inner as $crate::ToValType>::to_val_type()
+            }
+        }
+
+        impl $crate::FromWasmValueTuple for $name {
+            #[inline]
+            fn from_wasm_value_tuple(values: &[$crate::types::WasmValue]) -> $crate::Result<Self> {
+                <$inner as $crate::FromWasmValueTuple>::from_wasm_value_tuple(values).map($name)
+            }
+        }
+
+        impl $crate::IntoWasmValueTuple for $name {
+            #[inline]
+            fn into_wasm_value_tuple(self) -> $crate::__private::Vec<$crate::types::WasmValue> {
+                <$inner as $crate::IntoWasmValueTuple>::into_wasm_value_tuple(self.0)
+            }
+        }
+    )+};
+}
@@ -1,12 +1,14 @@
 use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
+use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::{String, ToString};
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::fmt::Debug;
 
 use crate::func::{FromWasmValueTuple, IntoWasmValueTuple, ValTypesFromTuple};
-use crate::{log, LinkingError, MemoryRef, MemoryRefMut, Result};
+use crate::{log, HostFnReturnMismatch, LinkingError, MemoryRef, MemoryRefMut, Result};
 use tinywasm_types::*;
 
 /// The internal representation of a function
@@ -16,7 +18,7 @@ pub enum Function {
     Host(Rc<HostFunction>),
 
     /// A pointer to a WebAssembly function
-    Wasm(Rc<WasmFunction>),
+    Wasm(Arc<WasmFunction>),
 }
 
 impl Function {
@@ -66,6 +68,15 @@ impl FuncContext<'_> {
         self.store
     }
 
+    /// Create an error that makes the calling wasm code trap
+    ///
+    /// Returning `Err(FuncContext::trap(trap))` (or just `Err(trap.into())`) from a host function
+    /// unwinds the wasm call stack exactly like a trap raised by a wasm instruction, and the
+    /// caller gets [`crate::Error::Trap`].
+    pub fn trap(trap: crate::Trap) -> crate::Error {
+        crate::Error::Trap(trap)
+    }
+
     /// Get a reference to the module instance
     pub fn module(&self) -> crate::ModuleInstance {
         self.store.get_module_instance_raw(self.module_addr)
@@ -80,6 +91,65 @@ impl FuncContext<'_> {
     pub fn exported_memory_mut(&mut self, name: &str) -> Result<MemoryRefMut<'_>> {
         self.module().exported_memory_mut(self.store, name)
     }
+
+    /// Get a memory exported by the calling module
+    ///
+    /// Unlike [`FuncContext::module`], this doesn't panic if the calling instance is gone:
+    /// a dropped instance, a missing export or an export that isn't a memory are all returned as errors.
+    pub fn memory(&mut self, name: &str) -> Result<MemoryRef<'_>> {
+        let Some(module) = self.store.get_module_instance(self.module_addr).cloned() else {
+            return Err(crate::Error::Other(format!("module instance {} not found", self.module_addr)));
+        };
+        module.exported_memory(self.store, name)
+    }
+
+    /// Get mutable references to two different exported memories
+    ///
+    /// See [`crate::ModuleInstance::exported_memories_mut`]
+    pub fn exported_memories_mut(&mut self, name: &str, name2: &str) -> Result<(MemoryRefMut<'_>, MemoryRefMut<'_>)> {
+        self.module().exported_memories_mut(self.store, name, name2)
+    }
+
+    /// Copy a buffer into the calling module's default memory
+    ///
+    /// The guest is expected to provide a region of at least `data.len()` bytes starting at `ptr`.
+    /// If the region is out of bounds, a [`crate::Trap::MemoryOutOfBounds`] is returned and the memory is left untouched.
+    ///
+    /// ## Example
+    /// ```rust
+    /// # fn main() -> tinywasm::Result<()> {
+    /// use tinywasm::{Extern, FuncContext, Imports, Module, Store};
+    /// let wasm = wat::parse_str(r#"
+    ///     (module
+    ///         (import "host" "greeting" (func $greeting (param i32 i32) (result i32)))
+    ///         (memory (export "memory") 1)
+    ///         (func (export "run") (result i32) (call $greeting (i32.const 16) (i32.const 32)))
+    ///     )
+    /// "#).unwrap();
+    ///
+    /// let mut imports = Imports::new();
+    /// let greeting = Extern::typed_func(|mut ctx: FuncContext<'_>, (ptr, cap): (i32, i32)| {
+    ///     let msg = b"hello from the host";
+    ///     let len = msg.len().min(cap as usize);
+    ///     ctx.write_to_guest(ptr as u32, &msg[..len])?;
+    ///     Ok(len as i32)
+    /// });
+    /// imports.define("host", "greeting", greeting)?;
+    ///
+    /// let mut store = Store::default();
+    /// let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, Some(imports))?;
+    /// let len = instance.exported_func::<(), i32>(&store, "run")?.call(&mut store, ())?;
+    /// assert_eq!(instance.exported_memory(&mut store, "memory")?.load(16, len as usize)?, b"hello from the host");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_to_guest(&mut self, ptr: u32, data: &[u8]) -> Result<()> {
+        let module = self.module();
+        if module.0.mem_addrs.is_empty() {
+            return Err(crate::Error::Other("module has no memory to write to".to_string()));
+        }
+        module.memory_mut(self.store, 0)?.store(ptr as usize, data.len(), data)
+    }
 }
 
 impl Debug for HostFunction {
@@ -114,6 +184,14 @@ pub enum Extern {
         ty: MemoryType,
     },
 
+    /// A memory that starts out with the given contents, see [`Extern::memory_with_data`]
+    MemoryWithData {
+        /// Defines the type of the memory, including its limits and the type of its pages.
+        ty: MemoryType,
+        /// The initial contents of the memory, starting at offset 0.
+        data: Box<[u8]>,
+    },
+
     /// A function
     Function(Function),
 }
@@ -134,7 +212,21 @@ impl Extern {
         Self::Memory { ty }
     }
 
+    /// Create a new memory import that starts out with `data` copied to offset 0
+    ///
+    /// Returns a [`crate::Trap::MemoryOutOfBounds`] if `data` doesn't fit into the initial size of the memory.
+    pub fn memory_with_data(ty: MemoryType, data: &[u8]) -> Result<Self> {
+        let max = usize::try_from(ty.initial_size()).unwrap_or(usize::MAX);
+        if data.len() > max {
+            return Err(crate::Trap::MemoryOutOfBounds { offset: 0, len: data.len(), max }.into());
+        }
+        Ok(Self::MemoryWithData { ty, data: Box::from(data) })
+    }
+
     /// Create a new function import
+    ///
+    /// Errors returned by `func` end the call, see [`FuncContext::trap`] for raising a wasm trap
+    /// and [`crate::Error::host`] for passing on errors of your own.
     pub fn func(
         ty: &tinywasm_types::FuncType,
         func: impl Fn(FuncContext<'_>, &[WasmValue]) -> Result<Vec<WasmValue>> + 'static,
@@ -145,15 +237,16 @@ impl Extern {
             let result = func(ctx, args)?;
 
             if result.len() != _ty.results.len() {
-                return Err(crate::Error::InvalidHostFnReturn { expected: _ty.clone(), actual: result });
+                let mismatch = HostFnReturnMismatch::Arity { expected: _ty.results.len(), actual: result.len() };
+                return Err(crate::Error::InvalidHostFnReturn { expected: _ty, actual: result, mismatch });
             };
 
-            result.iter().zip(_ty.results.iter()).try_for_each(|(val, ty)| {
-                if val.val_type() != *ty {
-                    return Err(crate::Error::InvalidHostFnReturn { expected: _ty.clone(), actual: result.clone() });
-                }
-                Ok(())
-            })?;
+            let wrong_type = result.iter().zip(_ty.results.iter()).position(|(val, ty)| val.val_type() != *ty);
+            if let Some(index) = wrong_type {
+                let (expected, actual) = (_ty.results[index], result[index].val_type());
+                let mismatch = HostFnReturnMismatch::Type { index, expected, actual };
+                return Err(crate::Error::InvalidHostFnReturn { expected: _ty, actual: result, mismatch });
+            }
 
             Ok(result)
         };
@@ -161,7 +254,23 @@ impl Extern {
         Self::Function(Function::Host(Rc::new(HostFunction { func: Box::new(inner_func), ty: ty.clone() })))
     }
 
+    /// Create a new function import that also receives its own type
+    ///
+    /// Works like [`Extern::func`], but passes `ty` to every call of `func`, so that a single
+    /// generic implementation (e.g. a syscall dispatcher) can check and route the arguments
+    /// of several imports with different signatures.
+    pub fn func_with_type(
+        ty: &tinywasm_types::FuncType,
+        func: impl Fn(FuncContext<'_>, &tinywasm_types::FuncType, &[WasmValue]) -> Result<Vec<WasmValue>> + 'static,
+    ) -> Self {
+        let func_ty = ty.clone();
+        Self::func(ty, move |ctx, args| func(ctx, &func_ty, args))
+    }
+
     /// Create a new typed function import
+    ///
+    /// Errors returned by `func` end the call, see [`FuncContext::trap`] for raising a wasm trap
+    /// and [`crate::Error::host`] for passing on errors of your own.
     pub fn typed_func<P, R>(func: impl Fn(FuncContext<'_>, P) -> Result<R> + 'static) -> Self
     where
         P: FromWasmValueTuple + ValTypesFromTuple,
@@ -177,12 +286,31 @@ impl Extern {
         Self::Function(Function::Host(Rc::new(HostFunction { func: Box::new(inner_func), ty })))
     }
 
+    /// Create a new function import that receives a slice of the caller's default memory
+    ///
+    /// The guest calls it with a `(ptr: i32, len: i32)` pair, which is resolved to the bytes
+    /// in that range before `func` runs. Both values are treated as unsigned, and a range that
+    /// doesn't fit into the memory traps with [`crate::Trap::MemoryOutOfBounds`].
+    pub fn func_with_memory<R>(func: impl Fn(&FuncContext<'_>, &[u8]) -> Result<R> + 'static) -> Self
+    where
+        R: IntoWasmValueTuple + ValTypesFromTuple + Debug,
+    {
+        Self::typed_func(move |ctx: FuncContext<'_>, (ptr, len): (i32, i32)| {
+            let module = ctx.module();
+            if module.0.mem_addrs.is_empty() {
+                return Err(crate::Error::Other("module has no memory to read from".to_string()));
+            }
+            let memory = module.memory(ctx.store, 0)?;
+            func(&ctx, memory.load(ptr as u32 as usize, len as u32 as usize)?)
+        })
+    }
+
     /// Get the kind of the external value
     pub fn kind(&self) -> ExternalKind {
         match self {
             Self::Global { .. } => ExternalKind::Global,
             Self::Table { .. } => ExternalKind::Table,
-            Self::Memory { .. } => ExternalKind::Memory,
+            Self::Memory { .. } | Self::MemoryWithData { .. } => ExternalKind::Memory,
             Self::Function { .. } => ExternalKind::Func,
         }
     }
@@ -195,13 +323,25 @@ pub struct ExternName {
     name: String,
 }
 
+impl ExternName {
+    /// The module the import is taken from
+    pub fn module(&self) -> &str {
+        &self.module
+    }
+
+    /// The name of the import within its module
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
 impl From<&Import> for ExternName {
     fn from(import: &Import) -> Self {
         Self { module: import.module.to_string(), name: import.name.to_string() }
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Default)]
 /// Imports for a module instance
 ///
 /// This is used to link a module instance to its imports
@@ -239,7 +379,22 @@ impl From<&Import> for ExternName {
 #[derive(Clone)]
 pub struct Imports {
     values: BTreeMap<ExternName, Extern>,
+    aliases: BTreeMap<ExternName, ExternVal>,
     modules: BTreeMap<String, ModuleInstanceAddr>,
+    wrappers: Vec<ExternWrapper>,
+}
+
+type ExternWrapper = Rc<dyn Fn(&ExternName, Extern) -> Extern>;
+
+impl Debug for Imports {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Imports")
+            .field("values", &self.values)
+            .field("aliases", &self.aliases)
+            .field("modules", &self.modules)
+            .field("wrappers", &self.wrappers.len())
+            .finish()
+    }
 }
 
 pub(crate) enum ResolvedExtern<S, V> {
@@ -263,13 +418,27 @@ impl ResolvedImports {
 impl Imports {
     /// Create a new empty import set
     pub fn new() -> Self {
-        Imports { values: BTreeMap::new(), modules: BTreeMap::new() }
+        Imports { values: BTreeMap::new(), aliases: BTreeMap::new(), modules: BTreeMap::new(), wrappers: Vec::new() }
     }
 
     /// Merge two import sets
     pub fn merge(mut self, other: Self) -> Self {
         self.values.extend(other.values);
+        self.aliases.extend(other.aliases);
         self.modules.extend(other.modules);
+        self.wrappers.extend(other.wrappers);
+        self
+    }
+
+    /// Wrap every defined import
+    ///
+    /// The wrapper is applied to each value added with [`Imports::define`] when it is linked during
+    /// instantiation, e.g. to decorate all host functions with logging or timing.
+    /// Wrapped values still have to match the type the module expects, so the wrapper should keep the
+    /// function's signature intact. Imports resolved through [`Imports::link_module`] are not wrapped.
+    /// Multiple wrappers are applied in the order they were added.
+    pub fn wrap_all(&mut self, wrapper: impl Fn(&ExternName, Extern) -> Extern + 'static) -> &mut Self {
+        self.wrappers.push(Rc::new(wrapper));
         self
     }
 
@@ -281,6 +450,31 @@ impl Imports {
         Ok(self)
     }
 
+    /// Make exports of an instantiated module available under new names
+    ///
+    /// Each mapping is an `(export_name, new_module, new_name)` triple, so unlike [`Imports::link_module`]
+    /// only the listed exports are linked, and they can be moved to a different namespace.
+    /// Fails if the module instance or one of the exports doesn't exist.
+    /// Like linked modules, aliased exports are not affected by [`Imports::wrap_all`].
+    pub fn alias(
+        &mut self,
+        store: &crate::Store,
+        src_module: ModuleInstanceAddr,
+        mappings: &[(&str, &str, &str)],
+    ) -> Result<&mut Self> {
+        let Some(instance) = store.get_module_instance(src_module) else {
+            return Err(crate::Error::Other(format!("module instance {src_module} not found")));
+        };
+
+        for (export_name, module, name) in mappings {
+            let Some(val) = instance.export_addr(export_name) else {
+                return Err(crate::Error::Other(format!("Export not found: {export_name}")));
+            };
+            self.aliases.insert(ExternName { module: module.to_string(), name: name.to_string() }, val);
+        }
+        Ok(self)
+    }
+
     /// Define an import
     pub fn define(&mut self, module: &str, name: &str, value: Extern) -> Result<&mut Self> {
         self.values.insert(ExternName { module: module.to_string(), name: name.to_string() }, value);
@@ -294,7 +488,11 @@ impl Imports {
     ) -> Option<ResolvedExtern<ExternVal, Extern>> {
         let name = ExternName::from(import);
         if let Some(v) = self.values.get(&name) {
-            return Some(ResolvedExtern::Extern(v.clone()));
+            let v = self.wrappers.iter().fold(v.clone(), |v, wrap| wrap(&name, v));
+            return Some(ResolvedExtern::Extern(v));
+        }
+        if let Some(val) = self.aliases.get(&name) {
+            return Some(ResolvedExtern::Store(val.clone()));
         }
         if let Some(addr) = self.modules.get(&name.module) {
             let instance = store.get_module_instance(*addr)?;
@@ -312,47 +510,51 @@ impl Imports {
         Ok(())
     }
 
-    fn compare_table_types(import: &Import, expected: &TableType, actual: &TableType) -> Result<()> {
-        Self::compare_types(import, &actual.element_type, &expected.element_type)?;
+    /// Check the limits of a provided table or memory against the ones an import requires
+    ///
+    /// See <https://webassembly.github.io/spec/core/valid/types.html#match-limits>
+    fn compare_limits(import: &Import, provided: (u64, Option<u64>), required: (u64, Option<u64>)) -> Result<()> {
+        let max_matches = match (provided.1, required.1) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(provided_max), Some(required_max)) => provided_max <= required_max,
+        };
 
-        if actual.size_initial > expected.size_initial {
+        if provided.0 < required.0 || !max_matches {
+            log::error!("failed to link import {}, expected limits {:?}, got {:?}", import.name, required, provided);
             return Err(LinkingError::incompatible_import_type(import).into());
         }
-
-        match (expected.size_max, actual.size_max) {
-            (None, Some(_)) => return Err(LinkingError::incompatible_import_type(import).into()),
-            (Some(expected_max), Some(actual_max)) if actual_max < expected_max => {
-                return Err(LinkingError::incompatible_import_type(import).into())
-            }
-            _ => {}
-        }
-
         Ok(())
     }
 
+    fn compare_table_types(import: &Import, provided: &TableType, required: &TableType) -> Result<()> {
+        Self::compare_types(import, &provided.element_type, &required.element_type)?;
+        Self::compare_limits(
+            import,
+            (provided.size_initial.into(), provided.size_max.map(u64::from)),
+            (required.size_initial.into(), required.size_max.map(u64::from)),
+        )
+    }
+
     fn compare_memory_types(
         import: &Import,
-        expected: &MemoryType,
-        actual: &MemoryType,
+        provided: &MemoryType,
+        required: &MemoryType,
         real_size: Option<usize>,
     ) -> Result<()> {
-        Self::compare_types(import, &expected.arch(), &actual.arch())?;
-
-        if actual.page_count_initial() > expected.page_count_initial()
-            && real_size.map_or(true, |size| actual.page_count_initial() > size as u64)
-        {
-            return Err(LinkingError::incompatible_import_type(import).into());
-        }
-
-        if expected.page_size() != actual.page_size() {
-            return Err(LinkingError::incompatible_import_type(import).into());
-        }
+        Self::compare_types(import, &provided.arch(), &required.arch())?;
 
-        if expected.page_count_max() > actual.page_count_max() {
+        if provided.page_size() != required.page_size() || provided.is_shared() != required.is_shared() {
             return Err(LinkingError::incompatible_import_type(import).into());
         }
 
-        Ok(())
+        // memories that are already in the store may have grown past their initial size
+        let provided_min = real_size.map_or(provided.page_count_initial(), |size| size as u64);
+        Self::compare_limits(
+            import,
+            (provided_min, provided.page_count_max_declared()),
+            (required.page_count_initial(), required.page_count_max_declared()),
+        )
     }
 
     pub(crate) fn link(
@@ -363,80 +565,108 @@ impl Imports {
     ) -> Result<ResolvedImports> {
         let mut imports = ResolvedImports::new();
 
+        // keep going after a failed import, so all of them can be reported at once
+        let mut errors = Vec::new();
         for import in &module.0.imports {
-            let val = self.take(store, import).ok_or_else(|| LinkingError::unknown_import(import))?;
-
-            match val {
-                // A link to something that needs to be added to the store
-                ResolvedExtern::Extern(ex) => match (ex, &import.kind) {
-                    (Extern::Global { ty, val }, ImportKind::Global(import_ty)) => {
-                        Self::compare_types(import, &ty, import_ty)?;
-                        imports.globals.push(store.add_global(ty, val.into(), idx)?);
+            match self.link_import(store, module, idx, import, &mut imports) {
+                Err(crate::Error::Linker(err)) => errors.push(err),
+                res => res?,
+            }
+        }
+
+        match errors.len() {
+            0 => Ok(imports),
+            1 => Err(errors.remove(0).into()),
+            _ => Err(LinkingError::Multiple(errors).into()),
+        }
+    }
+
+    fn link_import(
+        &mut self,
+        store: &mut crate::Store,
+        module: &crate::Module,
+        idx: ModuleInstanceAddr,
+        import: &Import,
+        imports: &mut ResolvedImports,
+    ) -> Result<()> {
+        let val = self.take(store, import).ok_or_else(|| LinkingError::unknown_import(import))?;
+
+        match val {
+            // A link to something that needs to be added to the store
+            ResolvedExtern::Extern(ex) => match (ex, &import.kind) {
+                (Extern::Global { ty, val }, ImportKind::Global(import_ty)) => {
+                    Self::compare_types(import, &ty, import_ty)?;
+                    imports.globals.push(store.add_global(ty, val.into(), idx)?);
+                }
+                (Extern::Table { ty, .. }, ImportKind::Table(import_ty)) => {
+                    Self::compare_table_types(import, &ty, import_ty)?;
+                    imports.tables.push(store.add_table(ty, idx)?);
+                }
+                (Extern::Memory { ty }, ImportKind::Memory(import_ty)) => {
+                    Self::compare_memory_types(import, &ty, import_ty, None)?;
+                    imports.memories.push(store.add_mem(ty, idx)?);
+                }
+                (Extern::MemoryWithData { ty, data }, ImportKind::Memory(import_ty)) => {
+                    Self::compare_memory_types(import, &ty, import_ty, None)?;
+                    let addr = store.add_mem(ty, idx)?;
+                    store.get_mem_mut(addr).store(0, data.len(), &data)?;
+                    imports.memories.push(addr);
+                }
+                (Extern::Function(extern_func), ImportKind::Function(ty)) => {
+                    let import_func_type = module
+                        .0
+                        .func_types
+                        .get(*ty as usize)
+                        .ok_or_else(|| LinkingError::incompatible_import_type(import))?;
+
+                    Self::compare_types(import, extern_func.ty(), import_func_type)?;
+                    imports.funcs.push(store.add_func(extern_func, idx)?);
+                }
+                _ => return Err(LinkingError::incompatible_import_type(import).into()),
+            },
+
+            // A link to something already in the store
+            ResolvedExtern::Store(val) => {
+                // check if the kind matches
+                if val.kind() != (&import.kind).into() {
+                    return Err(LinkingError::incompatible_import_type(import).into());
+                }
+
+                match (val, &import.kind) {
+                    (ExternVal::Global(global_addr), ImportKind::Global(ty)) => {
+                        let global = store.get_global(global_addr);
+                        Self::compare_types(import, &global.ty, ty)?;
+                        imports.globals.push(global_addr);
                     }
-                    (Extern::Table { ty, .. }, ImportKind::Table(import_ty)) => {
-                        Self::compare_table_types(import, &ty, import_ty)?;
-                        imports.tables.push(store.add_table(ty, idx)?);
+                    (ExternVal::Table(table_addr), ImportKind::Table(ty)) => {
+                        let table = store.get_table(table_addr);
+                        let mut kind = table.kind.clone();
+                        kind.size_initial = table.size() as u32;
+                        Self::compare_table_types(import, &kind, ty)?;
+                        imports.tables.push(table_addr);
                     }
-                    (Extern::Memory { ty }, ImportKind::Memory(import_ty)) => {
-                        Self::compare_memory_types(import, &ty, import_ty, None)?;
-                        imports.memories.push(store.add_mem(ty, idx)?);
+                    (ExternVal::Memory(memory_addr), ImportKind::Memory(ty)) => {
+                        let mem = store.get_mem(memory_addr);
+                        let (size, kind) = { (mem.page_count, mem.kind) };
+                        Self::compare_memory_types(import, &kind, ty, Some(size))?;
+                        imports.memories.push(memory_addr);
                     }
-                    (Extern::Function(extern_func), ImportKind::Function(ty)) => {
+                    (ExternVal::Func(func_addr), ImportKind::Function(ty)) => {
+                        let func = store.get_func(func_addr);
                         let import_func_type = module
                             .0
                             .func_types
                             .get(*ty as usize)
                             .ok_or_else(|| LinkingError::incompatible_import_type(import))?;
 
-                        Self::compare_types(import, extern_func.ty(), import_func_type)?;
-                        imports.funcs.push(store.add_func(extern_func, idx)?);
+                        Self::compare_types(import, func.func.ty(), import_func_type)?;
+                        imports.funcs.push(func_addr);
                     }
                     _ => return Err(LinkingError::incompatible_import_type(import).into()),
-                },
-
-                // A link to something already in the store
-                ResolvedExtern::Store(val) => {
-                    // check if the kind matches
-                    if val.kind() != (&import.kind).into() {
-                        return Err(LinkingError::incompatible_import_type(import).into());
-                    }
-
-                    match (val, &import.kind) {
-                        (ExternVal::Global(global_addr), ImportKind::Global(ty)) => {
-                            let global = store.get_global(global_addr);
-                            Self::compare_types(import, &global.ty, ty)?;
-                            imports.globals.push(global_addr);
-                        }
-                        (ExternVal::Table(table_addr), ImportKind::Table(ty)) => {
-                            let table = store.get_table(table_addr);
-                            let mut kind = table.kind.clone();
-                            kind.size_initial = table.size() as u32;
-                            Self::compare_table_types(import, &kind, ty)?;
-                            imports.tables.push(table_addr);
-                        }
-                        (ExternVal::Memory(memory_addr), ImportKind::Memory(ty)) => {
-                            let mem = store.get_mem(memory_addr);
-                            let (size, kind) = { (mem.page_count, mem.kind) };
-                            Self::compare_memory_types(import, &kind, ty, Some(size))?;
-                            imports.memories.push(memory_addr);
-                        }
-                        (ExternVal::Func(func_addr), ImportKind::Function(ty)) => {
-                            let func = store.get_func(func_addr);
-                            let import_func_type = module
-                                .0
-                                .func_types
-                                .get(*ty as usize)
-                                .ok_or_else(|| LinkingError::incompatible_import_type(import))?;
-
-                            Self::compare_types(import, func.func.ty(), import_func_type)?;
-                            imports.funcs.push(func_addr);
-                        }
-                        _ => return Err(LinkingError::incompatible_import_type(import).into()),
-                    }
                 }
             }
         }
 
-        Ok(imports)
+        Ok(())
     }
 }
@@ -2,7 +2,9 @@ use alloc::{boxed::Box, format, rc::Rc, string::ToString};
 use tinywasm_types::*;
 
 use crate::func::{FromWasmValueTuple, IntoWasmValueTuple};
-use crate::{Error, FuncHandle, FuncHandleTyped, Imports, MemoryRef, MemoryRefMut, Module, Result, Store};
+use crate::{
+    Error, FuncHandle, FuncHandleTyped, Imports, MemoryRef, MemoryRefMut, Module, Result, Store, TableRef, TableRefMut,
+};
 
 /// An instanciated WebAssembly module
 ///
@@ -18,6 +20,7 @@ pub(crate) struct ModuleInstanceInner {
     pub(crate) failed_to_instantiate: bool,
 
     pub(crate) store_id: usize,
+    pub(crate) store_generation: u32,
     pub(crate) idx: ModuleInstanceAddr,
 
     pub(crate) types: Box<[FuncType]>,
@@ -32,6 +35,7 @@ pub(crate) struct ModuleInstanceInner {
     pub(crate) func_start: Option<FuncAddr>,
     pub(crate) imports: Box<[Import]>,
     pub(crate) exports: Box<[Export]>,
+    pub(crate) func_names: Box<[(FuncAddr, Box<str>)]>,
 }
 
 impl ModuleInstance {
@@ -72,11 +76,13 @@ impl ModuleInstance {
         let global_addrs = store.init_globals(addrs.globals, module.0.globals.into(), &addrs.funcs, idx)?;
         let (elem_addrs, elem_trapped) =
             store.init_elements(&addrs.tables, &addrs.funcs, &global_addrs, &module.0.elements, idx)?;
-        let (data_addrs, data_trapped) = store.init_datas(&addrs.memories, module.0.data.into(), idx)?;
+        let (data_addrs, data_trapped) =
+            store.init_datas(&addrs.memories, &global_addrs, &addrs.funcs, module.0.data.into(), idx)?;
 
         let instance = ModuleInstanceInner {
             failed_to_instantiate: elem_trapped.is_some() || data_trapped.is_some(),
             store_id: store.id(),
+            store_generation: store.generation(),
             idx,
             types: module.0.func_types,
             func_addrs: addrs.funcs.into_boxed_slice(),
@@ -88,6 +94,7 @@ impl ModuleInstance {
             func_start: module.0.start_func,
             imports: module.0.imports,
             exports: module.0.exports,
+            func_names: module.0.func_names,
         };
 
         let instance = ModuleInstance::new(instance);
@@ -101,15 +108,68 @@ impl ModuleInstance {
 
     /// Get a export by name
     pub fn export_addr(&self, name: &str) -> Option<ExternVal> {
-        let exports = self.0.exports.iter().find(|e| e.name == name.into())?;
-        let addr = match exports.kind {
-            ExternalKind::Func => self.0.func_addrs.get(exports.index as usize)?,
-            ExternalKind::Table => self.0.table_addrs.get(exports.index as usize)?,
-            ExternalKind::Memory => self.0.mem_addrs.get(exports.index as usize)?,
-            ExternalKind::Global => self.0.global_addrs.get(exports.index as usize)?,
+        let export = self.0.exports.iter().find(|e| e.name == name.into())?;
+        self.resolve_export(export)
+    }
+
+    /// Iterate over all exports of the module instance
+    ///
+    /// Yields each export's name together with its store address.
+    /// Use [`ExternVal::kind`] to filter for a specific kind of export.
+    pub fn exports(&self) -> impl Iterator<Item = (&str, ExternVal)> {
+        self.0.exports.iter().filter_map(|export| Some((&*export.name, self.resolve_export(export)?)))
+    }
+
+    fn resolve_export(&self, export: &Export) -> Option<ExternVal> {
+        let addr = match export.kind {
+            ExternalKind::Func => self.0.func_addrs.get(export.index as usize)?,
+            ExternalKind::Table => self.0.table_addrs.get(export.index as usize)?,
+            ExternalKind::Memory => self.0.mem_addrs.get(export.index as usize)?,
+            ExternalKind::Global => self.0.global_addrs.get(export.index as usize)?,
         };
 
-        Some(ExternVal::new(exports.kind, *addr))
+        Some(ExternVal::new(export.kind, *addr))
+    }
+
+    /// Check if the function at the given module-local index is imported
+    ///
+    /// Returns `false` for functions defined by the module and for indices that don't exist.
+    pub fn is_func_imported(&self, idx: FuncAddr) -> bool {
+        self.is_imported(ExternalKind::Func, idx)
+    }
+
+    /// Check if the table at the given module-local index is imported
+    ///
+    /// Returns `false` for tables defined by the module and for indices that don't exist.
+    pub fn is_table_imported(&self, idx: TableAddr) -> bool {
+        self.is_imported(ExternalKind::Table, idx)
+    }
+
+    /// Check if the memory at the given module-local index is imported
+    ///
+    /// Returns `false` for memories defined by the module and for indices that don't exist.
+    pub fn is_memory_imported(&self, idx: MemAddr) -> bool {
+        self.is_imported(ExternalKind::Memory, idx)
+    }
+
+    /// Check if the global at the given module-local index is imported
+    ///
+    /// Returns `false` for globals defined by the module and for indices that don't exist.
+    pub fn is_global_imported(&self, idx: GlobalAddr) -> bool {
+        self.is_imported(ExternalKind::Global, idx)
+    }
+
+    // imports always come first in each index space
+    fn is_imported(&self, kind: ExternalKind, idx: u32) -> bool {
+        self.0.imports.iter().filter(|import| ExternalKind::from(&import.kind) == kind).count() > idx as usize
+    }
+
+    // the name from the name section, or the export name if there is none
+    pub(crate) fn func_name(&self, idx: FuncAddr) -> Option<&str> {
+        match self.0.func_names.binary_search_by_key(&idx, |(i, _)| *i) {
+            Ok(pos) => Some(&self.0.func_names[pos].1),
+            Err(_) => self.0.exports.iter().find(|e| e.kind == ExternalKind::Func && e.index == idx).map(|e| &*e.name),
+        }
     }
 
     #[inline]
@@ -122,11 +182,6 @@ impl ModuleInstance {
         &self.0.types[addr as usize]
     }
 
-    #[inline]
-    pub(crate) fn func_addrs(&self) -> &[FuncAddr] {
-        &self.0.func_addrs
-    }
-
     // resolve a function address to the global store address
     #[inline]
     pub(crate) fn resolve_func_addr(&self, addr: FuncAddr) -> FuncAddr {
@@ -163,11 +218,17 @@ impl ModuleInstance {
         self.0.global_addrs[addr as usize]
     }
 
-    /// Get an exported function by name
-    pub fn exported_func_untyped(&self, store: &Store, name: &str) -> Result<FuncHandle> {
-        if self.0.store_id != store.id() {
+    // instances are only valid in the store they were created in, and only until it is reset
+    fn check_store(&self, store: &Store) -> Result<()> {
+        if self.0.store_id != store.id() || self.0.store_generation != store.generation() {
             return Err(Error::InvalidStore);
         }
+        Ok(())
+    }
+
+    /// Get an exported function by name
+    pub fn exported_func_untyped(&self, store: &Store, name: &str) -> Result<FuncHandle> {
+        self.check_store(store)?;
 
         let export = self.export_addr(name).ok_or_else(|| Error::Other(format!("Export not found: {name}")))?;
         let ExternVal::Func(func_addr) = export else {
@@ -175,7 +236,14 @@ impl ModuleInstance {
         };
 
         let ty = store.get_func(func_addr).func.ty();
-        Ok(FuncHandle { addr: func_addr, module_addr: self.id(), name: Some(name.to_string()), ty: ty.clone() })
+        let name = store.func_name(func_addr).unwrap_or(name).to_string();
+        Ok(FuncHandle {
+            addr: func_addr,
+            module_addr: self.id(),
+            generation: store.generation(),
+            name: Some(name),
+            ty: ty.clone(),
+        })
     }
 
     /// Get a typed exported function by name
@@ -188,34 +256,132 @@ impl ModuleInstance {
         Ok(FuncHandleTyped { func, marker: core::marker::PhantomData })
     }
 
+    /// Get the type of an exported function by name, without creating a handle for it
+    ///
+    /// Returns `None` if there is no function exported under `name` or the store doesn't belong to this instance.
+    pub fn func_type<'a>(&self, store: &'a Store, name: &str) -> Option<&'a FuncType> {
+        self.check_store(store).ok()?;
+
+        let ExternVal::Func(func_addr) = self.export_addr(name)? else { return None };
+        Some(store.get_func(func_addr).func.ty())
+    }
+
+    /// Get a reference to an exported function by name
+    ///
+    /// The reference can be passed to any module instance in the same store, e.g. as a `funcref` argument or table element.
+    pub fn func_ref(&self, store: &Store, name: &str) -> Result<FuncRef> {
+        let func = self.exported_func_untyped(store, name)?;
+        Ok(FuncRef::new(Some(func.addr)))
+    }
+
     /// Get an exported memory by name
     pub fn exported_memory<'a>(&self, store: &'a mut Store, name: &str) -> Result<MemoryRef<'a>> {
+        self.check_store(store)?;
+        // export addresses are already store addresses
+        Ok(MemoryRef(store.get_mem(self.exported_mem_addr(name)?)))
+    }
+
+    /// Get an exported memory by name
+    pub fn exported_memory_mut<'a>(&self, store: &'a mut Store, name: &str) -> Result<MemoryRefMut<'a>> {
+        self.check_store(store)?;
+        Ok(MemoryRefMut(store.get_mem_mut(self.exported_mem_addr(name)?)))
+    }
+
+    /// Get two distinct exported memories by name at the same time
+    ///
+    /// Fails if both names refer to the same memory.
+    pub fn exported_memories_mut<'a>(
+        &self,
+        store: &'a mut Store,
+        name: &str,
+        name2: &str,
+    ) -> Result<(MemoryRefMut<'a>, MemoryRefMut<'a>)> {
+        self.check_store(store)?;
+        let (addr, addr2) = (self.exported_mem_addr(name)?, self.exported_mem_addr(name2)?);
+        if addr == addr2 {
+            return Err(Error::Other(format!("Exports refer to the same memory: {name}, {name2}")));
+        }
+
+        let (mem, mem2) = store.get_mems_mut(addr, addr2)?;
+        Ok((MemoryRefMut(mem), MemoryRefMut(mem2)))
+    }
+
+    fn exported_mem_addr(&self, name: &str) -> Result<MemAddr> {
         let export = self.export_addr(name).ok_or_else(|| Error::Other(format!("Export not found: {name}")))?;
         let ExternVal::Memory(mem_addr) = export else {
-            return Err(Error::Other(format!("Export is not a memory: {}", name)));
+            return Err(Error::Other(format!("Export is not a memory: {name}")));
         };
+        Ok(mem_addr)
+    }
 
-        self.memory(store, mem_addr)
+    /// Get an exported table by name
+    pub fn exported_table<'a>(&self, store: &'a Store, name: &str) -> Result<TableRef<'a>> {
+        self.check_store(store)?;
+        Ok(TableRef(store.get_table(self.exported_table_addr(name)?)))
     }
 
-    /// Get an exported memory by name
-    pub fn exported_memory_mut<'a>(&self, store: &'a mut Store, name: &str) -> Result<MemoryRefMut<'a>> {
+    /// Get an exported table by name (mutable)
+    pub fn exported_table_mut<'a>(&self, store: &'a mut Store, name: &str) -> Result<TableRefMut<'a>> {
+        self.check_store(store)?;
+        let addr = self.exported_table_addr(name)?;
+        let func_count = store.data.funcs.len();
+        Ok(TableRefMut { table: store.get_table_mut(addr), func_count })
+    }
+
+    fn exported_table_addr(&self, name: &str) -> Result<TableAddr> {
         let export = self.export_addr(name).ok_or_else(|| Error::Other(format!("Export not found: {name}")))?;
-        let ExternVal::Memory(mem_addr) = export else {
-            return Err(Error::Other(format!("Export is not a memory: {}", name)));
+        let ExternVal::Table(table_addr) = export else {
+            return Err(Error::Other(format!("Export is not a table: {name}")));
         };
+        Ok(table_addr)
+    }
 
-        self.memory_mut(store, mem_addr)
+    /// Get the value of an exported global by name
+    pub fn exported_global(&self, store: &Store, name: &str) -> Result<WasmValue> {
+        self.check_store(store)?;
+        let global = store.get_global(self.exported_global_addr(name)?);
+        Ok(global.value.get().attach_type(global.ty.ty))
+    }
+
+    /// Set the value of an exported global by name
+    ///
+    /// Fails if the global is immutable or the value doesn't match the global's type.
+    pub fn set_exported_global(&self, store: &mut Store, name: &str, value: WasmValue) -> Result<()> {
+        self.check_store(store)?;
+        let global = store.get_global(self.exported_global_addr(name)?);
+        if !global.ty.mutable {
+            return Err(Error::Other(format!("Global is immutable: {name}")));
+        }
+        if global.ty.ty != value.val_type() {
+            return Err(Error::Other(format!(
+                "Global type mismatch: {name} expected {:?}, got {:?}",
+                global.ty.ty,
+                value.val_type()
+            )));
+        }
+
+        global.value.set(value.into());
+        Ok(())
+    }
+
+    fn exported_global_addr(&self, name: &str) -> Result<GlobalAddr> {
+        let export = self.export_addr(name).ok_or_else(|| Error::Other(format!("Export not found: {name}")))?;
+        let ExternVal::Global(global_addr) = export else {
+            return Err(Error::Other(format!("Export is not a global: {name}")));
+        };
+        Ok(global_addr)
     }
 
     /// Get a memory by address
     pub fn memory<'a>(&self, store: &'a Store, addr: MemAddr) -> Result<MemoryRef<'a>> {
+        self.check_store(store)?;
         let mem = store.get_mem(self.resolve_mem_addr(addr));
         Ok(MemoryRef(mem))
     }
 
     /// Get a memory by address (mutable)
     pub fn memory_mut<'a>(&self, store: &'a mut Store, addr: MemAddr) -> Result<MemoryRefMut<'a>> {
+        self.check_store(store)?;
         let mem = store.get_mem_mut(self.resolve_mem_addr(addr));
         Ok(MemoryRefMut(mem))
     }
@@ -227,9 +393,7 @@ impl ModuleInstance {
     ///
     /// See <https://webassembly.github.io/spec/core/syntax/modules.html#start-function>
     pub fn start_func(&self, store: &Store) -> Result<Option<FuncHandle>> {
-        if self.0.store_id != store.id() {
-            return Err(Error::InvalidStore);
-        }
+        self.check_store(store)?;
 
         let func_index = match self.0.func_start {
             Some(func_index) => func_index,
@@ -247,7 +411,14 @@ impl ModuleInstance {
         let func_inst = store.get_func(func_addr);
         let ty = func_inst.func.ty();
 
-        Ok(Some(FuncHandle { module_addr: self.id(), addr: func_addr, ty: ty.clone(), name: None }))
+        let name = store.func_name(func_addr).map(ToString::to_string);
+        Ok(Some(FuncHandle {
+            module_addr: self.id(),
+            addr: func_addr,
+            generation: store.generation(),
+            ty: ty.clone(),
+            name,
+        }))
     }
 
     /// Invoke the start function of the module
@@ -2,7 +2,7 @@
 #[allow(unused_imports)]
 use super::no_std_floats::NoStdFloatExt;
 
-use alloc::{format, rc::Rc, string::ToString};
+use alloc::{format, rc::Rc, string::ToString, sync::Arc};
 use core::ops::ControlFlow;
 use interpreter::simd::exec_next_simd;
 use interpreter::stack::CallFrame;
@@ -32,16 +32,45 @@ impl<'store, 'stack> Executor<'store, 'stack> {
         loop {
             if let ControlFlow::Break(res) = self.exec_next() {
                 return match res {
-                    Some(e) => Err(e),
+                    Some(e) => Err(self.attach_backtrace(e)),
                     None => Ok(()),
                 };
             }
         }
     }
 
+    /// Record the wasm call stack in traps, see [`Store::capture_backtraces`]
+    #[cold]
+    fn attach_backtrace(&self, err: Error) -> Error {
+        let Error::Trap(trap) = err else { return err };
+        if !self.store.capture_backtraces {
+            return Error::Trap(trap);
+        }
+
+        // suspended frames have already moved past their call instruction
+        let callers = self.stack.call_stack.frames().iter().rev();
+        let callers = callers.map(|cf| (cf.func_addr(), cf.instr_ptr().saturating_sub(1)));
+        let frames = core::iter::once((self.cf.func_addr(), self.cf.instr_ptr())).chain(callers);
+        trap.with_backtrace(frames).into()
+    }
+
     #[inline(always)]
     fn exec_next(&mut self) -> ControlFlow<Option<Error>> {
         use tinywasm_types::Instruction::*;
+
+        // every instruction costs one unit of fuel when metering is enabled
+        if let Some(fuel) = self.store.fuel.as_mut() {
+            if unlikely(*fuel == 0) {
+                return ControlFlow::Break(Some(Trap::OutOfFuel.into()));
+            }
+            *fuel -= 1;
+        }
+
+        #[cfg(feature = "trace")]
+        if let Some(hook) = self.store.trace_hook.as_mut() {
+            hook(self.cf.func_addr(), self.cf.instr_ptr(), self.cf.fetch_instr());
+        }
+
         match self.cf.fetch_instr() {
             Nop | BrLabel(_) | I32ReinterpretF32 | I64ReinterpretF64 | F32ReinterpretI32 | F64ReinterpretI64 => {}
             Unreachable => self.exec_unreachable()?,
@@ -56,8 +85,10 @@ impl<'store, 'stack> Executor<'store, 'stack> {
             Select128 => self.stack.values.select::<Value128>(),
             SelectRef => self.stack.values.select::<ValueRef>(),
 
-            Call(v) => return self.exec_call_direct(*v),
-            CallIndirect(ty, table) => return self.exec_call_indirect(*ty, *table),
+            Call(v) => return self.exec_call_direct::<false>(*v),
+            CallIndirect(ty, table) => return self.exec_call_indirect::<false>(*ty, *table),
+            ReturnCall(v) => return self.exec_call_direct::<true>(*v),
+            ReturnCallIndirect(ty, table) => return self.exec_call_indirect::<true>(*ty, *table),
 
             If(end, el) => self.exec_if(*end, *el, (StackHeight::default(), StackHeight::default())),
             IfWithType(ty, end, el) => self.exec_if(*end, *el, (StackHeight::default(), (*ty).into())),
@@ -91,21 +122,24 @@ impl<'store, 'stack> Executor<'store, 'stack> {
             LocalTeeRef(local_index) => self.exec_local_tee::<ValueRef>(*local_index),
 
             GlobalGet(global_index) => self.exec_global_get(*global_index),
-            GlobalSet32(global_index) => self.exec_global_set::<Value32>(*global_index),
-            GlobalSet64(global_index) => self.exec_global_set::<Value64>(*global_index),
-            GlobalSet128(global_index) => self.exec_global_set::<Value128>(*global_index),
-            GlobalSetRef(global_index) => self.exec_global_set::<ValueRef>(*global_index),
+            GlobalSet32(global_index) => self.exec_global_set::<Value32>(*global_index).to_cf()?,
+            GlobalSet64(global_index) => self.exec_global_set::<Value64>(*global_index).to_cf()?,
+            GlobalSet128(global_index) => self.exec_global_set::<Value128>(*global_index).to_cf()?,
+            GlobalSetRef(global_index) => self.exec_global_set::<ValueRef>(*global_index).to_cf()?,
 
             I32Const(val) => self.exec_const(*val),
             I64Const(val) => self.exec_const(*val),
             F32Const(val) => self.exec_const(*val),
             F64Const(val) => self.exec_const(*val),
-            RefFunc(func_idx) => self.exec_const::<ValueRef>(Some(*func_idx)),
+            RefFunc(func_idx) => self.exec_const::<ValueRef>(Some(self.module.resolve_func_addr(*func_idx))),
             RefNull(_) => self.exec_const::<ValueRef>(None),
             RefIsNull => self.exec_ref_is_null(),
+            RefI31 => self.stack.values.replace_top::<i32, ValueRef>(|v| Ok(I31Ref::new(v).get_u())).to_cf()?,
+            I31GetS => self.exec_i31_get(I31Ref::get_s).to_cf()?,
+            I31GetU => self.exec_i31_get(|r| r.get_u().map(|v| v as i32)).to_cf()?,
 
             MemorySize(addr) => self.exec_memory_size(*addr),
-            MemoryGrow(addr) => self.exec_memory_grow(*addr),
+            MemoryGrow(addr) => self.exec_memory_grow(*addr).to_cf()?,
 
             // Bulk memory operations
             MemoryCopy(from, to) => self.exec_memory_copy(*from, *to).to_cf()?,
@@ -115,30 +149,30 @@ impl<'store, 'stack> Executor<'store, 'stack> {
             ElemDrop(elem_index) => self.exec_elem_drop(*elem_index),
             TableCopy { from, to } => self.exec_table_copy(*from, *to).to_cf()?,
 
-            I32Store(m) => self.exec_mem_store::<i32, i32, 4>(m.mem_addr(), m.offset(), |v| v)?,
-            I64Store(m) => self.exec_mem_store::<i64, i64, 8>(m.mem_addr(), m.offset(), |v| v)?,
-            F32Store(m) => self.exec_mem_store::<f32, f32, 4>(m.mem_addr(), m.offset(), |v| v)?,
-            F64Store(m) => self.exec_mem_store::<f64, f64, 8>(m.mem_addr(), m.offset(), |v| v)?,
-            I32Store8(m) => self.exec_mem_store::<i32, i8, 1>(m.mem_addr(), m.offset(), |v| v as i8)?,
-            I32Store16(m) => self.exec_mem_store::<i32, i16, 2>(m.mem_addr(), m.offset(), |v| v as i16)?,
-            I64Store8(m) => self.exec_mem_store::<i64, i8, 1>(m.mem_addr(), m.offset(), |v| v as i8)?,
-            I64Store16(m) => self.exec_mem_store::<i64, i16, 2>(m.mem_addr(), m.offset(), |v| v as i16)?,
-            I64Store32(m) => self.exec_mem_store::<i64, i32, 4>(m.mem_addr(), m.offset(), |v| v as i32)?,
-
-            I32Load(m) => self.exec_mem_load::<i32, 4, _>(m.mem_addr(), m.offset(), |v| v)?,
-            I64Load(m) => self.exec_mem_load::<i64, 8, _>(m.mem_addr(), m.offset(), |v| v)?,
-            F32Load(m) => self.exec_mem_load::<f32, 4, _>(m.mem_addr(), m.offset(), |v| v)?,
-            F64Load(m) => self.exec_mem_load::<f64, 8, _>(m.mem_addr(), m.offset(), |v| v)?,
-            I32Load8S(m) => self.exec_mem_load::<i8, 1, _>(m.mem_addr(), m.offset(), |v| v as i32)?,
-            I32Load8U(m) => self.exec_mem_load::<u8, 1, _>(m.mem_addr(), m.offset(), |v| v as i32)?,
-            I32Load16S(m) => self.exec_mem_load::<i16, 2, _>(m.mem_addr(), m.offset(), |v| v as i32)?,
-            I32Load16U(m) => self.exec_mem_load::<u16, 2, _>(m.mem_addr(), m.offset(), |v| v as i32)?,
-            I64Load8S(m) => self.exec_mem_load::<i8, 1, _>(m.mem_addr(), m.offset(), |v| v as i64)?,
-            I64Load8U(m) => self.exec_mem_load::<u8, 1, _>(m.mem_addr(), m.offset(), |v| v as i64)?,
-            I64Load16S(m) => self.exec_mem_load::<i16, 2, _>(m.mem_addr(), m.offset(), |v| v as i64)?,
-            I64Load16U(m) => self.exec_mem_load::<u16, 2, _>(m.mem_addr(), m.offset(), |v| v as i64)?,
-            I64Load32S(m) => self.exec_mem_load::<i32, 4, _>(m.mem_addr(), m.offset(), |v| v as i64)?,
-            I64Load32U(m) => self.exec_mem_load::<u32, 4, _>(m.mem_addr(), m.offset(), |v| v as i64)?,
+            I32Store(m) => self.exec_mem_store::<i32, i32, 4, false>(m.mem_addr(), m.offset(), |v| v)?,
+            I64Store(m) => self.exec_mem_store::<i64, i64, 8, false>(m.mem_addr(), m.offset(), |v| v)?,
+            F32Store(m) => self.exec_mem_store::<f32, f32, 4, false>(m.mem_addr(), m.offset(), |v| v)?,
+            F64Store(m) => self.exec_mem_store::<f64, f64, 8, false>(m.mem_addr(), m.offset(), |v| v)?,
+            I32Store8(m) => self.exec_mem_store::<i32, i8, 1, false>(m.mem_addr(), m.offset(), |v| v as i8)?,
+            I32Store16(m) => self.exec_mem_store::<i32, i16, 2, false>(m.mem_addr(), m.offset(), |v| v as i16)?,
+            I64Store8(m) => self.exec_mem_store::<i64, i8, 1, false>(m.mem_addr(), m.offset(), |v| v as i8)?,
+            I64Store16(m) => self.exec_mem_store::<i64, i16, 2, false>(m.mem_addr(), m.offset(), |v| v as i16)?,
+            I64Store32(m) => self.exec_mem_store::<i64, i32, 4, false>(m.mem_addr(), m.offset(), |v| v as i32)?,
+
+            I32Load(m) => self.exec_mem_load::<i32, 4, _, false>(m.mem_addr(), m.offset(), |v| v)?,
+            I64Load(m) => self.exec_mem_load::<i64, 8, _, false>(m.mem_addr(), m.offset(), |v| v)?,
+            F32Load(m) => self.exec_mem_load::<f32, 4, _, false>(m.mem_addr(), m.offset(), |v| v)?,
+            F64Load(m) => self.exec_mem_load::<f64, 8, _, false>(m.mem_addr(), m.offset(), |v| v)?,
+            I32Load8S(m) => self.exec_mem_load::<i8, 1, _, false>(m.mem_addr(), m.offset(), |v| v as i32)?,
+            I32Load8U(m) => self.exec_mem_load::<u8, 1, _, false>(m.mem_addr(), m.offset(), |v| v as i32)?,
+            I32Load16S(m) => self.exec_mem_load::<i16, 2, _, false>(m.mem_addr(), m.offset(), |v| v as i32)?,
+            I32Load16U(m) => self.exec_mem_load::<u16, 2, _, false>(m.mem_addr(), m.offset(), |v| v as i32)?,
+            I64Load8S(m) => self.exec_mem_load::<i8, 1, _, false>(m.mem_addr(), m.offset(), |v| v as i64)?,
+            I64Load8U(m) => self.exec_mem_load::<u8, 1, _, false>(m.mem_addr(), m.offset(), |v| v as i64)?,
+            I64Load16S(m) => self.exec_mem_load::<i16, 2, _, false>(m.mem_addr(), m.offset(), |v| v as i64)?,
+            I64Load16U(m) => self.exec_mem_load::<u16, 2, _, false>(m.mem_addr(), m.offset(), |v| v as i64)?,
+            I64Load32S(m) => self.exec_mem_load::<i32, 4, _, false>(m.mem_addr(), m.offset(), |v| v as i64)?,
+            I64Load32U(m) => self.exec_mem_load::<u32, 4, _, false>(m.mem_addr(), m.offset(), |v| v as i64)?,
 
             I64Eqz => self.stack.values.replace_top::<i64, _>(|v| Ok(i32::from(v == 0))).to_cf()?,
             I32Eqz => self.stack.values.replace_top_same::<i32>(|v| Ok(i32::from(v == 0))).to_cf()?,
@@ -182,21 +216,21 @@ impl<'store, 'stack> Executor<'store, 'stack> {
 
             I32Add => self.stack.values.calculate_same::<i32>(|a, b| Ok(a.wrapping_add(b))).to_cf()?,
             I64Add => self.stack.values.calculate_same::<i64>(|a, b| Ok(a.wrapping_add(b))).to_cf()?,
-            F32Add => self.stack.values.calculate_same::<f32>(|a, b| Ok(a + b)).to_cf()?,
-            F64Add => self.stack.values.calculate_same::<f64>(|a, b| Ok(a + b)).to_cf()?,
+            F32Add => self.exec_float_binop::<f32>(|a, b| a + b).to_cf()?,
+            F64Add => self.exec_float_binop::<f64>(|a, b| a + b).to_cf()?,
 
             I32Sub => self.stack.values.calculate_same::<i32>(|a, b| Ok(a.wrapping_sub(b))).to_cf()?,
             I64Sub => self.stack.values.calculate_same::<i64>(|a, b| Ok(a.wrapping_sub(b))).to_cf()?,
-            F32Sub => self.stack.values.calculate_same::<f32>(|a, b| Ok(a - b)).to_cf()?,
-            F64Sub => self.stack.values.calculate_same::<f64>(|a, b| Ok(a - b)).to_cf()?,
+            F32Sub => self.exec_float_binop::<f32>(|a, b| a - b).to_cf()?,
+            F64Sub => self.exec_float_binop::<f64>(|a, b| a - b).to_cf()?,
 
-            F32Div => self.stack.values.calculate_same::<f32>(|a, b| Ok(a / b)).to_cf()?,
-            F64Div => self.stack.values.calculate_same::<f64>(|a, b| Ok(a / b)).to_cf()?,
+            F32Div => self.exec_float_binop::<f32>(|a, b| a / b).to_cf()?,
+            F64Div => self.exec_float_binop::<f64>(|a, b| a / b).to_cf()?,
 
             I32Mul => self.stack.values.calculate_same::<i32>(|a, b| Ok(a.wrapping_mul(b))).to_cf()?,
             I64Mul => self.stack.values.calculate_same::<i64>(|a, b| Ok(a.wrapping_mul(b))).to_cf()?,
-            F32Mul => self.stack.values.calculate_same::<f32>(|a, b| Ok(a * b)).to_cf()?,
-            F64Mul => self.stack.values.calculate_same::<f64>(|a, b| Ok(a * b)).to_cf()?,
+            F32Mul => self.exec_float_binop::<f32>(|a, b| a * b).to_cf()?,
+            F64Mul => self.exec_float_binop::<f64>(|a, b| a * b).to_cf()?,
 
             I32DivS => self.stack.values.calculate_same::<i32>(|a, b| a.wasm_checked_div(b)).to_cf()?,
             I64DivS => self.stack.values.calculate_same::<i64>(|a, b| a.wasm_checked_div(b)).to_cf()?,
@@ -249,27 +283,27 @@ impl<'store, 'stack> Executor<'store, 'stack> {
             I64ExtendI32S => self.stack.values.replace_top::<i32, _>(|v| Ok(v as i64)).to_cf()?,
             I32WrapI64 => self.stack.values.replace_top::<i64, _>(|v| Ok(v as i32)).to_cf()?,
 
-            F32DemoteF64 => self.stack.values.replace_top::<f64, _>(|v| Ok(v as f32)).to_cf()?,
-            F64PromoteF32 => self.stack.values.replace_top::<f32, _>(|v| Ok(v as f64)).to_cf()?,
+            F32DemoteF64 => self.exec_float_unop::<f64, f32>(|v| v as f32).to_cf()?,
+            F64PromoteF32 => self.exec_float_unop::<f32, f64>(|v| v as f64).to_cf()?,
 
             F32Abs => self.stack.values.replace_top_same::<f32>(|v| Ok(v.abs())).to_cf()?,
             F64Abs => self.stack.values.replace_top_same::<f64>(|v| Ok(v.abs())).to_cf()?,
             F32Neg => self.stack.values.replace_top_same::<f32>(|v| Ok(-v)).to_cf()?,
             F64Neg => self.stack.values.replace_top_same::<f64>(|v| Ok(-v)).to_cf()?,
-            F32Ceil => self.stack.values.replace_top_same::<f32>(|v| Ok(v.ceil())).to_cf()?,
-            F64Ceil => self.stack.values.replace_top_same::<f64>(|v| Ok(v.ceil())).to_cf()?,
-            F32Floor => self.stack.values.replace_top_same::<f32>(|v| Ok(v.floor())).to_cf()?,
-            F64Floor => self.stack.values.replace_top_same::<f64>(|v| Ok(v.floor())).to_cf()?,
-            F32Trunc => self.stack.values.replace_top_same::<f32>(|v| Ok(v.trunc())).to_cf()?,
-            F64Trunc => self.stack.values.replace_top_same::<f64>(|v| Ok(v.trunc())).to_cf()?,
-            F32Nearest => self.stack.values.replace_top_same::<f32>(|v| Ok(v.tw_nearest())).to_cf()?,
-            F64Nearest => self.stack.values.replace_top_same::<f64>(|v| Ok(v.tw_nearest())).to_cf()?,
-            F32Sqrt => self.stack.values.replace_top_same::<f32>(|v| Ok(v.sqrt())).to_cf()?,
-            F64Sqrt => self.stack.values.replace_top_same::<f64>(|v| Ok(v.sqrt())).to_cf()?,
-            F32Min => self.stack.values.calculate_same::<f32>(|a, b| Ok(a.tw_minimum(b))).to_cf()?,
-            F64Min => self.stack.values.calculate_same::<f64>(|a, b| Ok(a.tw_minimum(b))).to_cf()?,
-            F32Max => self.stack.values.calculate_same::<f32>(|a, b| Ok(a.tw_maximum(b))).to_cf()?,
-            F64Max => self.stack.values.calculate_same::<f64>(|a, b| Ok(a.tw_maximum(b))).to_cf()?,
+            F32Ceil => self.exec_float_unop::<f32, f32>(|v| v.ceil()).to_cf()?,
+            F64Ceil => self.exec_float_unop::<f64, f64>(|v| v.ceil()).to_cf()?,
+            F32Floor => self.exec_float_unop::<f32, f32>(|v| v.floor()).to_cf()?,
+            F64Floor => self.exec_float_unop::<f64, f64>(|v| v.floor()).to_cf()?,
+            F32Trunc => self.exec_float_unop::<f32, f32>(|v| v.trunc()).to_cf()?,
+            F64Trunc => self.exec_float_unop::<f64, f64>(|v| v.trunc()).to_cf()?,
+            F32Nearest => self.exec_float_unop::<f32, f32>(|v| v.tw_nearest()).to_cf()?,
+            F64Nearest => self.exec_float_unop::<f64, f64>(|v| v.tw_nearest()).to_cf()?,
+            F32Sqrt => self.exec_float_unop::<f32, f32>(|v| v.sqrt()).to_cf()?,
+            F64Sqrt => self.exec_float_unop::<f64, f64>(|v| v.sqrt()).to_cf()?,
+            F32Min => self.exec_float_binop::<f32>(|a, b| a.tw_minimum(b)).to_cf()?,
+            F64Min => self.exec_float_binop::<f64>(|a, b| a.tw_minimum(b)).to_cf()?,
+            F32Max => self.exec_float_binop::<f32>(|a, b| a.tw_maximum(b)).to_cf()?,
+            F64Max => self.exec_float_binop::<f64>(|a, b| a.tw_maximum(b)).to_cf()?,
             F32Copysign => self.stack.values.calculate_same::<f32>(|a, b| Ok(a.copysign(b))).to_cf()?,
             F64Copysign => self.stack.values.calculate_same::<f64>(|a, b| Ok(a.copysign(b))).to_cf()?,
 
@@ -304,6 +338,7 @@ impl<'store, 'stack> Executor<'store, 'stack> {
             LocalCopyRef(from, to) => self.exec_local_copy::<ValueRef>(*from, *to),
 
             Simd(op) => exec_next_simd(self, *op).to_cf()?,
+            Atomic(op) => self.exec_atomic(*op)?,
         };
 
         self.cf.incr_instr_ptr();
@@ -315,32 +350,71 @@ impl<'store, 'stack> Executor<'store, 'stack> {
         ControlFlow::Break(Some(Trap::Unreachable.into()))
     }
 
-    fn exec_call(&mut self, wasm_func: Rc<WasmFunction>, owner: ModuleInstanceAddr) -> ControlFlow<Option<Error>> {
+    fn exec_call<const IS_RETURN_CALL: bool>(
+        &mut self,
+        wasm_func: Arc<WasmFunction>,
+        func_addr: FuncAddr,
+        owner: ModuleInstanceAddr,
+    ) -> ControlFlow<Option<Error>> {
+        self.store.count_call(func_addr);
         let locals = self.stack.values.pop_locals(wasm_func.params, wasm_func.locals);
-        let new_call_frame = CallFrame::new_raw(wasm_func, owner, locals, self.stack.blocks.len() as u32);
-        self.cf.incr_instr_ptr(); // skip the call instruction
-        self.stack.call_stack.push(core::mem::replace(&mut self.cf, new_call_frame))?;
+
+        if IS_RETURN_CALL {
+            // reuse the current frame: discard its operands and blocks, the callee returns directly to our caller
+            let (block_ptr, stack_ptr) = (self.cf.block_ptr(), self.cf.stack_ptr());
+            self.stack.values.truncate_keep(stack_ptr, StackHeight::default());
+            self.stack.blocks.truncate(block_ptr);
+            self.cf = CallFrame::new_raw(wasm_func, func_addr, owner, locals, block_ptr, stack_ptr);
+        } else {
+            let new_call_frame = CallFrame::new_raw(
+                wasm_func,
+                func_addr,
+                owner,
+                locals,
+                self.stack.blocks.len() as u32,
+                self.stack.values.height(),
+            );
+            self.cf.incr_instr_ptr(); // skip the call instruction
+            self.stack.call_stack.push(core::mem::replace(&mut self.cf, new_call_frame))?;
+        }
+
         self.module.swap_with(self.cf.module_addr(), self.store);
         ControlFlow::Continue(())
     }
-    fn exec_call_direct(&mut self, v: u32) -> ControlFlow<Option<Error>> {
-        let func_inst = self.store.get_func(self.module.resolve_func_addr(v));
+    fn exec_call_host<const IS_RETURN_CALL: bool>(
+        &mut self,
+        host_func: Rc<HostFunction>,
+        func_addr: FuncAddr,
+    ) -> ControlFlow<Option<Error>> {
+        self.store.count_call(func_addr);
+        let params = self.stack.values.pop_params(&host_func.ty.params);
+        let res = host_func.call(FuncContext { store: self.store, module_addr: self.module.id() }, &params).to_cf()?;
+        self.stack.values.extend_from_wasmvalues(&res);
+
+        if IS_RETURN_CALL {
+            return self.exec_return();
+        }
+
+        self.cf.incr_instr_ptr();
+        ControlFlow::Continue(())
+    }
+    fn exec_call_direct<const IS_RETURN_CALL: bool>(&mut self, v: u32) -> ControlFlow<Option<Error>> {
+        let func_addr = self.module.resolve_func_addr(v);
+        let func_inst = self.store.get_func(func_addr);
         let wasm_func = match &func_inst.func {
             crate::Function::Wasm(wasm_func) => wasm_func,
             crate::Function::Host(host_func) => {
-                let func = &host_func.clone();
-                let params = self.stack.values.pop_params(&host_func.ty.params);
-                let res =
-                    func.call(FuncContext { store: self.store, module_addr: self.module.id() }, &params).to_cf()?;
-                self.stack.values.extend_from_wasmvalues(&res);
-                self.cf.incr_instr_ptr();
-                return ControlFlow::Continue(());
+                return self.exec_call_host::<IS_RETURN_CALL>(host_func.clone(), func_addr)
             }
         };
 
-        self.exec_call(wasm_func.clone(), func_inst.owner)
+        self.exec_call::<IS_RETURN_CALL>(wasm_func.clone(), func_addr, func_inst.owner)
     }
-    fn exec_call_indirect(&mut self, type_addr: u32, table_addr: u32) -> ControlFlow<Option<Error>> {
+    fn exec_call_indirect<const IS_RETURN_CALL: bool>(
+        &mut self,
+        type_addr: u32,
+        table_addr: u32,
+    ) -> ControlFlow<Option<Error>> {
         // verify that the table is of the right type, this should be validated by the parser already
         let func_ref = {
             let table = self.store.get_table(self.module.resolve_table_addr(table_addr));
@@ -367,17 +441,7 @@ impl<'store, 'stack> Executor<'store, 'stack> {
                     ));
                 }
 
-                let host_func = host_func.clone();
-                let params = self.stack.values.pop_params(&host_func.ty.params);
-                let res =
-                    match host_func.call(FuncContext { store: self.store, module_addr: self.module.id() }, &params) {
-                        Ok(res) => res,
-                        Err(e) => return ControlFlow::Break(Some(e)),
-                    };
-
-                self.stack.values.extend_from_wasmvalues(&res);
-                self.cf.incr_instr_ptr();
-                return ControlFlow::Continue(());
+                return self.exec_call_host::<IS_RETURN_CALL>(host_func.clone(), func_ref);
             }
         };
 
@@ -387,7 +451,7 @@ impl<'store, 'stack> Executor<'store, 'stack> {
             ));
         }
 
-        self.exec_call(wasm_func.clone(), func_inst.owner)
+        self.exec_call::<IS_RETURN_CALL>(wasm_func.clone(), func_ref, func_inst.owner)
     }
 
     fn exec_if(&mut self, else_offset: u32, end_offset: u32, (params, results): (StackHeight, StackHeight)) {
@@ -418,7 +482,8 @@ impl<'store, 'stack> Executor<'store, 'stack> {
         self.stack.blocks.push(BlockFrame {
             instr_ptr: self.cf.instr_ptr(),
             end_instr_offset,
-            stack_ptr: self.stack.values.height(),
+            // the params are already on the stack and belong to the block
+            stack_ptr: self.stack.values.height_below(params),
             results,
             params,
             ty,
@@ -467,6 +532,9 @@ impl<'store, 'stack> Executor<'store, 'stack> {
         ControlFlow::Continue(())
     }
     fn exec_return(&mut self) -> ControlFlow<Option<Error>> {
+        // `return` can leave operands below the results, these must not leak into the caller
+        self.stack.values.truncate_keep(self.cf.stack_ptr(), self.cf.results());
+
         let old = self.cf.block_ptr();
         match self.stack.call_stack.pop() {
             None => return ControlFlow::Break(None),
@@ -500,9 +568,26 @@ impl<'store, 'stack> Executor<'store, 'stack> {
     fn exec_global_get(&mut self, global_index: u32) {
         self.stack.values.push_dyn(self.store.get_global_val(self.module.resolve_global_addr(global_index)));
     }
-    fn exec_global_set<T: InternalValue>(&mut self, global_index: u32) {
-        self.store.set_global_val(self.module.resolve_global_addr(global_index), self.stack.values.pop::<T>().into());
+    fn exec_global_set<T: InternalValue>(&mut self, global_index: u32) -> Result<()> {
+        // validation rejects `global.set` on immutable globals, the store checks again in case it was bypassed
+        self.store.set_global_val(self.module.resolve_global_addr(global_index), self.stack.values.pop::<T>().into())
+    }
+    // arithmetic float ops, NaN results are canonicalized if enabled with `Store::set_canonicalize_nans`
+    #[inline(always)]
+    fn exec_float_binop<T: InternalValue + TinywasmFloatExt>(&mut self, op: impl FnOnce(T, T) -> T) -> Result<()> {
+        let canonicalize = self.store.canonicalize_nans;
+        self.stack.values.calculate_same::<T>(|a, b| Ok(op(a, b).tw_canonicalize_nan(canonicalize)))
+    }
+
+    #[inline(always)]
+    fn exec_float_unop<T: InternalValue, U: InternalValue + TinywasmFloatExt>(
+        &mut self,
+        op: impl FnOnce(T) -> U,
+    ) -> Result<()> {
+        let canonicalize = self.store.canonicalize_nans;
+        self.stack.values.replace_top::<T, U>(|v| Ok(op(v).tw_canonicalize_nan(canonicalize)))
     }
+
     fn exec_const<T: InternalValue>(&mut self, val: T) {
         self.stack.values.push(val);
     }
@@ -511,50 +596,253 @@ impl<'store, 'stack> Executor<'store, 'stack> {
         self.stack.values.push::<i32>(is_null);
     }
 
+    fn exec_i31_get(&mut self, get: fn(&I31Ref) -> Option<i32>) -> Result<()> {
+        self.stack.values.replace_top::<ValueRef, i32>(|v| {
+            let i31 = v.map_or(I31Ref::null(), |v| I31Ref::new(v as i32));
+            get(&i31).ok_or(Error::Trap(Trap::NullI31Reference))
+        })
+    }
+
+    /// Atomic instructions, which behave like their non-atomic counterparts since there is only one thread
+    fn exec_atomic(&mut self, op: AtomicInstruction) -> ControlFlow<Option<Error>> {
+        use AtomicInstruction::*;
+        let add: fn(u64, u64) -> u64 = u64::wrapping_add;
+        let sub: fn(u64, u64) -> u64 = u64::wrapping_sub;
+        let and: fn(u64, u64) -> u64 = |a, b| a & b;
+        let or: fn(u64, u64) -> u64 = |a, b| a | b;
+        let xor: fn(u64, u64) -> u64 = |a, b| a ^ b;
+        let xchg: fn(u64, u64) -> u64 = |_, b| b;
+
+        match op {
+            AtomicFence => {}
+            MemoryAtomicNotify(m) => self.exec_atomic_notify(m).to_cf()?,
+            MemoryAtomicWait32(m) => self.exec_atomic_wait::<i32, 4>(m).to_cf()?,
+            MemoryAtomicWait64(m) => self.exec_atomic_wait::<i64, 8>(m).to_cf()?,
+
+            I32AtomicLoad(m) => self.exec_mem_load::<i32, 4, _, true>(m.mem_addr(), m.offset(), |v| v)?,
+            I32AtomicLoad8U(m) => self.exec_mem_load::<u8, 1, _, true>(m.mem_addr(), m.offset(), |v| v as i32)?,
+            I32AtomicLoad16U(m) => self.exec_mem_load::<u16, 2, _, true>(m.mem_addr(), m.offset(), |v| v as i32)?,
+            I64AtomicLoad(m) => self.exec_mem_load::<i64, 8, _, true>(m.mem_addr(), m.offset(), |v| v)?,
+            I64AtomicLoad8U(m) => self.exec_mem_load::<u8, 1, _, true>(m.mem_addr(), m.offset(), |v| v as i64)?,
+            I64AtomicLoad16U(m) => self.exec_mem_load::<u16, 2, _, true>(m.mem_addr(), m.offset(), |v| v as i64)?,
+            I64AtomicLoad32U(m) => self.exec_mem_load::<u32, 4, _, true>(m.mem_addr(), m.offset(), |v| v as i64)?,
+
+            I32AtomicStore(m) => self.exec_mem_store::<i32, i32, 4, true>(m.mem_addr(), m.offset(), |v| v)?,
+            I32AtomicStore8(m) => self.exec_mem_store::<i32, i8, 1, true>(m.mem_addr(), m.offset(), |v| v as i8)?,
+            I32AtomicStore16(m) => self.exec_mem_store::<i32, i16, 2, true>(m.mem_addr(), m.offset(), |v| v as i16)?,
+            I64AtomicStore(m) => self.exec_mem_store::<i64, i64, 8, true>(m.mem_addr(), m.offset(), |v| v)?,
+            I64AtomicStore8(m) => self.exec_mem_store::<i64, i8, 1, true>(m.mem_addr(), m.offset(), |v| v as i8)?,
+            I64AtomicStore16(m) => self.exec_mem_store::<i64, i16, 2, true>(m.mem_addr(), m.offset(), |v| v as i16)?,
+            I64AtomicStore32(m) => self.exec_mem_store::<i64, i32, 4, true>(m.mem_addr(), m.offset(), |v| v as i32)?,
+
+            I32AtomicRmwAdd(m) => self.exec_atomic_rmw::<i32, 4>(m, add).to_cf()?,
+            I32AtomicRmw8AddU(m) => self.exec_atomic_rmw::<i32, 1>(m, add).to_cf()?,
+            I32AtomicRmw16AddU(m) => self.exec_atomic_rmw::<i32, 2>(m, add).to_cf()?,
+            I64AtomicRmwAdd(m) => self.exec_atomic_rmw::<i64, 8>(m, add).to_cf()?,
+            I64AtomicRmw8AddU(m) => self.exec_atomic_rmw::<i64, 1>(m, add).to_cf()?,
+            I64AtomicRmw16AddU(m) => self.exec_atomic_rmw::<i64, 2>(m, add).to_cf()?,
+            I64AtomicRmw32AddU(m) => self.exec_atomic_rmw::<i64, 4>(m, add).to_cf()?,
+            I32AtomicRmwSub(m) => self.exec_atomic_rmw::<i32, 4>(m, sub).to_cf()?,
+            I32AtomicRmw8SubU(m) => self.exec_atomic_rmw::<i32, 1>(m, sub).to_cf()?,
+            I32AtomicRmw16SubU(m) => self.exec_atomic_rmw::<i32, 2>(m, sub).to_cf()?,
+            I64AtomicRmwSub(m) => self.exec_atomic_rmw::<i64, 8>(m, sub).to_cf()?,
+            I64AtomicRmw8SubU(m) => self.exec_atomic_rmw::<i64, 1>(m, sub).to_cf()?,
+            I64AtomicRmw16SubU(m) => self.exec_atomic_rmw::<i64, 2>(m, sub).to_cf()?,
+            I64AtomicRmw32SubU(m) => self.exec_atomic_rmw::<i64, 4>(m, sub).to_cf()?,
+            I32AtomicRmwAnd(m) => self.exec_atomic_rmw::<i32, 4>(m, and).to_cf()?,
+            I32AtomicRmw8AndU(m) => self.exec_atomic_rmw::<i32, 1>(m, and).to_cf()?,
+            I32AtomicRmw16AndU(m) => self.exec_atomic_rmw::<i32, 2>(m, and).to_cf()?,
+            I64AtomicRmwAnd(m) => self.exec_atomic_rmw::<i64, 8>(m, and).to_cf()?,
+            I64AtomicRmw8AndU(m) => self.exec_atomic_rmw::<i64, 1>(m, and).to_cf()?,
+            I64AtomicRmw16AndU(m) => self.exec_atomic_rmw::<i64, 2>(m, and).to_cf()?,
+            I64AtomicRmw32AndU(m) => self.exec_atomic_rmw::<i64, 4>(m, and).to_cf()?,
+            I32AtomicRmwOr(m) => self.exec_atomic_rmw::<i32, 4>(m, or).to_cf()?,
+            I32AtomicRmw8OrU(m) => self.exec_atomic_rmw::<i32, 1>(m, or).to_cf()?,
+            I32AtomicRmw16OrU(m) => self.exec_atomic_rmw::<i32, 2>(m, or).to_cf()?,
+            I64AtomicRmwOr(m) => self.exec_atomic_rmw::<i64, 8>(m, or).to_cf()?,
+            I64AtomicRmw8OrU(m) => self.exec_atomic_rmw::<i64, 1>(m, or).to_cf()?,
+            I64AtomicRmw16OrU(m) => self.exec_atomic_rmw::<i64, 2>(m, or).to_cf()?,
+            I64AtomicRmw32OrU(m) => self.exec_atomic_rmw::<i64, 4>(m, or).to_cf()?,
+            I32AtomicRmwXor(m) => self.exec_atomic_rmw::<i32, 4>(m, xor).to_cf()?,
+            I32AtomicRmw8XorU(m) => self.exec_atomic_rmw::<i32, 1>(m, xor).to_cf()?,
+            I32AtomicRmw16XorU(m) => self.exec_atomic_rmw::<i32, 2>(m, xor).to_cf()?,
+            I64AtomicRmwXor(m) => self.exec_atomic_rmw::<i64, 8>(m, xor).to_cf()?,
+            I64AtomicRmw8XorU(m) => self.exec_atomic_rmw::<i64, 1>(m, xor).to_cf()?,
+            I64AtomicRmw16XorU(m) => self.exec_atomic_rmw::<i64, 2>(m, xor).to_cf()?,
+            I64AtomicRmw32XorU(m) => self.exec_atomic_rmw::<i64, 4>(m, xor).to_cf()?,
+            I32AtomicRmwXchg(m) => self.exec_atomic_rmw::<i32, 4>(m, xchg).to_cf()?,
+            I32AtomicRmw8XchgU(m) => self.exec_atomic_rmw::<i32, 1>(m, xchg).to_cf()?,
+            I32AtomicRmw16XchgU(m) => self.exec_atomic_rmw::<i32, 2>(m, xchg).to_cf()?,
+            I64AtomicRmwXchg(m) => self.exec_atomic_rmw::<i64, 8>(m, xchg).to_cf()?,
+            I64AtomicRmw8XchgU(m) => self.exec_atomic_rmw::<i64, 1>(m, xchg).to_cf()?,
+            I64AtomicRmw16XchgU(m) => self.exec_atomic_rmw::<i64, 2>(m, xchg).to_cf()?,
+            I64AtomicRmw32XchgU(m) => self.exec_atomic_rmw::<i64, 4>(m, xchg).to_cf()?,
+
+            I32AtomicRmwCmpxchg(m) => self.exec_atomic_cmpxchg::<i32, 4>(m).to_cf()?,
+            I32AtomicRmw8CmpxchgU(m) => self.exec_atomic_cmpxchg::<i32, 1>(m).to_cf()?,
+            I32AtomicRmw16CmpxchgU(m) => self.exec_atomic_cmpxchg::<i32, 2>(m).to_cf()?,
+            I64AtomicRmwCmpxchg(m) => self.exec_atomic_cmpxchg::<i64, 8>(m).to_cf()?,
+            I64AtomicRmw8CmpxchgU(m) => self.exec_atomic_cmpxchg::<i64, 1>(m).to_cf()?,
+            I64AtomicRmw16CmpxchgU(m) => self.exec_atomic_cmpxchg::<i64, 2>(m).to_cf()?,
+            I64AtomicRmw32CmpxchgU(m) => self.exec_atomic_cmpxchg::<i64, 4>(m).to_cf()?,
+        }
+        ControlFlow::Continue(())
+    }
+    /// Pop the address of an atomic access of `N` bytes, which has to be in bounds and a multiple of `N`
+    fn atomic_addr<const N: usize>(&mut self, m: MemoryArg) -> Result<usize> {
+        let mem = self.store.get_mem(self.module.resolve_mem_addr(m.mem_addr()));
+        let base = self.stack.values.pop_mem_operand(mem.is_64());
+        let Some(Ok(addr)) = base.checked_add(m.offset()).map(usize::try_from) else {
+            cold();
+            return Err(Error::Trap(Trap::MemoryOutOfBounds {
+                offset: saturating_usize(base),
+                len: N,
+                max: mem.len(),
+            }));
+        };
+        MemoryInstance::check_alignment(addr, N)?;
+        mem.load(addr, N)?;
+        Ok(addr)
+    }
+    /// Replace the `N` bytes at the popped address with `update` applied to their value, returning the old value
+    fn exec_atomic_update<const N: usize>(&mut self, m: MemoryArg, update: impl FnOnce(u64) -> u64) -> Result<u64> {
+        let addr = self.atomic_addr::<N>(m)?;
+        let mem = self.store.get_mem_mut(self.module.resolve_mem_addr(m.mem_addr()));
+        let mut bytes = [0; 8];
+        bytes[..N].copy_from_slice(mem.load(addr, N)?);
+        let old = u64::from_le_bytes(bytes);
+        mem.store(addr, N, &update(old).to_le_bytes()[..N])?;
+        Ok(old)
+    }
+    fn exec_atomic_rmw<T: AtomicInt + InternalValue, const N: usize>(
+        &mut self,
+        m: MemoryArg,
+        op: fn(u64, u64) -> u64,
+    ) -> Result<()> {
+        let val = self.stack.values.pop::<T>().to_bits();
+        let old = self.exec_atomic_update::<N>(m, |old| op(old, val))?;
+        self.stack.values.push(T::from_bits(old));
+        Ok(())
+    }
+    fn exec_atomic_cmpxchg<T: AtomicInt + InternalValue, const N: usize>(&mut self, m: MemoryArg) -> Result<()> {
+        let replacement = self.stack.values.pop::<T>().to_bits();
+        // only `N` bytes are compared, so the expected value is wrapped the same way the loaded one is
+        let expected = self.stack.values.pop::<T>().to_bits() & (u64::MAX >> (64 - 8 * N));
+        let old = self.exec_atomic_update::<N>(m, |old| if old == expected { replacement } else { old })?;
+        self.stack.values.push(T::from_bits(old));
+        Ok(())
+    }
+    fn exec_atomic_wait<T: AtomicInt + InternalValue, const N: usize>(&mut self, m: MemoryArg) -> Result<()> {
+        let timeout = self.stack.values.pop::<i64>();
+        let expected = self.stack.values.pop::<T>().to_bits();
+        let addr = self.atomic_addr::<N>(m)?;
+        let mem = self.store.get_mem(self.module.resolve_mem_addr(m.mem_addr()));
+        if !mem.kind.is_shared() {
+            return Err(Error::Trap(Trap::NotSharedMemory));
+        }
+
+        let mut bytes = [0; 8];
+        bytes[..N].copy_from_slice(mem.load(addr, N)?);
+
+        // 1: "not-equal", 2: "timed-out". With only one thread nothing can wake a waiter, so any
+        // finite wait times out right away and an infinite one would never return
+        let result = match u64::from_le_bytes(bytes) != expected {
+            true => 1,
+            false if timeout >= 0 => 2,
+            false => return Err(Error::Trap(Trap::Deadlock)),
+        };
+        self.stack.values.push::<i32>(result);
+        Ok(())
+    }
+    fn exec_atomic_notify(&mut self, m: MemoryArg) -> Result<()> {
+        let _count = self.stack.values.pop::<i32>();
+        self.atomic_addr::<4>(m)?;
+        // with only one thread nothing is ever waiting, so no waiters are woken
+        self.stack.values.push::<i32>(0);
+        Ok(())
+    }
+
     fn exec_memory_size(&mut self, addr: u32) {
         let mem = self.store.get_mem(self.module.resolve_mem_addr(addr));
-        self.stack.values.push::<i32>(mem.page_count as i32);
+        self.stack.values.push_mem_operand(mem.page_count as i64, mem.is_64());
     }
-    fn exec_memory_grow(&mut self, addr: u32) {
-        let mem = self.store.get_mem_mut(self.module.resolve_mem_addr(addr));
-        let prev_size = mem.page_count as i32;
-        let pages_delta = self.stack.values.pop::<i32>();
-        self.stack.values.push::<i32>(match mem.grow(pages_delta) {
-            Some(_) => prev_size,
-            None => -1,
-        });
+    fn exec_memory_grow(&mut self, addr: u32) -> Result<()> {
+        let mem_addr = self.module.resolve_mem_addr(addr);
+        let is_64 = self.store.get_mem(mem_addr).is_64();
+
+        // the delta is unsigned, anything that doesn't fit in an i64 can never succeed
+        let Ok(pages_delta) = i64::try_from(self.stack.values.pop_mem_operand(is_64)) else {
+            self.stack.values.push_mem_operand(-1, is_64);
+            return Ok(());
+        };
+
+        // growing past the store's limit fails the same way as growing past the declared maximum
+        if let Some(max_pages) = self.store.max_memory_pages {
+            let new_pages = (self.store.get_mem(mem_addr).page_count as i64).checked_add(pages_delta);
+            if new_pages.map_or(true, |pages| pages > max_pages as i64) {
+                self.stack.values.push_mem_operand(-1, is_64);
+                return Ok(());
+            }
+        }
+
+        // a grow that fails anyway (e.g. past the declared maximum) pushes -1 instead of exceeding the budget
+        let mem = self.store.get_mem(mem_addr);
+        let Some(new_size) = mem.grown_size(pages_delta) else {
+            self.stack.values.push_mem_operand(-1, is_64);
+            return Ok(());
+        };
+
+        // see `FuncHandle::call_with_memory_budget`
+        let requested = new_size - mem.len();
+        if let Some(remaining) = self.store.memory_budget {
+            if requested > remaining {
+                return Err(Trap::MemoryBudgetExceeded { requested, remaining }.into());
+            }
+        }
+
+        let mem = self.store.get_mem_mut(mem_addr);
+        let res = mem.grow(pages_delta);
+        if let (Some(_), Some(remaining)) = (res, self.store.memory_budget.as_mut()) {
+            *remaining -= requested;
+        }
+
+        self.stack.values.push_mem_operand(res.unwrap_or(-1), is_64);
+        Ok(())
     }
 
     fn exec_memory_copy(&mut self, from: u32, to: u32) -> Result<()> {
-        let size: i32 = self.stack.values.pop();
-        let src: i32 = self.stack.values.pop();
-        let dst: i32 = self.stack.values.pop();
+        let dst_64 = self.store.get_mem(self.module.resolve_mem_addr(from)).is_64();
+        let src_64 = self.store.get_mem(self.module.resolve_mem_addr(to)).is_64();
+
+        // the size is only an i64 if both memories are 64-bit
+        let size = saturating_usize(self.stack.values.pop_mem_operand(dst_64 && src_64));
+        let src = saturating_usize(self.stack.values.pop_mem_operand(src_64));
+        let dst = saturating_usize(self.stack.values.pop_mem_operand(dst_64));
 
+        // both ranges are checked before any bytes are written, so a trap leaves the memory untouched
         if from == to {
             let mem_from = self.store.get_mem_mut(self.module.resolve_mem_addr(from));
             // copy within the same memory
-            mem_from.copy_within(dst as usize, src as usize, size as usize)?;
+            mem_from.copy_within(dst, src, size)?;
         } else {
             // copy between two memories
             let (mem_from, mem_to) =
                 self.store.get_mems_mut(self.module.resolve_mem_addr(from), self.module.resolve_mem_addr(to))?;
 
-            mem_from.copy_from_slice(dst as usize, mem_to.load(src as usize, size as usize)?)?;
+            mem_from.copy_from_slice(dst, mem_to.load(src, size)?)?;
         }
         Ok(())
     }
     fn exec_memory_fill(&mut self, addr: u32) -> Result<()> {
-        let size: i32 = self.stack.values.pop();
-        let val: i32 = self.stack.values.pop();
-        let dst: i32 = self.stack.values.pop();
-
         let mem = self.store.get_mem_mut(self.module.resolve_mem_addr(addr));
-        mem.fill(dst as usize, size as usize, val as u8)
+        let size = saturating_usize(self.stack.values.pop_mem_operand(mem.is_64()));
+        let val: i32 = self.stack.values.pop();
+        let dst = saturating_usize(self.stack.values.pop_mem_operand(mem.is_64()));
+        mem.fill(dst, size, val as u8)
     }
     fn exec_memory_init(&mut self, data_index: u32, mem_index: u32) -> Result<()> {
-        let size: i32 = self.stack.values.pop();
-        let offset: i32 = self.stack.values.pop();
-        let dst: i32 = self.stack.values.pop();
+        let size = self.stack.values.pop::<i32>() as u32 as usize;
+        let offset = self.stack.values.pop::<i32>() as u32 as usize;
 
         let data = self
             .store
@@ -570,18 +858,20 @@ impl<'store, 'stack> Executor<'store, 'stack> {
             .get_mut(self.module.resolve_mem_addr(mem_index) as usize)
             .ok_or_else(|| Error::Other("memory not found".to_string()))?;
 
-        let data_len = data.data.as_ref().map_or(0, |d| d.len());
+        let dst = saturating_usize(self.stack.values.pop_mem_operand(mem.is_64()));
 
-        if unlikely(((size + offset) as usize > data_len) || ((dst + size) as usize > mem.len())) {
-            return Err(Trap::MemoryOutOfBounds { offset: offset as usize, len: size as usize, max: data_len }.into());
+        // a dropped segment behaves like an empty one
+        let data = data.data.as_deref().unwrap_or_default();
+
+        if unlikely(offset.saturating_add(size) > data.len() || dst.saturating_add(size) > mem.len()) {
+            return Err(Trap::MemoryOutOfBounds { offset, len: size, max: data.len() }.into());
         }
 
         if size == 0 {
             return Ok(());
         }
 
-        let Some(data) = &data.data else { return Err(Trap::MemoryOutOfBounds { offset: 0, len: 0, max: 0 }.into()) };
-        mem.store(dst as usize, size as usize, &data[offset as usize..((offset + size) as usize)])
+        mem.store(dst, size, &data[offset..offset + size])
     }
     fn exec_data_drop(&mut self, data_index: u32) {
         self.store.get_data_mut(self.module.resolve_data_addr(data_index)).drop();
@@ -590,47 +880,59 @@ impl<'store, 'stack> Executor<'store, 'stack> {
         self.store.get_elem_mut(self.module.resolve_elem_addr(elem_index)).drop();
     }
     fn exec_table_copy(&mut self, from: u32, to: u32) -> Result<()> {
-        let size: i32 = self.stack.values.pop();
-        let src: i32 = self.stack.values.pop();
-        let dst: i32 = self.stack.values.pop();
+        let size = self.stack.values.pop::<i32>() as u32 as usize;
+        let src = self.stack.values.pop::<i32>() as u32 as usize;
+        let dst = self.stack.values.pop::<i32>() as u32 as usize;
 
         if from == to {
             // copy within the same memory
-            self.store.get_table_mut(self.module.resolve_table_addr(from)).copy_within(
-                dst as usize,
-                src as usize,
-                size as usize,
-            )?;
+            self.store.get_table_mut(self.module.resolve_table_addr(from)).copy_within(dst, src, size)?;
         } else {
             // copy between two memories
             let (table_from, table_to) =
                 self.store.get_tables_mut(self.module.resolve_table_addr(from), self.module.resolve_table_addr(to))?;
-            table_to.copy_from_slice(dst as usize, table_from.load(src as usize, size as usize)?)?;
+
+            // rejected by the validator, but modules can also be constructed without it
+            if table_from.kind.element_type != table_to.kind.element_type {
+                let (expected, actual) = (table_to.kind.element_type, table_from.kind.element_type);
+                return Err(Trap::TableTypeMismatch { expected, actual }.into());
+            }
+            table_to.copy_from_slice(dst, table_from.load(src, size)?)?;
         }
         Ok(())
     }
 
-    fn exec_mem_load<LOAD: MemLoadable<LOAD_SIZE>, const LOAD_SIZE: usize, TARGET: InternalValue>(
+    /// Load a value from memory, `ALIGNED` accesses trap unless the address is a multiple of `LOAD_SIZE`
+    fn exec_mem_load<
+        LOAD: MemLoadable<LOAD_SIZE>,
+        const LOAD_SIZE: usize,
+        TARGET: InternalValue,
+        const ALIGNED: bool,
+    >(
         &mut self,
         mem_addr: tinywasm_types::MemAddr,
         offset: u64,
         cast: fn(LOAD) -> TARGET,
     ) -> ControlFlow<Option<Error>> {
         let mem = self.store.get_mem(self.module.resolve_mem_addr(mem_addr));
-        let val = self.stack.values.pop::<i32>() as u64;
+        let val = self.stack.values.pop_mem_operand(mem.is_64());
         let Some(Ok(addr)) = offset.checked_add(val).map(TryInto::try_into) else {
             cold();
             return ControlFlow::Break(Some(Error::Trap(Trap::MemoryOutOfBounds {
-                offset: val as usize,
+                offset: saturating_usize(val),
                 len: LOAD_SIZE,
-                max: 0,
+                max: mem.len(),
             })));
         };
+        if ALIGNED {
+            MemoryInstance::check_alignment(addr, LOAD_SIZE).to_cf()?;
+        }
         let val = mem.load_as::<LOAD_SIZE, LOAD>(addr).to_cf()?;
         self.stack.values.push(cast(val));
         ControlFlow::Continue(())
     }
-    fn exec_mem_store<T: InternalValue, U: MemStorable<N>, const N: usize>(
+    /// Store a value to memory, `ALIGNED` accesses trap unless the address is a multiple of `N`
+    fn exec_mem_store<T: InternalValue, U: MemStorable<N>, const N: usize, const ALIGNED: bool>(
         &mut self,
         mem_addr: tinywasm_types::MemAddr,
         offset: u64,
@@ -639,8 +941,11 @@ impl<'store, 'stack> Executor<'store, 'stack> {
         let mem = self.store.get_mem_mut(self.module.resolve_mem_addr(mem_addr));
         let val = self.stack.values.pop::<T>();
         let val = (cast(val)).to_mem_bytes();
-        let addr = self.stack.values.pop::<i32>() as u64;
-        if let Err(e) = mem.store((offset + addr) as usize, val.len(), &val) {
+        let addr = saturating_usize(offset.saturating_add(self.stack.values.pop_mem_operand(mem.is_64())));
+        if ALIGNED {
+            MemoryInstance::check_alignment(addr, N).to_cf()?;
+        }
+        if let Err(e) = mem.store(addr, val.len(), &val) {
             return ControlFlow::Break(Some(e));
         }
         ControlFlow::Continue(())
@@ -665,9 +970,9 @@ impl<'store, 'stack> Executor<'store, 'stack> {
         Ok(())
     }
     fn exec_table_init(&mut self, elem_index: u32, table_index: u32) -> Result<()> {
-        let size: i32 = self.stack.values.pop(); // n
-        let offset: i32 = self.stack.values.pop(); // s
-        let dst: i32 = self.stack.values.pop(); // d
+        let size = self.stack.values.pop::<i32>() as u32 as usize; // n
+        let offset = self.stack.values.pop::<i32>() as u32 as usize; // s
+        let dst = self.stack.values.pop::<i32>() as u32 as usize; // d
 
         let elem = self
             .store
@@ -683,35 +988,29 @@ impl<'store, 'stack> Executor<'store, 'stack> {
             .get_mut(self.module.resolve_table_addr(table_index) as usize)
             .ok_or_else(|| Error::Other("table not found".to_string()))?;
 
-        let elem_len = elem.items.as_ref().map_or(0, alloc::vec::Vec::len);
-        let table_len = table.size();
+        // dropped (and active) segments behave like empty ones
+        let items = elem.items.as_deref().unwrap_or_default();
+        let table_len = table.size() as usize;
 
-        if unlikely(size < 0 || ((size + offset) as usize > elem_len) || ((dst + size) > table_len)) {
-            return Err(Trap::TableOutOfBounds { offset: offset as usize, len: size as usize, max: elem_len }.into());
+        if unlikely(offset.saturating_add(size) > items.len() || dst.saturating_add(size) > table_len) {
+            return Err(Trap::TableOutOfBounds { offset, len: size, max: items.len() }.into());
         }
 
         if size == 0 {
             return Ok(());
         }
 
-        if let ElementKind::Active { .. } = elem.kind {
-            return Err(Error::Other("table.init with active element".to_string()));
-        }
-
-        let Some(items) = elem.items.as_ref() else {
-            return Err(Trap::TableOutOfBounds { offset: 0, len: 0, max: 0 }.into());
-        };
-
-        table.init(dst, &items[offset as usize..(offset + size) as usize])
+        table.init(dst as i32, &items[offset..offset + size])
     }
     fn exec_table_grow(&mut self, table_index: u32) -> Result<()> {
+        let max_elements = self.store.max_table_elements;
         let table = self.store.get_table_mut(self.module.resolve_table_addr(table_index));
         let sz = table.size();
 
         let n = self.stack.values.pop::<i32>();
         let val = self.stack.values.pop::<ValueRef>();
 
-        match table.grow(n, val.into()) {
+        match table.grow(n, val.into(), max_elements) {
             Ok(_) => self.stack.values.push(sz),
             Err(_) => self.stack.values.push(-1_i32),
         }
@@ -721,23 +1020,19 @@ impl<'store, 'stack> Executor<'store, 'stack> {
     fn exec_table_fill(&mut self, table_index: u32) -> Result<()> {
         let table = self.store.get_table_mut(self.module.resolve_table_addr(table_index));
 
-        let n = self.stack.values.pop::<i32>();
+        let n = self.stack.values.pop::<i32>() as u32 as usize;
         let val = self.stack.values.pop::<ValueRef>();
-        let i = self.stack.values.pop::<i32>();
+        let i = self.stack.values.pop::<i32>() as u32 as usize;
 
-        if unlikely(i + n > table.size()) {
-            return Err(Error::Trap(Trap::TableOutOfBounds {
-                offset: i as usize,
-                len: n as usize,
-                max: table.size() as usize,
-            }));
+        if unlikely(i.saturating_add(n) > table.size() as usize) {
+            return Err(Error::Trap(Trap::TableOutOfBounds { offset: i, len: n, max: table.size() as usize }));
         }
 
         if n == 0 {
             return Ok(());
         }
 
-        table.fill(self.module.func_addrs(), i as usize, n as usize, val.into())
+        table.fill(i, n, val.into())
     }
 
     fn exec_local_copy<T: InternalValue>(&mut self, from: u16, to: u16) {
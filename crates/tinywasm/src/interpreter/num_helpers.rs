@@ -57,10 +57,18 @@ pub(crate) use float_min_max;
 pub(super) fn trap_0() -> Error {
     Error::Trap(crate::Trap::DivisionByZero)
 }
+
+/// Convert a memory address or size to `usize`, values the host can't address become `usize::MAX`
+/// so they fail the bounds checks of the memory instead of wrapping around
+#[inline(always)]
+pub(super) fn saturating_usize(value: u64) -> usize {
+    usize::try_from(value).unwrap_or(usize::MAX)
+}
 pub(crate) trait TinywasmFloatExt {
     fn tw_minimum(self, other: Self) -> Self;
     fn tw_maximum(self, other: Self) -> Self;
     fn tw_nearest(self) -> Self;
+    fn tw_canonicalize_nan(self, enabled: bool) -> Self;
 }
 
 use crate::{Error, Result};
@@ -71,6 +79,12 @@ use super::no_std_floats::NoStdFloatExt;
 macro_rules! impl_wasm_float_ops {
     ($($t:ty)*) => ($(
         impl TinywasmFloatExt for $t {
+            // https://webassembly.github.io/spec/core/exec/numerics.html#nan-propagation
+            #[inline(always)]
+            fn tw_canonicalize_nan(self, enabled: bool) -> Self {
+                if enabled && self.is_nan() { <$t>::NAN } else { self }
+            }
+
             // https://webassembly.github.io/spec/core/exec/numerics.html#op-fnearest
             fn tw_nearest(self) -> Self {
                 match self {
@@ -180,3 +194,33 @@ macro_rules! impl_checked_wrapping_rem {
 }
 
 impl_checked_wrapping_rem! { i32 i64 u32 u64 }
+
+/// The integers atomic instructions operate on, as the zero-extended bits they read from and write to memory
+pub(crate) trait AtomicInt: Copy {
+    fn to_bits(self) -> u64;
+    fn from_bits(bits: u64) -> Self;
+}
+
+impl AtomicInt for i32 {
+    #[inline]
+    fn to_bits(self) -> u64 {
+        self as u32 as u64
+    }
+
+    #[inline]
+    fn from_bits(bits: u64) -> Self {
+        bits as i32
+    }
+}
+
+impl AtomicInt for i64 {
+    #[inline]
+    fn to_bits(self) -> u64 {
+        self as u64
+    }
+
+    #[inline]
+    fn from_bits(bits: u64) -> Self {
+        bits as i64
+    }
+}
@@ -1,32 +1,394 @@
-use tinywasm_types::SimdInstruction;
+use alloc::format;
+use tinywasm_types::{MemoryArg, SimdInstruction};
 
-use crate::Result;
+use crate::{Error, Result, Trap};
 
 #[cfg(not(feature = "std"))]
 #[allow(unused_imports)]
 use super::no_std_floats::NoStdFloatExt;
+use super::num_helpers::{saturating_usize, TinywasmFloatExt};
 use super::{executor::Executor, Value128};
 
 #[inline(always)]
 pub(crate) fn exec_next_simd(e: &mut Executor<'_, '_>, op: SimdInstruction) -> Result<()> {
+    use SimdInstruction::*;
+    let nan = e.store.canonicalize_nans;
     match op {
+        V128Const(idx) => e.stack.values.push::<Value128>(e.cf.data().v128_constants[idx as usize]),
+        I8x16Shuffle(idx) => {
+            let lanes = e.cf.data().v128_constants[idx as usize].to_le_bytes();
+            let b = e.stack.values.pop::<Value128>().to_le_bytes();
+            e.stack.values.replace_top_same(|a: Value128| {
+                let a = a.to_le_bytes();
+                Ok(u128::from_le_bytes(lanes.map(|i| if i < 16 { a[i as usize] } else { b[i as usize - 16] })))
+            })?;
+        }
+
+        // memory
+        V128Load(m) => {
+            let bytes = load::<16>(e, m)?;
+            e.stack.values.push::<Value128>(u128::from_le_bytes(bytes));
+        }
+        V128Load8x8S(m) => exec_load_extend(e, m, |x: i8| x as i16)?,
+        V128Load8x8U(m) => exec_load_extend(e, m, |x: u8| x as u16)?,
+        V128Load16x4S(m) => exec_load_extend(e, m, |x: i16| x as i32)?,
+        V128Load16x4U(m) => exec_load_extend(e, m, |x: u16| x as u32)?,
+        V128Load32x2S(m) => exec_load_extend(e, m, |x: i32| x as i64)?,
+        V128Load32x2U(m) => exec_load_extend(e, m, |x: u32| x as u64)?,
+        V128Load8Splat(m) => exec_load_splat::<u8, 1>(e, m)?,
+        V128Load16Splat(m) => exec_load_splat::<u16, 2>(e, m)?,
+        V128Load32Splat(m) => exec_load_splat::<u32, 4>(e, m)?,
+        V128Load64Splat(m) => exec_load_splat::<u64, 8>(e, m)?,
+        V128Load32Zero(m) => {
+            let bytes = load::<4>(e, m)?;
+            e.stack.values.push::<Value128>(u32::from_le_bytes(bytes) as u128);
+        }
+        V128Load64Zero(m) => {
+            let bytes = load::<8>(e, m)?;
+            e.stack.values.push::<Value128>(u64::from_le_bytes(bytes) as u128);
+        }
+        V128Load8Lane(m, l) => exec_load_lane::<u8, 1>(e, m, l)?,
+        V128Load16Lane(m, l) => exec_load_lane::<u16, 2>(e, m, l)?,
+        V128Load32Lane(m, l) => exec_load_lane::<u32, 4>(e, m, l)?,
+        V128Load64Lane(m, l) => exec_load_lane::<u64, 8>(e, m, l)?,
+        V128Store(m) => {
+            let v = e.stack.values.pop::<Value128>();
+            store(e, m, &v.to_le_bytes())?;
+        }
+        V128Store8Lane(m, l) => exec_store_lane::<u8>(e, m, l)?,
+        V128Store16Lane(m, l) => exec_store_lane::<u16>(e, m, l)?,
+        V128Store32Lane(m, l) => exec_store_lane::<u32>(e, m, l)?,
+        V128Store64Lane(m, l) => exec_store_lane::<u64>(e, m, l)?,
+
         // unops
-        SimdInstruction::V128Not => e.stack.values.replace_top_same(|a: Value128| Ok(!a))?,
+        V128Not => e.stack.values.replace_top_same(|a: Value128| Ok(!a))?,
+        V128AnyTrue => e.stack.values.replace_top::<Value128, i32>(|a| Ok((a != 0) as i32))?,
+        I8x16AllTrue => e.stack.values.replace_top::<Value128, i32>(|a| Ok(all_true::<u8>(a) as i32))?,
+        I16x8AllTrue => e.stack.values.replace_top::<Value128, i32>(|a| Ok(all_true::<u16>(a) as i32))?,
+        I32x4AllTrue => e.stack.values.replace_top::<Value128, i32>(|a| Ok(all_true::<u32>(a) as i32))?,
+        I64x2AllTrue => e.stack.values.replace_top::<Value128, i32>(|a| Ok(all_true::<u64>(a) as i32))?,
+
+        I8x16Abs => e.stack.values.replace_top_same(|a| Ok(map_lanes(a, i8::wrapping_abs)))?,
+        I16x8Abs => e.stack.values.replace_top_same(|a| Ok(map_lanes(a, i16::wrapping_abs)))?,
+        I32x4Abs => e.stack.values.replace_top_same(|a| Ok(map_lanes(a, i32::wrapping_abs)))?,
+        I64x2Abs => e.stack.values.replace_top_same(|a| Ok(map_lanes(a, i64::wrapping_abs)))?,
+        I8x16Neg => e.stack.values.replace_top_same(|a| Ok(map_lanes(a, i8::wrapping_neg)))?,
+        I16x8Neg => e.stack.values.replace_top_same(|a| Ok(map_lanes(a, i16::wrapping_neg)))?,
+        I32x4Neg => e.stack.values.replace_top_same(|a| Ok(map_lanes(a, i32::wrapping_neg)))?,
+        I64x2Neg => e.stack.values.replace_top_same(|a| Ok(map_lanes(a, i64::wrapping_neg)))?,
+        F32x4Abs => e.stack.values.replace_top_same(|a| Ok(map_lanes(a, |x: f32| x.abs())))?,
+        F64x2Abs => e.stack.values.replace_top_same(|a| Ok(map_lanes(a, |x: f64| x.abs())))?,
+        F32x4Neg => e.stack.values.replace_top_same(|a| Ok(map_lanes(a, |x: f32| -x)))?,
+        F64x2Neg => e.stack.values.replace_top_same(|a| Ok(map_lanes(a, |x: f64| -x)))?,
+
         // binops
-        SimdInstruction::V128And => e.stack.values.calculate_same(|a: Value128, b: Value128| Ok(a & b))?,
-        SimdInstruction::V128AndNot => e.stack.values.calculate_same(|a: Value128, b: Value128| Ok(a & !b))?,
-        SimdInstruction::V128Or => e.stack.values.calculate_same(|a: Value128, b: Value128| Ok(a | b))?,
-        SimdInstruction::V128Xor => e.stack.values.calculate_same(|a: Value128, b: Value128| Ok(a ^ b))?,
+        V128And => e.stack.values.calculate_same(|a: Value128, b: Value128| Ok(a & b))?,
+        V128AndNot => e.stack.values.calculate_same(|a: Value128, b: Value128| Ok(a & !b))?,
+        V128Or => e.stack.values.calculate_same(|a: Value128, b: Value128| Ok(a | b))?,
+        V128Xor => e.stack.values.calculate_same(|a: Value128, b: Value128| Ok(a ^ b))?,
+
+        I8x16Add => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, i8::wrapping_add)))?,
+        I16x8Add => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, i16::wrapping_add)))?,
+        I32x4Add => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, i32::wrapping_add)))?,
+        I64x2Add => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, i64::wrapping_add)))?,
+        I8x16Sub => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, i8::wrapping_sub)))?,
+        I16x8Sub => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, i16::wrapping_sub)))?,
+        I32x4Sub => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, i32::wrapping_sub)))?,
+        I64x2Sub => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, i64::wrapping_sub)))?,
+        I16x8Mul => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, i16::wrapping_mul)))?,
+        I32x4Mul => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, i32::wrapping_mul)))?,
+        I64x2Mul => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, i64::wrapping_mul)))?,
+
+        I8x16MinS => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, i8::min)))?,
+        I16x8MinS => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, i16::min)))?,
+        I32x4MinS => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, i32::min)))?,
+        I8x16MinU => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, u8::min)))?,
+        I16x8MinU => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, u16::min)))?,
+        I32x4MinU => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, u32::min)))?,
+        I8x16MaxS => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, i8::max)))?,
+        I16x8MaxS => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, i16::max)))?,
+        I32x4MaxS => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, i32::max)))?,
+        I8x16MaxU => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, u8::max)))?,
+        I16x8MaxU => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, u16::max)))?,
+        I32x4MaxU => e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, u32::max)))?,
+
+        F32x4Add => {
+            e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, |x: f32, y| (x + y).tw_canonicalize_nan(nan))))?
+        }
+        F64x2Add => {
+            e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, |x: f64, y| (x + y).tw_canonicalize_nan(nan))))?
+        }
+        F32x4Sub => {
+            e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, |x: f32, y| (x - y).tw_canonicalize_nan(nan))))?
+        }
+        F64x2Sub => {
+            e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, |x: f64, y| (x - y).tw_canonicalize_nan(nan))))?
+        }
+        F32x4Mul => {
+            e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, |x: f32, y| (x * y).tw_canonicalize_nan(nan))))?
+        }
+        F64x2Mul => {
+            e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, |x: f64, y| (x * y).tw_canonicalize_nan(nan))))?
+        }
+        F32x4Div => {
+            e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, |x: f32, y| (x / y).tw_canonicalize_nan(nan))))?
+        }
+        F64x2Div => {
+            e.stack.values.calculate_same(|a, b| Ok(zip_lanes(a, b, |x: f64, y| (x / y).tw_canonicalize_nan(nan))))?
+        }
+
+        // comparisons
+        I8x16Eq => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i8, y| x == y)))?,
+        I16x8Eq => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i16, y| x == y)))?,
+        I32x4Eq => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i32, y| x == y)))?,
+        I64x2Eq => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i64, y| x == y)))?,
+        F32x4Eq => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: f32, y| x == y)))?,
+        F64x2Eq => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: f64, y| x == y)))?,
+        I8x16Ne => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i8, y| x != y)))?,
+        I16x8Ne => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i16, y| x != y)))?,
+        I32x4Ne => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i32, y| x != y)))?,
+        I64x2Ne => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i64, y| x != y)))?,
+        F32x4Ne => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: f32, y| x != y)))?,
+        F64x2Ne => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: f64, y| x != y)))?,
+
+        I8x16LtS => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i8, y| x < y)))?,
+        I16x8LtS => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i16, y| x < y)))?,
+        I32x4LtS => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i32, y| x < y)))?,
+        I64x2LtS => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i64, y| x < y)))?,
+        I8x16LtU => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: u8, y| x < y)))?,
+        I16x8LtU => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: u16, y| x < y)))?,
+        I32x4LtU => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: u32, y| x < y)))?,
+        F32x4Lt => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: f32, y| x < y)))?,
+        F64x2Lt => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: f64, y| x < y)))?,
+
+        I8x16GtS => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i8, y| x > y)))?,
+        I16x8GtS => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i16, y| x > y)))?,
+        I32x4GtS => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i32, y| x > y)))?,
+        I64x2GtS => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i64, y| x > y)))?,
+        I8x16GtU => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: u8, y| x > y)))?,
+        I16x8GtU => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: u16, y| x > y)))?,
+        I32x4GtU => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: u32, y| x > y)))?,
+        F32x4Gt => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: f32, y| x > y)))?,
+        F64x2Gt => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: f64, y| x > y)))?,
+
+        I8x16LeS => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i8, y| x <= y)))?,
+        I16x8LeS => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i16, y| x <= y)))?,
+        I32x4LeS => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i32, y| x <= y)))?,
+        I64x2LeS => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i64, y| x <= y)))?,
+        I8x16LeU => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: u8, y| x <= y)))?,
+        I16x8LeU => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: u16, y| x <= y)))?,
+        I32x4LeU => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: u32, y| x <= y)))?,
+        F32x4Le => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: f32, y| x <= y)))?,
+        F64x2Le => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: f64, y| x <= y)))?,
+
+        I8x16GeS => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i8, y| x >= y)))?,
+        I16x8GeS => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i16, y| x >= y)))?,
+        I32x4GeS => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i32, y| x >= y)))?,
+        I64x2GeS => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: i64, y| x >= y)))?,
+        I8x16GeU => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: u8, y| x >= y)))?,
+        I16x8GeU => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: u16, y| x >= y)))?,
+        I32x4GeU => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: u32, y| x >= y)))?,
+        F32x4Ge => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: f32, y| x >= y)))?,
+        F64x2Ge => e.stack.values.calculate_same(|a, b| Ok(cmp_lanes(a, b, |x: f64, y| x >= y)))?,
+
         // ternops
-        SimdInstruction::V128Bitselect => {
+        V128Bitselect => {
             let c: Value128 = e.stack.values.pop();
-            e.stack.values.calculate(|a: Value128, b: Value128| Ok((a & b) | (!a & c)))?;
+            e.stack.values.calculate(|a: Value128, b: Value128| Ok((a & c) | (b & !c)))?;
         }
+
         // shifts
-        _ => {}
+        I8x16Shl => exec_shift(e, |a, s| map_lanes(a, |x: i8| x.wrapping_shl(s)))?,
+        I16x8Shl => exec_shift(e, |a, s| map_lanes(a, |x: i16| x.wrapping_shl(s)))?,
+        I32x4Shl => exec_shift(e, |a, s| map_lanes(a, |x: i32| x.wrapping_shl(s)))?,
+        I64x2Shl => exec_shift(e, |a, s| map_lanes(a, |x: i64| x.wrapping_shl(s)))?,
+        I8x16ShrS => exec_shift(e, |a, s| map_lanes(a, |x: i8| x.wrapping_shr(s)))?,
+        I16x8ShrS => exec_shift(e, |a, s| map_lanes(a, |x: i16| x.wrapping_shr(s)))?,
+        I32x4ShrS => exec_shift(e, |a, s| map_lanes(a, |x: i32| x.wrapping_shr(s)))?,
+        I64x2ShrS => exec_shift(e, |a, s| map_lanes(a, |x: i64| x.wrapping_shr(s)))?,
+        I8x16ShrU => exec_shift(e, |a, s| map_lanes(a, |x: u8| x.wrapping_shr(s)))?,
+        I16x8ShrU => exec_shift(e, |a, s| map_lanes(a, |x: u16| x.wrapping_shr(s)))?,
+        I32x4ShrU => exec_shift(e, |a, s| map_lanes(a, |x: u32| x.wrapping_shr(s)))?,
+        I64x2ShrU => exec_shift(e, |a, s| map_lanes(a, |x: u64| x.wrapping_shr(s)))?,
+
+        // splats
+        I8x16Splat => e.stack.values.replace_top::<i32, Value128>(|v| Ok(splat(v as i8)))?,
+        I16x8Splat => e.stack.values.replace_top::<i32, Value128>(|v| Ok(splat(v as i16)))?,
+        I32x4Splat => e.stack.values.replace_top::<i32, Value128>(|v| Ok(splat(v)))?,
+        I64x2Splat => e.stack.values.replace_top::<i64, Value128>(|v| Ok(splat(v)))?,
+        F32x4Splat => e.stack.values.replace_top::<f32, Value128>(|v| Ok(splat(v)))?,
+        F64x2Splat => e.stack.values.replace_top::<f64, Value128>(|v| Ok(splat(v)))?,
+
+        // lanes
+        I8x16ExtractLaneS(l) => e.stack.values.replace_top::<Value128, i32>(|v| Ok(lane::<i8>(v, l) as i32))?,
+        I8x16ExtractLaneU(l) => e.stack.values.replace_top::<Value128, i32>(|v| Ok(lane::<u8>(v, l) as i32))?,
+        I16x8ExtractLaneS(l) => e.stack.values.replace_top::<Value128, i32>(|v| Ok(lane::<i16>(v, l) as i32))?,
+        I16x8ExtractLaneU(l) => e.stack.values.replace_top::<Value128, i32>(|v| Ok(lane::<u16>(v, l) as i32))?,
+        I32x4ExtractLane(l) => e.stack.values.replace_top::<Value128, i32>(|v| Ok(lane::<i32>(v, l)))?,
+        I64x2ExtractLane(l) => e.stack.values.replace_top::<Value128, i64>(|v| Ok(lane::<i64>(v, l)))?,
+        F32x4ExtractLane(l) => e.stack.values.replace_top::<Value128, f32>(|v| Ok(lane::<f32>(v, l)))?,
+        F64x2ExtractLane(l) => e.stack.values.replace_top::<Value128, f64>(|v| Ok(lane::<f64>(v, l)))?,
+
+        I8x16ReplaceLane(l) => {
+            let x = e.stack.values.pop::<i32>() as i8;
+            e.stack.values.replace_top_same(|v| Ok(replace_lane(v, l, x)))?;
+        }
+        I16x8ReplaceLane(l) => {
+            let x = e.stack.values.pop::<i32>() as i16;
+            e.stack.values.replace_top_same(|v| Ok(replace_lane(v, l, x)))?;
+        }
+        I32x4ReplaceLane(l) => {
+            let x = e.stack.values.pop::<i32>();
+            e.stack.values.replace_top_same(|v| Ok(replace_lane(v, l, x)))?;
+        }
+        I64x2ReplaceLane(l) => {
+            let x = e.stack.values.pop::<i64>();
+            e.stack.values.replace_top_same(|v| Ok(replace_lane(v, l, x)))?;
+        }
+        F32x4ReplaceLane(l) => {
+            let x = e.stack.values.pop::<f32>();
+            e.stack.values.replace_top_same(|v| Ok(replace_lane(v, l, x)))?;
+        }
+        F64x2ReplaceLane(l) => {
+            let x = e.stack.values.pop::<f64>();
+            e.stack.values.replace_top_same(|v| Ok(replace_lane(v, l, x)))?;
+        }
+
+        op => return Err(Error::UnsupportedFeature(format!("simd instruction {op:?}"))),
+    }
+    Ok(())
+}
+
+fn exec_shift(e: &mut Executor<'_, '_>, shift: impl Fn(Value128, u32) -> Value128) -> Result<()> {
+    let s = e.stack.values.pop::<i32>() as u32;
+    e.stack.values.replace_top_same(|a: Value128| Ok(shift(a, s)))
+}
+
+/// Pop the address operand and compute the effective address of a memory access
+#[inline]
+fn effective_addr(e: &mut Executor<'_, '_>, m: MemoryArg, len: usize) -> Result<usize> {
+    let mem = e.store.get_mem(e.module.resolve_mem_addr(m.mem_addr()));
+    let base = e.stack.values.pop_mem_operand(mem.is_64());
+    match base.checked_add(m.offset()).map(usize::try_from) {
+        Some(Ok(addr)) => Ok(addr),
+        _ => Err(Error::Trap(Trap::MemoryOutOfBounds { offset: saturating_usize(base), len, max: mem.len() })),
     }
+}
+
+fn load<const N: usize>(e: &mut Executor<'_, '_>, m: MemoryArg) -> Result<[u8; N]> {
+    let addr = effective_addr(e, m, N)?;
+    let mem = e.store.get_mem(e.module.resolve_mem_addr(m.mem_addr()));
+    Ok(mem.load(addr, N)?.try_into().expect("invalid load size, this is a bug"))
+}
+
+fn store(e: &mut Executor<'_, '_>, m: MemoryArg, bytes: &[u8]) -> Result<()> {
+    let addr = effective_addr(e, m, bytes.len())?;
+    let mem = e.store.get_mem_mut(e.module.resolve_mem_addr(m.mem_addr()));
+    mem.store(addr, bytes.len(), bytes)
+}
+
+/// Load 64 bits and widen each lane to twice its size
+fn exec_load_extend<T: Lane, U: Lane>(e: &mut Executor<'_, '_>, m: MemoryArg, f: impl Fn(T) -> U) -> Result<()> {
+    let src = load::<8>(e, m)?;
+    let mut bytes = [0; 16];
+    bytes.chunks_exact_mut(U::SIZE).zip(src.chunks_exact(T::SIZE)).for_each(|(d, s)| f(T::read(s)).write(d));
+    e.stack.values.push::<Value128>(u128::from_le_bytes(bytes));
     Ok(())
 }
 
-// trait SimdExt {}
-// impl SimdExt for Value128 {}
+fn exec_load_splat<T: Lane, const N: usize>(e: &mut Executor<'_, '_>, m: MemoryArg) -> Result<()> {
+    let bytes = load::<N>(e, m)?;
+    e.stack.values.push::<Value128>(splat(T::read(&bytes)));
+    Ok(())
+}
+
+fn exec_load_lane<T: Lane, const N: usize>(e: &mut Executor<'_, '_>, m: MemoryArg, l: u8) -> Result<()> {
+    let v = e.stack.values.pop::<Value128>();
+    let bytes = load::<N>(e, m)?;
+    e.stack.values.push::<Value128>(replace_lane(v, l, T::read(&bytes)));
+    Ok(())
+}
+
+fn exec_store_lane<T: Lane>(e: &mut Executor<'_, '_>, m: MemoryArg, l: u8) -> Result<()> {
+    let v = e.stack.values.pop::<Value128>();
+    let start = l as usize * T::SIZE;
+    store(e, m, &v.to_le_bytes()[start..start + T::SIZE])
+}
+
+/// A single lane of a v128 value
+trait Lane: Copy {
+    const SIZE: usize;
+    fn read(bytes: &[u8]) -> Self;
+    fn write(self, bytes: &mut [u8]);
+}
+
+macro_rules! impl_lane {
+    ($($t:ty)*) => ($(
+        impl Lane for $t {
+            const SIZE: usize = core::mem::size_of::<$t>();
+
+            #[inline(always)]
+            fn read(bytes: &[u8]) -> Self {
+                <$t>::from_le_bytes(bytes.try_into().expect("invalid lane size, this is a bug"))
+            }
+
+            #[inline(always)]
+            fn write(self, bytes: &mut [u8]) {
+                bytes.copy_from_slice(&self.to_le_bytes());
+            }
+        }
+    )*)
+}
+
+impl_lane! { i8 u8 i16 u16 i32 u32 i64 u64 f32 f64 }
+
+#[inline]
+fn map_lanes<T: Lane>(a: Value128, f: impl Fn(T) -> T) -> Value128 {
+    let mut bytes = a.to_le_bytes();
+    bytes.chunks_exact_mut(T::SIZE).for_each(|l| f(T::read(l)).write(l));
+    u128::from_le_bytes(bytes)
+}
+
+#[inline]
+fn zip_lanes<T: Lane>(a: Value128, b: Value128, f: impl Fn(T, T) -> T) -> Value128 {
+    let (mut a, b) = (a.to_le_bytes(), b.to_le_bytes());
+    a.chunks_exact_mut(T::SIZE).zip(b.chunks_exact(T::SIZE)).for_each(|(x, y)| f(T::read(x), T::read(y)).write(x));
+    u128::from_le_bytes(a)
+}
+
+/// Lane-wise comparison, each lane of the result is either all ones or all zeros
+#[inline]
+fn cmp_lanes<T: Lane>(a: Value128, b: Value128, f: impl Fn(T, T) -> bool) -> Value128 {
+    let (mut a, b) = (a.to_le_bytes(), b.to_le_bytes());
+    a.chunks_exact_mut(T::SIZE).zip(b.chunks_exact(T::SIZE)).for_each(|(x, y)| {
+        let res = if f(T::read(x), T::read(y)) { 0xff } else { 0 };
+        x.fill(res);
+    });
+    u128::from_le_bytes(a)
+}
+
+#[inline]
+fn all_true<T: Lane>(a: Value128) -> bool {
+    a.to_le_bytes().chunks_exact(T::SIZE).all(|l| l.iter().any(|b| *b != 0))
+}
+
+#[inline]
+fn splat<T: Lane>(v: T) -> Value128 {
+    let mut bytes = [0; 16];
+    bytes.chunks_exact_mut(T::SIZE).for_each(|l| v.write(l));
+    u128::from_le_bytes(bytes)
+}
+
+#[inline]
+fn lane<T: Lane>(v: Value128, idx: u8) -> T {
+    let start = idx as usize * T::SIZE;
+    T::read(&v.to_le_bytes()[start..start + T::SIZE])
+}
+
+#[inline]
+fn replace_lane<T: Lane>(v: Value128, idx: u8, x: T) -> Value128 {
+    let mut bytes = v.to_le_bytes();
+    let start = idx as usize * T::SIZE;
+    x.write(&mut bytes[start..start + T::SIZE]);
+    u128::from_le_bytes(bytes)
+}
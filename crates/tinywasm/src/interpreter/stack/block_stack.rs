@@ -41,6 +41,11 @@ impl BlockStack {
         self.0.pop().expect("block stack underflow, this is a bug")
     }
 
+    #[inline]
+    pub(crate) fn clear(&mut self) {
+        self.0.clear();
+    }
+
     /// keep the top `len` blocks and discard the rest
     #[inline(always)]
     pub(crate) fn truncate(&mut self, len: u32) {
@@ -53,7 +58,7 @@ pub(crate) struct BlockFrame {
     pub(crate) instr_ptr: usize, // position of the instruction pointer when the block was entered
     pub(crate) end_instr_offset: u32, // position of the end instruction of the block
 
-    pub(crate) stack_ptr: StackLocation, // stack pointer when the block was entered
+    pub(crate) stack_ptr: StackLocation, // stack pointer when the block was entered, below its params
     pub(crate) results: StackHeight,
     pub(crate) params: StackHeight,
 
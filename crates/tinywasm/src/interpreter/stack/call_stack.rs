@@ -6,20 +6,43 @@ use crate::Trap;
 use crate::{unlikely, Error};
 
 use alloc::boxed::Box;
-use alloc::{rc::Rc, vec, vec::Vec};
-use tinywasm_types::{Instruction, LocalAddr, ModuleInstanceAddr, WasmFunction, WasmValue};
+use alloc::{sync::Arc, vec, vec::Vec};
+use tinywasm_types::{FuncAddr, Instruction, LocalAddr, ModuleInstanceAddr, WasmFunction, WasmFunctionData, WasmValue};
 
 pub(crate) const MAX_CALL_STACK_SIZE: usize = 1024;
 
+/// Upper bound for the configurable call depth, regardless of what the store allows
+pub(crate) const INTERNAL_MAX_CALL_STACK_SIZE: usize = 1 << 16;
+
 #[derive(Debug)]
 pub(crate) struct CallStack {
     stack: Vec<CallFrame>,
+    max_depth: usize,
 }
 
 impl CallStack {
     #[inline]
-    pub(crate) fn new(initial_frame: CallFrame) -> Self {
-        Self { stack: vec![initial_frame] }
+    pub(crate) fn new(initial_frame: CallFrame, max_depth: usize) -> Self {
+        Self { stack: vec![initial_frame], max_depth }
+    }
+
+    #[inline]
+    pub(crate) fn reset(&mut self, initial_frame: CallFrame, max_depth: usize) {
+        self.stack.clear();
+        self.stack.push(initial_frame);
+        self.max_depth = max_depth;
+    }
+
+    #[cold]
+    fn overflow(&self) -> Error {
+        let internal = self.max_depth > INTERNAL_MAX_CALL_STACK_SIZE;
+        let max_depth = if internal { INTERNAL_MAX_CALL_STACK_SIZE } else { self.max_depth };
+        Trap::CallStackOverflow { max_depth, internal }.into()
+    }
+
+    /// The suspended frames, outermost first
+    pub(crate) fn frames(&self) -> &[CallFrame] {
+        &self.stack
     }
 
     #[inline]
@@ -29,8 +52,8 @@ impl CallStack {
 
     #[inline]
     pub(crate) fn push(&mut self, call_frame: CallFrame) -> ControlFlow<Option<Error>> {
-        if unlikely((self.stack.len() + 1) >= MAX_CALL_STACK_SIZE) {
-            return ControlFlow::Break(Some(Trap::CallStackOverflow.into()));
+        if unlikely((self.stack.len() + 1) >= self.max_depth.min(INTERNAL_MAX_CALL_STACK_SIZE)) {
+            return ControlFlow::Break(Some(self.overflow()));
         }
         self.stack.push(call_frame);
         ControlFlow::Continue(())
@@ -40,8 +63,10 @@ impl CallStack {
 #[derive(Debug)]
 pub(crate) struct CallFrame {
     instr_ptr: usize,
-    func_instance: Rc<WasmFunction>,
+    func_instance: Arc<WasmFunction>,
+    func_addr: FuncAddr,
     block_ptr: u32,
+    stack_ptr: StackLocation, // value stack height when the function was entered (excluding params)
     module_addr: ModuleInstanceAddr,
     pub(crate) locals: Locals,
 }
@@ -80,6 +105,11 @@ impl CallFrame {
         self.instr_ptr += offset;
     }
 
+    #[inline]
+    pub(crate) fn func_addr(&self) -> FuncAddr {
+        self.func_addr
+    }
+
     #[inline]
     pub(crate) fn module_addr(&self) -> ModuleInstanceAddr {
         self.module_addr
@@ -90,6 +120,22 @@ impl CallFrame {
         self.block_ptr
     }
 
+    #[inline]
+    pub(crate) fn stack_ptr(&self) -> StackLocation {
+        self.stack_ptr
+    }
+
+    /// The stack space taken up by the function's results
+    #[inline]
+    pub(crate) fn results(&self) -> StackHeight {
+        StackHeight::from(&self.func_instance.ty.results[..])
+    }
+
+    #[inline]
+    pub(crate) fn data(&self) -> &WasmFunctionData {
+        &self.func_instance.data
+    }
+
     #[inline(always)]
     pub(crate) fn fetch_instr(&self) -> &Instruction {
         match self.func_instance.instructions.get(self.instr_ptr) {
@@ -145,7 +191,8 @@ impl CallFrame {
 
     #[inline]
     pub(crate) fn new(
-        wasm_func_inst: Rc<WasmFunction>,
+        wasm_func_inst: Arc<WasmFunction>,
+        func_addr: FuncAddr,
         owner: ModuleInstanceAddr,
         params: &[WasmValue],
         block_ptr: u32,
@@ -182,17 +229,35 @@ impl CallFrame {
             }
         };
 
-        Self { instr_ptr: 0, func_instance: wasm_func_inst, module_addr: owner, block_ptr, locals }
+        Self {
+            instr_ptr: 0,
+            func_instance: wasm_func_inst,
+            func_addr,
+            module_addr: owner,
+            block_ptr,
+            stack_ptr: StackLocation::default(),
+            locals,
+        }
     }
 
     #[inline]
     pub(crate) fn new_raw(
-        wasm_func_inst: Rc<WasmFunction>,
+        wasm_func_inst: Arc<WasmFunction>,
+        func_addr: FuncAddr,
         owner: ModuleInstanceAddr,
         locals: Locals,
         block_ptr: u32,
+        stack_ptr: StackLocation,
     ) -> Self {
-        Self { instr_ptr: 0, func_instance: wasm_func_inst, module_addr: owner, block_ptr, locals }
+        Self {
+            instr_ptr: 0,
+            func_instance: wasm_func_inst,
+            func_addr,
+            module_addr: owner,
+            block_ptr,
+            stack_ptr,
+            locals,
+        }
     }
 
     #[inline]
@@ -3,7 +3,7 @@ mod call_stack;
 mod value_stack;
 
 pub(crate) use block_stack::{BlockFrame, BlockStack, BlockType};
-pub(crate) use call_stack::{CallFrame, CallStack, Locals};
+pub(crate) use call_stack::{CallFrame, CallStack, Locals, MAX_CALL_STACK_SIZE};
 pub(crate) use value_stack::ValueStack;
 
 /// A WebAssembly Stack
@@ -15,7 +15,18 @@ pub(crate) struct Stack {
 }
 
 impl Stack {
-    pub(crate) fn new(call_frame: CallFrame) -> Self {
-        Self { values: ValueStack::new(), blocks: BlockStack::default(), call_stack: CallStack::new(call_frame) }
+    pub(crate) fn new(call_frame: CallFrame, max_call_depth: usize) -> Self {
+        Self {
+            values: ValueStack::new(),
+            blocks: BlockStack::default(),
+            call_stack: CallStack::new(call_frame, max_call_depth),
+        }
+    }
+
+    /// Start over with a new call frame, keeping the allocated buffers
+    pub(crate) fn reset(&mut self, call_frame: CallFrame, max_call_depth: usize) {
+        self.values.clear();
+        self.blocks.clear();
+        self.call_stack.reset(call_frame, max_call_depth);
     }
 }
@@ -1,5 +1,5 @@
 use alloc::vec::Vec;
-use tinywasm_types::{ExternRef, FuncRef, ValType, ValueCounts, ValueCountsSmall, WasmValue};
+use tinywasm_types::{ExternRef, FuncRef, I31Ref, ValType, ValueCounts, ValueCountsSmall, WasmValue};
 
 use crate::{interpreter::*, Result};
 
@@ -27,6 +27,13 @@ impl ValueStack {
         }
     }
 
+    pub(crate) fn clear(&mut self) {
+        self.stack_32.clear();
+        self.stack_64.clear();
+        self.stack_128.clear();
+        self.stack_ref.clear();
+    }
+
     pub(crate) fn height(&self) -> StackLocation {
         StackLocation {
             s32: self.stack_32.len() as u32,
@@ -36,6 +43,17 @@ impl ValueStack {
         }
     }
 
+    /// The stack location below the topmost `values`
+    #[inline]
+    pub(crate) fn height_below(&self, values: StackHeight) -> StackLocation {
+        StackLocation {
+            s32: self.stack_32.len() as u32 - u32::from(values.s32),
+            s64: self.stack_64.len() as u32 - u32::from(values.s64),
+            s128: self.stack_128.len() as u32 - u32::from(values.s128),
+            sref: self.stack_ref.len() as u32 - u32::from(values.sref),
+        }
+    }
+
     #[inline]
     pub(crate) fn peek<T: InternalValue>(&self) -> T {
         T::stack_peek(self)
@@ -51,6 +69,26 @@ impl ValueStack {
         T::stack_push(self, value);
     }
 
+    /// Pop an address, size or page count operand of a memory, an `i64` for 64-bit memories
+    #[inline]
+    pub(crate) fn pop_mem_operand(&mut self, is_64: bool) -> u64 {
+        if is_64 {
+            self.pop::<i64>() as u64
+        } else {
+            self.pop::<i32>() as u32 as u64
+        }
+    }
+
+    /// Push an address, size or page count result of a memory, an `i64` for 64-bit memories
+    #[inline]
+    pub(crate) fn push_mem_operand(&mut self, value: i64, is_64: bool) {
+        if is_64 {
+            self.push::<i64>(value);
+        } else {
+            self.push::<i32>(value as i32);
+        }
+    }
+
     #[inline]
     pub(crate) fn drop<T: InternalValue>(&mut self) {
         T::stack_pop(self);
@@ -98,7 +136,10 @@ impl ValueStack {
     }
 
     pub(crate) fn pop_params(&mut self, val_types: &[ValType]) -> Vec<WasmValue> {
-        val_types.iter().map(|val_type| self.pop_wasmvalue(*val_type)).collect::<Vec<_>>()
+        // the last param is on top of the stack
+        let mut params = val_types.iter().rev().map(|val_type| self.pop_wasmvalue(*val_type)).collect::<Vec<_>>();
+        params.reverse();
+        params
     }
 
     pub(crate) fn pop_results(&mut self, val_types: &[ValType]) -> Vec<WasmValue> {
@@ -175,6 +216,9 @@ impl ValueStack {
             ValType::F64 => WasmValue::F64(self.pop()),
             ValType::RefExtern => WasmValue::RefExtern(ExternRef::new(self.pop())),
             ValType::RefFunc => WasmValue::RefFunc(FuncRef::new(self.pop())),
+            ValType::RefI31 => {
+                WasmValue::RefI31(self.pop::<ValueRef>().map_or(I31Ref::null(), |v| I31Ref::new(v as i32)))
+            }
         }
     }
 
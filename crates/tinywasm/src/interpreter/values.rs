@@ -1,5 +1,5 @@
 use crate::Result;
-use tinywasm_types::{ExternRef, FuncRef, LocalAddr, ValType, WasmValue};
+use tinywasm_types::{ExternRef, FuncRef, I31Ref, LocalAddr, ValType, WasmValue};
 
 use super::stack::{Locals, ValueStack};
 
@@ -21,7 +21,7 @@ pub enum TinyWasmValue {
     ValueRef(ValueRef),
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub(crate) struct StackLocation {
     pub(crate) s32: u32,
     pub(crate) s64: u32,
@@ -43,7 +43,7 @@ impl From<ValType> for StackHeight {
             ValType::I32 | ValType::F32 => Self { s32: 1, ..Default::default() },
             ValType::I64 | ValType::F64 => Self { s64: 1, ..Default::default() },
             ValType::V128 => Self { s128: 1, ..Default::default() },
-            ValType::RefExtern | ValType::RefFunc => Self { sref: 1, ..Default::default() },
+            ValType::RefExtern | ValType::RefFunc | ValType::RefI31 => Self { sref: 1, ..Default::default() },
         }
     }
 }
@@ -59,7 +59,7 @@ impl From<&[ValType]> for StackHeight {
                 ValType::I32 | ValType::F32 => s32 += 1,
                 ValType::I64 | ValType::F64 => s64 += 1,
                 ValType::V128 => s128 += 1,
-                ValType::RefExtern | ValType::RefFunc => sref += 1,
+                ValType::RefExtern | ValType::RefFunc | ValType::RefI31 => sref += 1,
             }
         }
         Self { s32, s64, s128, sref }
@@ -109,6 +109,7 @@ impl TinyWasmValue {
             ValType::V128 => WasmValue::V128(self.unwrap_128()),
             ValType::RefExtern => WasmValue::RefExtern(ExternRef::new(self.unwrap_ref())),
             ValType::RefFunc => WasmValue::RefFunc(FuncRef::new(self.unwrap_ref())),
+            ValType::RefI31 => WasmValue::RefI31(self.unwrap_ref().map_or(I31Ref::null(), |v| I31Ref::new(v as i32))),
         }
     }
 }
@@ -123,6 +124,7 @@ impl From<&WasmValue> for TinyWasmValue {
             WasmValue::F64(v) => TinyWasmValue::Value64(v.to_bits()),
             WasmValue::RefExtern(v) => TinyWasmValue::ValueRef(v.addr()),
             WasmValue::RefFunc(v) => TinyWasmValue::ValueRef(v.addr()),
+            WasmValue::RefI31(v) => TinyWasmValue::ValueRef(v.get_u()),
         }
     }
 }
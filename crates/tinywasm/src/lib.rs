@@ -22,6 +22,12 @@
 //!  Enables the `tinywasm-parser` crate. This is enabled by default.
 //!- **`archive`**\
 //!  Enables pre-parsing of archives. This is enabled by default.
+//!- **`serde`**\
+//!  Implements `Serialize` and `Deserialize` for [`WasmValue`](types::WasmValue) and [`ValType`](types::ValType).
+//!- **`trace`**\
+//!  Enables [`Store::set_trace_hook`] for observing every executed instruction.
+//!- **`wasi`**\
+//!  Enables the [`wasi`] module, a minimal shim for `wasi_snapshot_preview1`.
 //!
 //! With all these features disabled, `TinyWasm` only depends on `core`, `alloc` and `libm`.
 //! By disabling `std`, you can use `TinyWasm` in `no_std` environments. This requires
@@ -92,7 +98,10 @@ pub(crate) mod log {
 
 mod error;
 pub use error::*;
-pub use func::{FuncHandle, FuncHandleTyped};
+pub use func::{
+    try_into_array, CallContext, FromWasmValueTuple, FuncHandle, FuncHandleTyped, IntoWasmValueTuple, ToValType,
+    ValTypesFromTuple,
+};
 pub use imports::*;
 pub use instance::ModuleInstance;
 pub use module::Module;
@@ -106,6 +115,9 @@ mod module;
 mod reference;
 mod store;
 
+#[cfg(feature = "wasi")]
+pub mod wasi;
+
 /// Runtime for executing WebAssembly modules.
 pub mod interpreter;
 pub use interpreter::InterpreterRuntime;
@@ -116,6 +128,11 @@ pub mod parser {
     pub use tinywasm_parser::*;
 }
 
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::vec::Vec;
+}
+
 /// Re-export of [`tinywasm_types`].
 pub mod types {
     pub use tinywasm_types::*;
@@ -1,5 +1,5 @@
 use crate::{Imports, ModuleInstance, Result, Store};
-use tinywasm_types::TinyWasmModule;
+use tinywasm_types::{FuncAddr, FuncType, ImportKind, TinyWasmModule};
 
 /// A WebAssembly Module
 ///
@@ -44,6 +44,43 @@ impl Module {
         Ok(data.into())
     }
 
+    /// Get a reference to the parsed module
+    ///
+    /// The layout of [`TinyWasmModule`] follows tinywasm's internal representation and may change
+    /// between minor versions, so only rely on it for tooling that is pinned to a specific version.
+    pub fn inner(&self) -> &TinyWasmModule {
+        &self.0
+    }
+
+    /// Consume the module and return the parsed module
+    ///
+    /// See [`Module::inner`] for stability expectations.
+    pub fn into_inner(self) -> TinyWasmModule {
+        self.0
+    }
+
+    /// Get the function types from the module's type section, indexed by type index
+    pub fn func_types(&self) -> &[FuncType] {
+        &self.0.func_types
+    }
+
+    /// Get the type of a function by its index in the module, or `None` if there is no such function
+    ///
+    /// Like in wasm itself, imported functions come first, followed by the functions the module defines.
+    pub fn function_signature(&self, index: FuncAddr) -> Option<&FuncType> {
+        let imported_funcs = || {
+            self.0.imports.iter().filter_map(|import| match import.kind {
+                ImportKind::Function(ty) => Some(ty),
+                _ => None,
+            })
+        };
+
+        match (index as usize).checked_sub(imported_funcs().count()) {
+            Some(idx) => self.0.funcs.get(idx).map(|func| &func.ty),
+            None => self.0.func_types.get(imported_funcs().nth(index as usize)? as usize),
+        }
+    }
+
     /// Instantiate the module in the given store
     ///
     /// Runs the start function if it exists
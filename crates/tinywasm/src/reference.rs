@@ -1,10 +1,12 @@
 use core::ffi::CStr;
 
 use alloc::ffi::CString;
+use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
-use crate::{MemoryInstance, Result};
+use crate::{Error, MemoryInstance, MemorySnapshot, Result, TableElement, TableInstance, Trap};
+use tinywasm_types::{ValType, WasmValue};
 
 // This module essentially contains the public APIs to interact with the data stored in the store
 
@@ -21,6 +23,10 @@ impl MemoryRefLoad for MemoryRef<'_> {
     fn load(&self, offset: usize, len: usize) -> Result<&[u8]> {
         self.0.load(offset, len)
     }
+
+    fn data_len(&self) -> usize {
+        self.0.len()
+    }
 }
 
 impl MemoryRefLoad for MemoryRefMut<'_> {
@@ -28,6 +34,10 @@ impl MemoryRefLoad for MemoryRefMut<'_> {
     fn load(&self, offset: usize, len: usize) -> Result<&[u8]> {
         self.0.load(offset, len)
     }
+
+    fn data_len(&self) -> usize {
+        self.0.len()
+    }
 }
 
 impl MemoryRef<'_> {
@@ -36,10 +46,28 @@ impl MemoryRef<'_> {
         self.0.load(offset, len)
     }
 
+    /// Get the entire contents of the memory
+    ///
+    /// The slice is `page_count * page_size` bytes long (64 KiB pages unless the module
+    /// declares a custom page size).
+    pub fn data(&self) -> &[u8] {
+        &self.0.data
+    }
+
+    /// Load a little-endian value from memory
+    pub fn load_as<T: MemoryValue>(&self, offset: usize) -> Result<T> {
+        self.load(offset, T::SIZE).map(T::from_le_slice)
+    }
+
     /// Load a slice of memory as a vector
     pub fn load_vec(&self, offset: usize, len: usize) -> Result<Vec<u8>> {
         self.load(offset, len).map(<[u8]>::to_vec)
     }
+
+    /// Copy the memory's contents and size into a snapshot
+    pub fn snapshot(&self) -> MemorySnapshot {
+        self.0.snapshot()
+    }
 }
 
 impl MemoryRefMut<'_> {
@@ -48,14 +76,41 @@ impl MemoryRefMut<'_> {
         self.0.load(offset, len)
     }
 
+    /// Get the entire contents of the memory, see [`MemoryRef::data`]
+    pub fn data(&self) -> &[u8] {
+        &self.0.data
+    }
+
+    /// Get the entire contents of the memory as a mutable slice, e.g. to copy a whole buffer at once
+    ///
+    /// Indexing the slice is bounds checked by Rust instead of trapping, so out of bounds
+    /// accesses panic. The slice is `page_count * page_size` bytes long.
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.0.data
+    }
+
+    /// Load a little-endian value from memory
+    pub fn load_as<T: MemoryValue>(&self, offset: usize) -> Result<T> {
+        self.load(offset, T::SIZE).map(T::from_le_slice)
+    }
+
+    /// Store a value in memory in little-endian byte order
+    pub fn store_as<T: MemoryValue>(&mut self, offset: usize, val: T) -> Result<()> {
+        let mut bytes = [0; 16];
+        val.write_le(&mut bytes[..T::SIZE]);
+        self.store(offset, T::SIZE, &bytes[..T::SIZE])
+    }
+
     /// Load a slice of memory as a vector
     pub fn load_vec(&self, offset: usize, len: usize) -> Result<Vec<u8>> {
         self.load(offset, len).map(<[u8]>::to_vec)
     }
 
     /// Grow the memory by the given number of pages
+    ///
+    /// Returns the previous number of pages, or `None` if the memory can't grow that far or `delta_pages` is negative.
     pub fn grow(&mut self, delta_pages: i32) -> Option<i32> {
-        self.0.grow(delta_pages)
+        self.0.grow(delta_pages.into()).map(|prev| prev as i32)
     }
 
     /// Get the current size of the memory in pages
@@ -77,11 +132,138 @@ impl MemoryRefMut<'_> {
     pub fn store(&mut self, offset: usize, len: usize, data: &[u8]) -> Result<()> {
         self.0.store(offset, len, data)
     }
+
+    /// Copy the memory's contents and size into a snapshot
+    pub fn snapshot(&self) -> MemorySnapshot {
+        self.0.snapshot()
+    }
+
+    /// Reset the memory to a snapshot, including its page count
+    ///
+    /// Fails if the snapshot was taken from a memory of a different type.
+    pub fn restore(&mut self, snapshot: &MemorySnapshot) -> Result<()> {
+        self.0.restore(snapshot)
+    }
+}
+
+/// A borrowed reference to a table instance
+#[derive(Debug)]
+pub struct TableRef<'a>(pub(crate) &'a TableInstance);
+
+/// A borrowed reference to a table instance
+#[derive(Debug)]
+pub struct TableRefMut<'a> {
+    pub(crate) table: &'a mut TableInstance,
+    pub(crate) func_count: usize, // number of functions in the store, to reject dangling funcrefs
 }
 
+impl TableRef<'_> {
+    /// Get the element at `idx`, or `None` if it's out of bounds
+    ///
+    /// Elements are returned as [`WasmValue::RefFunc`] or [`WasmValue::RefExtern`] depending on the table type.
+    pub fn get(&self, idx: u32) -> Option<WasmValue> {
+        self.0.get_wasm_val(idx).ok()
+    }
+
+    /// Get the current number of elements
+    pub fn size(&self) -> u32 {
+        self.0.elements.len() as u32
+    }
+
+    /// Get the type of the table's elements
+    pub fn element_type(&self) -> ValType {
+        self.0.kind.element_type
+    }
+}
+
+impl TableRefMut<'_> {
+    /// Get the element at `idx`, or `None` if it's out of bounds, see [`TableRef::get`]
+    pub fn get(&self, idx: u32) -> Option<WasmValue> {
+        self.table.get_wasm_val(idx).ok()
+    }
+
+    /// Get the current number of elements
+    pub fn size(&self) -> u32 {
+        self.table.elements.len() as u32
+    }
+
+    /// Get the type of the table's elements
+    pub fn element_type(&self) -> ValType {
+        self.table.kind.element_type
+    }
+
+    /// Set the element at `idx`
+    ///
+    /// Fails with [`Trap::TableOutOfBounds`] if `idx` is out of bounds. Values that don't match
+    /// the table type or refer to a function that doesn't exist are rejected as well.
+    pub fn set(&mut self, idx: u32, value: WasmValue) -> Result<()> {
+        let element = self.element(value)?;
+        self.table.set(idx, element)
+    }
+
+    /// Grow the table by `delta` elements initialized to `init`, returning the previous size
+    ///
+    /// Fails with [`Trap::TableOutOfBounds`] if the table would exceed its maximum size.
+    pub fn grow(&mut self, delta: u32, init: WasmValue) -> Result<u32> {
+        let element = self.element(init)?;
+        let prev = self.size();
+        let delta = i32::try_from(delta).map_err(|_| self.table.trap_oob(prev as usize, delta as usize))?;
+        self.table.grow(delta, element, None)?;
+        Ok(prev)
+    }
+
+    fn element(&self, value: WasmValue) -> Result<TableElement> {
+        let addr = match (self.table.kind.element_type, value) {
+            (ValType::RefFunc, WasmValue::RefFunc(func)) => func.addr(),
+            (ValType::RefExtern, WasmValue::RefExtern(ext)) => ext.addr(),
+            (ValType::RefI31, WasmValue::RefI31(i31)) => i31.get_u(),
+            (ty, value) => {
+                return Err(Error::Other(format!("table element type mismatch: expected {ty:?}, got {value:?}")))
+            }
+        };
+        if matches!(value, WasmValue::RefFunc(_)) && addr.is_some_and(|addr| addr as usize >= self.func_count) {
+            return Err(Error::Other(format!("function {} not found", addr.unwrap_or_default())));
+        }
+        Ok(addr.into())
+    }
+}
+
+/// A value with a fixed-size little-endian representation in memory
+///
+/// Used by [`MemoryRef::load_as`], [`MemoryRefMut::load_as`] and [`MemoryRefMut::store_as`].
+pub trait MemoryValue: Copy {
+    /// The size of the value in bytes, at most 16
+    const SIZE: usize;
+
+    /// Read the value from exactly [`Self::SIZE`] bytes
+    fn from_le_slice(bytes: &[u8]) -> Self;
+
+    /// Write the value to exactly [`Self::SIZE`] bytes
+    fn write_le(self, bytes: &mut [u8]);
+}
+
+macro_rules! impl_memory_value {
+    ($($t:ty),*) => ($(
+        impl MemoryValue for $t {
+            const SIZE: usize = core::mem::size_of::<$t>();
+
+            fn from_le_slice(bytes: &[u8]) -> Self {
+                <$t>::from_le_bytes(bytes.try_into().expect("slice length must match the value size"))
+            }
+
+            fn write_le(self, bytes: &mut [u8]) {
+                bytes.copy_from_slice(&self.to_le_bytes());
+            }
+        }
+    )*)
+}
+
+impl_memory_value!(u8, i8, u16, i16, u32, i32, u64, i64, f32, f64, u128, i128);
+
 #[doc(hidden)]
 pub trait MemoryRefLoad {
     fn load(&self, offset: usize, len: usize) -> Result<&[u8]>;
+    fn data_len(&self) -> usize;
     fn load_vec(&self, offset: usize, len: usize) -> Result<Vec<u8>> {
         self.load(offset, len).map(<[u8]>::to_vec)
     }
@@ -103,8 +285,26 @@ pub trait MemoryStringExt: MemoryRefLoad {
 
     /// Load a UTF-8 string from memory
     fn load_string(&self, offset: usize, len: usize) -> Result<String> {
-        let bytes = self.load(offset, len)?;
-        String::from_utf8(bytes.to_vec()).map_err(|_| crate::Error::Other("Invalid UTF-8 string".to_string()))
+        self.load_utf8(offset, len).map(ToString::to_string)
+    }
+
+    /// Load a borrowed UTF-8 string from memory
+    ///
+    /// Fails with [`Error::InvalidUtf8`] if the bytes aren't valid UTF-8.
+    fn load_utf8(&self, offset: usize, len: usize) -> Result<&str> {
+        core::str::from_utf8(self.load(offset, len)?).map_err(Error::InvalidUtf8)
+    }
+
+    /// Load a nul-terminated UTF-8 string from memory, without the terminator
+    ///
+    /// Reading past the end of memory without finding a nul byte traps with [`Trap::MemoryOutOfBounds`].
+    fn load_str_until_nul(&self, offset: usize) -> Result<&str> {
+        let max = self.data_len();
+        let bytes = self.load(offset, max.saturating_sub(offset))?;
+        let Some(len) = bytes.iter().position(|b| *b == 0) else {
+            return Err(Trap::MemoryOutOfBounds { offset, len: bytes.len() + 1, max }.into());
+        };
+        core::str::from_utf8(&bytes[..len]).map_err(Error::InvalidUtf8)
     }
 
     /// Load a C-style string from memory
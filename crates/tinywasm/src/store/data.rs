@@ -7,12 +7,12 @@ use tinywasm_types::*;
 #[derive(Debug)]
 pub(crate) struct DataInstance {
     pub(crate) data: Option<Vec<u8>>,
-    pub(crate) _owner: ModuleInstanceAddr, // index into store.module_instances
+    pub(crate) owner: ModuleInstanceAddr, // index into store.module_instances
 }
 
 impl DataInstance {
     pub(crate) fn new(data: Option<Vec<u8>>, owner: ModuleInstanceAddr) -> Self {
-        Self { data, _owner: owner }
+        Self { data, owner }
     }
 
     pub(crate) fn drop(&mut self) {
@@ -1,5 +1,5 @@
 use crate::Function;
-use alloc::rc::Rc;
+use alloc::sync::Arc;
 use tinywasm_types::*;
 
 #[derive(Debug, Clone)]
@@ -12,7 +12,7 @@ pub(crate) struct FunctionInstance {
 }
 
 impl FunctionInstance {
-    pub(crate) fn new_wasm(func: WasmFunction, owner: ModuleInstanceAddr) -> Self {
-        Self { func: Function::Wasm(Rc::new(func)), owner }
+    pub(crate) fn new_wasm(func: Arc<WasmFunction>, owner: ModuleInstanceAddr) -> Self {
+        Self { func: Function::Wasm(func), owner }
     }
 }
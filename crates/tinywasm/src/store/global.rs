@@ -9,11 +9,11 @@ use tinywasm_types::*;
 pub(crate) struct GlobalInstance {
     pub(crate) value: Cell<TinyWasmValue>,
     pub(crate) ty: GlobalType,
-    pub(crate) _owner: ModuleInstanceAddr, // index into store.module_instances
+    pub(crate) owner: ModuleInstanceAddr, // index into store.module_instances
 }
 
 impl GlobalInstance {
     pub(crate) fn new(ty: GlobalType, value: TinyWasmValue, owner: ModuleInstanceAddr) -> Self {
-        Self { ty, value: value.into(), _owner: owner }
+        Self { ty, value: value.into(), owner }
     }
 }
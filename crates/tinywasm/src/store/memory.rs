@@ -1,6 +1,7 @@
-use alloc::vec;
+use alloc::format;
+use alloc::string::ToString;
 use alloc::vec::Vec;
-use tinywasm_types::{MemoryType, ModuleInstanceAddr};
+use tinywasm_types::{MemoryArch, MemoryType, ModuleInstanceAddr};
 
 use crate::{cold, log, Error, Result};
 
@@ -12,20 +13,45 @@ pub(crate) struct MemoryInstance {
     pub(crate) kind: MemoryType,
     pub(crate) data: Vec<u8>,
     pub(crate) page_count: usize,
-    pub(crate) _owner: ModuleInstanceAddr, // index into store.module_instances
+    pub(crate) owner: ModuleInstanceAddr, // index into store.module_instances
+}
+
+/// A copy of a memory's contents and size
+///
+/// Created with [`crate::MemoryRef::snapshot`] and restored with [`crate::MemoryRefMut::restore`].
+#[derive(Debug, Clone)]
+pub struct MemorySnapshot {
+    kind: MemoryType,
+    data: Vec<u8>,
+    page_count: usize,
+}
+
+impl MemorySnapshot {
+    /// Get the number of pages the memory had when the snapshot was taken
+    pub fn page_count(&self) -> usize {
+        self.page_count
+    }
+
+    /// Get the contents of the memory when the snapshot was taken
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
 }
 
 impl MemoryInstance {
-    pub(crate) fn new(kind: MemoryType, owner: ModuleInstanceAddr) -> Self {
+    pub(crate) fn new(kind: MemoryType, owner: ModuleInstanceAddr) -> Result<Self> {
         assert!(kind.page_count_initial() <= kind.page_count_max());
         log::debug!("initializing memory with {} pages of {} bytes", kind.page_count_initial(), kind.page_size());
 
-        Self {
-            kind,
-            data: vec![0; kind.initial_size() as usize],
-            page_count: kind.page_count_initial() as usize,
-            _owner: owner,
-        }
+        // 64-bit memories can be larger than the host can address or allocate, so this has to fail instead of aborting
+        let pages = kind.page_count_initial();
+        let too_large = || Error::Other(format!("memory of {pages} pages can't be allocated on this host"));
+        let size = usize::try_from(kind.initial_size()).map_err(|_| too_large())?;
+        let mut data = Vec::new();
+        data.try_reserve_exact(size).map_err(|_| too_large())?;
+        data.resize(size, 0);
+
+        Ok(Self { kind, data, page_count: kind.page_count_initial() as usize, owner })
     }
 
     #[inline(always)]
@@ -33,12 +59,28 @@ impl MemoryInstance {
         self.data.len()
     }
 
+    /// Whether addresses, sizes and page counts of this memory are `i64` values
+    #[inline(always)]
+    pub(crate) fn is_64(&self) -> bool {
+        self.kind.arch() == MemoryArch::I64
+    }
+
     #[inline(never)]
     #[cold]
     fn trap_oob(&self, addr: usize, len: usize) -> Error {
         Error::Trap(crate::Trap::MemoryOutOfBounds { offset: addr, len, max: self.data.len() })
     }
 
+    /// Check that `addr` is a multiple of `align`, as required by atomic memory accesses
+    #[inline(always)]
+    pub(crate) fn check_alignment(addr: usize, align: usize) -> Result<()> {
+        if addr % align != 0 {
+            cold();
+            return Err(Error::Trap(crate::Trap::UnalignedAccess { addr, required_align: align }));
+        }
+        Ok(())
+    }
+
     pub(crate) fn store(&mut self, addr: usize, len: usize, data: &[u8]) -> Result<()> {
         let Some(end) = addr.checked_add(len) else {
             cold();
@@ -124,28 +166,54 @@ impl MemoryInstance {
     }
 
     #[inline]
-    pub(crate) fn grow(&mut self, pages_delta: i32) -> Option<i32> {
-        let current_pages = self.page_count;
-        let new_pages = current_pages as i64 + pages_delta as i64;
-        debug_assert!(new_pages <= i32::MAX as i64, "page count should never be greater than i32::MAX");
+    pub(crate) fn snapshot(&self) -> MemorySnapshot {
+        MemorySnapshot { kind: self.kind, data: self.data.clone(), page_count: self.page_count }
+    }
 
-        if new_pages < 0 || new_pages as usize > self.max_pages() {
+    pub(crate) fn restore(&mut self, snapshot: &MemorySnapshot) -> Result<()> {
+        if snapshot.kind != self.kind {
+            return Err(Error::Other("memory snapshot was taken from a memory of a different type".to_string()));
+        }
+
+        // reuses the existing allocation if the memory hasn't shrunk
+        self.data.clear();
+        self.data.extend_from_slice(&snapshot.data);
+        self.page_count = snapshot.page_count;
+        Ok(())
+    }
+
+    /// The size in bytes after growing by `pages_delta`, or `None` if the memory can't grow that far
+    pub(crate) fn grown_size(&self, pages_delta: i64) -> Option<usize> {
+        // memories can't shrink
+        if pages_delta < 0 {
+            return None;
+        }
+
+        let new_pages = (self.page_count as i64).checked_add(pages_delta)?;
+        if new_pages as u64 > self.max_pages() as u64 {
             log::debug!("memory.grow failed: new_pages={}, max_pages={}", new_pages, self.max_pages());
             log::debug!("{} {}", self.kind.page_count_max(), self.kind.page_size());
 
             return None;
         }
 
-        let new_size = (new_pages as u64 * self.kind.page_size()) as usize;
-        if new_size as u64 > self.kind.max_size() {
+        // the new size also has to be addressable by the host
+        let new_size = (new_pages as u64).checked_mul(self.kind.page_size())?;
+        if new_size > self.kind.max_size() {
             return None;
         }
+        usize::try_from(new_size).ok()
+    }
+
+    pub(crate) fn grow(&mut self, pages_delta: i64) -> Option<i64> {
+        let current_pages = self.page_count;
+        let new_size = self.grown_size(pages_delta)?;
 
-        // Zero initialize the new pages
-        self.data.reserve_exact(new_size);
+        // Zero initialize the new pages, failing instead of aborting if the host is out of memory
+        self.data.try_reserve_exact(new_size - self.data.len()).ok()?;
         self.data.resize_with(new_size, Default::default);
-        self.page_count = new_pages as usize;
-        Some(current_pages as i32)
+        self.page_count = current_pages + pages_delta as usize;
+        Some(current_pages as i64)
     }
 }
 
@@ -174,7 +242,7 @@ macro_rules! impl_mem_traits {
             impl MemStorable<$size> for $type {
                 #[inline(always)]
                 fn to_mem_bytes(self) -> [u8; $size] {
-                    self.to_ne_bytes()
+                    self.to_le_bytes()
                 }
             }
         )*
@@ -191,7 +259,7 @@ mod memory_instance_tests {
     fn create_test_memory() -> MemoryInstance {
         let kind = MemoryType::new(MemoryArch::I32, 1, Some(2), None);
         let owner = ModuleInstanceAddr::default();
-        MemoryInstance::new(kind, owner)
+        MemoryInstance::new(kind, owner).unwrap()
     }
 
     #[test]
@@ -210,6 +278,17 @@ mod memory_instance_tests {
         assert!(memory.store(memory.data.len(), data_to_store.len(), &data_to_store).is_err());
     }
 
+    #[test]
+    fn test_memory_check_alignment() {
+        assert!(MemoryInstance::check_alignment(0, 8).is_ok());
+        assert!(MemoryInstance::check_alignment(12, 4).is_ok());
+        assert!(MemoryInstance::check_alignment(13, 1).is_ok());
+        assert!(matches!(
+            MemoryInstance::check_alignment(6, 4),
+            Err(Error::Trap(crate::Trap::UnalignedAccess { addr: 6, required_align: 4 }))
+        ));
+    }
+
     #[test]
     fn test_memory_fill() {
         let mut memory = create_test_memory();
@@ -241,14 +320,14 @@ mod memory_instance_tests {
     fn test_memory_grow() {
         let mut memory = create_test_memory();
         let original_pages = memory.page_count;
-        assert_eq!(memory.grow(1), Some(original_pages as i32));
+        assert_eq!(memory.grow(1), Some(original_pages as i64));
         assert_eq!(memory.page_count, original_pages + 1);
     }
 
     #[test]
     fn test_memory_grow_out_of_bounds() {
         let mut memory = create_test_memory();
-        assert!(memory.grow(memory.kind.max_size() as i32 + 1).is_none());
+        assert!(memory.grow(memory.kind.max_size() as i64 + 1).is_none());
     }
 
     #[test]
@@ -258,11 +337,27 @@ mod memory_instance_tests {
         assert_eq!(memory.grow(1), None);
     }
 
+    #[test]
+    fn test_memory_grow_negative() {
+        let mut memory = create_test_memory();
+        assert_eq!(memory.grow(-1), None);
+        assert_eq!(memory.grow(i64::MIN), None);
+        assert_eq!(memory.page_count, 1);
+        assert_eq!(memory.len(), 0x10000);
+    }
+
+    #[test]
+    fn test_memory_too_large_for_host() {
+        // 2^48 pages of 64 KiB can't be allocated anywhere, creating the memory has to fail instead of aborting
+        let kind = MemoryType::new(MemoryArch::I64, 1 << 48, None, None);
+        assert!(MemoryInstance::new(kind, ModuleInstanceAddr::default()).is_err());
+    }
+
     #[test]
     fn test_memory_custom_page_size_out_of_bounds() {
         let kind = MemoryType::new(MemoryArch::I32, 1, Some(2), Some(1));
         let owner = ModuleInstanceAddr::default();
-        let mut memory = MemoryInstance::new(kind, owner);
+        let mut memory = MemoryInstance::new(kind, owner).unwrap();
 
         let data_to_store = [1, 2];
         assert!(memory.store(0, data_to_store.len(), &data_to_store).is_err());
@@ -272,7 +367,7 @@ mod memory_instance_tests {
     fn test_memory_custom_page_size_grow() {
         let kind = MemoryType::new(MemoryArch::I32, 1, Some(2), Some(1));
         let owner = ModuleInstanceAddr::default();
-        let mut memory = MemoryInstance::new(kind, owner);
+        let mut memory = MemoryInstance::new(kind, owner).unwrap();
 
         assert_eq!(memory.grow(1), Some(1));
 
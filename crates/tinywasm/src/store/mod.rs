@@ -1,19 +1,26 @@
-use alloc::{boxed::Box, format, string::ToString, vec::Vec};
+use alloc::{boxed::Box, collections::BTreeMap, format, rc::Rc, string::String, string::ToString, sync::Arc, vec::Vec};
 use core::fmt::Debug;
 use core::sync::atomic::{AtomicUsize, Ordering};
 use tinywasm_types::*;
 
+use crate::interpreter::stack::MAX_CALL_STACK_SIZE;
 use crate::interpreter::{self, InterpreterRuntime, TinyWasmValue};
-use crate::{cold, Error, Function, ModuleInstance, Result, Trap};
+use crate::{cold, unlikely, CallContext, Error, FuncHandle, Function, HostFunction, ModuleInstance, Result, Trap};
 
 mod data;
 mod element;
 mod function;
 mod global;
 mod memory;
+mod snapshot;
 mod table;
 
 pub(crate) use {data::*, element::*, function::*, global::*, memory::*, table::*};
+pub use {memory::MemorySnapshot, snapshot::InstanceSnapshot};
+
+/// A callback invoked before every instruction, see [`Store::set_trace_hook`]
+#[cfg(feature = "trace")]
+pub type TraceHook = Box<dyn FnMut(FuncAddr, usize, &Instruction)>;
 
 // global store id counter
 static STORE_ID: AtomicUsize = AtomicUsize::new(0);
@@ -24,25 +31,49 @@ static STORE_ID: AtomicUsize = AtomicUsize::new(0);
 ///
 /// Note that the state doesn't do any garbage collection - so it will grow
 /// indefinitely if you keep adding modules to it. When calling temporary
-/// functions, you should create a new store and then drop it when you're done (e.g. in a request handler)
+/// functions, you should create a new store and then drop it when you're done (e.g. in a request handler),
+/// or release short-lived instances with [`Store::drop_instance`].
 ///
 ///  See <https://webassembly.github.io/spec/core/exec/runtime.html#store>
 pub struct Store {
     id: usize,
-    module_instances: Vec<ModuleInstance>,
-
+    generation: u32, // incremented by `Store::reset` to invalidate instances and handles
+    module_instances: Vec<Option<ModuleInstance>>, // none if the instance was dropped
+    max_call_depth: usize,
+
+    pub(crate) fuel: Option<u64>,
+    pub(crate) canonicalize_nans: bool,
+    pub(crate) deterministic: bool,
+    pub(crate) capture_backtraces: bool,
+    pub(crate) max_memory_pages: Option<u32>,
+    pub(crate) max_table_elements: Option<u32>,
+    pub(crate) memory_budget: Option<usize>, // bytes left for `memory.grow` in the current call
+    call_counts: Option<BTreeMap<FuncAddr, u64>>,
+    #[cfg(feature = "trace")]
+    pub(crate) trace_hook: Option<TraceHook>,
     pub(crate) data: StoreData,
     pub(crate) runtime: Runtime,
 }
 
 impl Debug for Store {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        f.debug_struct("Store")
-            .field("id", &self.id)
+        let mut f = f.debug_struct("Store");
+        f.field("id", &self.id)
+            .field("generation", &self.generation)
             .field("module_instances", &self.module_instances)
+            .field("max_call_depth", &self.max_call_depth)
+            .field("fuel", &self.fuel)
+            .field("canonicalize_nans", &self.canonicalize_nans)
+            .field("deterministic", &self.deterministic)
+            .field("capture_backtraces", &self.capture_backtraces)
+            .field("max_memory_pages", &self.max_memory_pages)
+            .field("max_table_elements", &self.max_table_elements)
+            .field("call_counts", &self.call_counts)
             .field("data", &"...")
-            .field("runtime", &self.runtime)
-            .finish()
+            .field("runtime", &self.runtime);
+        #[cfg(feature = "trace")]
+        f.field("trace_hook", &self.trace_hook.as_ref().map(|_| "..."));
+        f.finish()
     }
 }
 
@@ -59,11 +90,362 @@ impl Store {
 
     /// Get a module instance by the internal id
     pub fn get_module_instance(&self, addr: ModuleInstanceAddr) -> Option<&ModuleInstance> {
-        self.module_instances.get(addr as usize)
+        self.module_instances.get(addr as usize)?.as_ref()
+    }
+
+    /// Find all module instances exporting an item with the given name
+    ///
+    /// Matches are returned in instantiation order.
+    pub fn find_export(&self, name: &str) -> Vec<(ModuleInstanceAddr, ExternVal)> {
+        self.module_instances
+            .iter()
+            .flatten()
+            .filter_map(|instance| Some((instance.id(), instance.export_addr(name)?)))
+            .collect()
+    }
+
+    /// Get the names of all functions in the store, sorted by address
+    ///
+    /// Names are taken from the `name` custom section and fall back to the export name,
+    /// functions with neither are left out. Meant for symbolizing profiles and traces.
+    pub fn symbol_map(&self) -> Vec<(FuncAddr, String)> {
+        let mut symbols = Vec::new();
+        for instance in self.module_instances.iter().flatten() {
+            for (idx, &addr) in instance.0.func_addrs.iter().enumerate() {
+                // imported functions are listed by the instance that owns them
+                if self.data.funcs[addr as usize].owner != instance.id() {
+                    continue;
+                }
+
+                if let Some(name) = instance.func_name(idx as FuncAddr) {
+                    symbols.push((addr, name.to_string()));
+                }
+            }
+        }
+
+        symbols.sort_by_key(|(addr, _)| *addr);
+        symbols
+    }
+
+    /// Get the name of a single function, see [`Store::symbol_map`]
+    pub fn func_name(&self, addr: FuncAddr) -> Option<&str> {
+        let instance = self.get_module_instance(self.data.funcs.get(addr as usize)?.owner)?;
+        let idx = instance.0.func_addrs.iter().position(|&a| a == addr)?;
+        instance.func_name(idx as FuncAddr)
+    }
+
+    /// Get a handle to the function at `addr`, e.g. one found with [`Store::find_export`]
+    pub fn func_handle(&self, addr: FuncAddr) -> Result<FuncHandle> {
+        let func =
+            self.data.funcs.get(addr as usize).ok_or_else(|| Error::Other(format!("function {addr} not found")))?;
+        let name = self.func_name(addr).map(ToString::to_string);
+        Ok(FuncHandle { module_addr: func.owner, addr, generation: self.generation, ty: func.func.ty().clone(), name })
+    }
+
+    /// Call a function through a handle, see [`FuncHandle::call`]
+    ///
+    /// Nothing is looked up by name, so resolving a handle once and calling it
+    /// repeatedly avoids the cost of [`ModuleInstance::exported_func_untyped`].
+    pub fn call(&mut self, handle: &FuncHandle, params: &[WasmValue]) -> Result<Vec<WasmValue>> {
+        handle.call(self, params)
+    }
+
+    /// Create a context for calling functions without allocating a new stack for every call
+    pub fn new_call_context(&self) -> CallContext {
+        CallContext::new()
+    }
+
+    /// Get the offset in the wasm binary of an instruction, e.g. a frame of [`Trap::backtrace`]
+    ///
+    /// Only available if the module was parsed with `Parser::with_instruction_offsets` enabled.
+    pub fn instruction_offset(&self, func: FuncAddr, instr: usize) -> Option<usize> {
+        match &self.data.funcs.get(func as usize)?.func {
+            Function::Wasm(f) => f.data.instruction_offsets.get(instr).map(|&offset| offset as usize),
+            Function::Host(_) => None,
+        }
+    }
+
+    /// Get the number of bytes of linear memory currently committed by all memories in the store
+    pub fn total_memory_bytes(&self) -> usize {
+        self.data.memories.iter().map(MemoryInstance::len).sum()
+    }
+
+    /// Get the number of bytes of linear memory currently committed by the memories a module instance owns
+    ///
+    /// Imported memories count towards the instance that defined them.
+    pub fn module_memory_bytes(&self, addr: ModuleInstanceAddr) -> usize {
+        self.data.memories.iter().filter(|m| m.owner == addr).map(MemoryInstance::len).sum()
+    }
+
+    /// Copy `len` bytes from offset `src_offset` in memory `src` to offset `dst_offset` in memory `dst`
+    ///
+    /// Works like `memory.copy` for memories of any module instance in the store, given their store addresses
+    /// (e.g. from [`ModuleInstance::export_addr`]). Both may be the same memory, overlapping regions are copied correctly.
+    /// If either region is out of bounds, a [`Trap::MemoryOutOfBounds`] is returned and nothing is copied.
+    pub fn memory_copy(
+        &mut self,
+        dst: MemAddr,
+        dst_offset: usize,
+        src: MemAddr,
+        src_offset: usize,
+        len: usize,
+    ) -> Result<()> {
+        if dst == src {
+            let mem = self.data.memories.get_mut(dst as usize).ok_or_else(|| Self::not_found_error("memory"))?;
+            return mem.copy_within(dst_offset, src_offset, len);
+        }
+
+        let (dst_mem, src_mem) = self.get_mems_mut(dst, src)?;
+        dst_mem.copy_from_slice(dst_offset, src_mem.load(src_offset, len)?)
     }
 
     pub(crate) fn get_module_instance_raw(&self, addr: ModuleInstanceAddr) -> ModuleInstance {
-        self.module_instances[addr as usize].clone()
+        // only live instances can be reached during execution, see `Store::drop_instance`
+        self.module_instances[addr as usize].clone().expect("module instance was dropped")
+    }
+
+    /// Remove a module instance and release the memories, tables, functions and segments it owns
+    ///
+    /// Addresses of other instances stay valid: dropped items are replaced with empty placeholders.
+    /// Handles to the dropped instance or its functions that are still around return errors when used.
+    ///
+    /// Fails if another instance still references one of the owned items, e.g. by importing
+    /// it or by storing a reference to one of its functions in a table or global.
+    pub fn drop_instance(&mut self, addr: ModuleInstanceAddr) -> Result<()> {
+        if self.get_module_instance(addr).is_none() {
+            return Err(Error::Other(format!("module instance {addr} not found")));
+        }
+
+        let owned = |owner: ModuleInstanceAddr| owner == addr;
+        let funcs: Vec<FuncAddr> =
+            self.data.funcs.iter().enumerate().filter(|(_, f)| owned(f.owner)).map(|(i, _)| i as FuncAddr).collect();
+        let is_owned_func = |func: Option<FuncAddr>| func.is_some_and(|func| funcs.binary_search(&func).is_ok());
+        let is_live = |owner: ModuleInstanceAddr| !owned(owner) && self.get_module_instance(owner).is_some();
+
+        for other in self.module_instances.iter().flatten().filter(|other| other.id() != addr) {
+            let imports_owned = other.0.func_addrs.iter().any(|&f| owned(self.data.funcs[f as usize].owner))
+                || other.0.table_addrs.iter().any(|&t| owned(self.data.tables[t as usize].owner))
+                || other.0.mem_addrs.iter().any(|&m| owned(self.data.memories[m as usize].owner))
+                || other.0.global_addrs.iter().any(|&g| owned(self.data.globals[g as usize].owner));
+
+            if imports_owned {
+                return Err(Error::Other(format!(
+                    "module instance {addr} is still referenced by module instance {}",
+                    other.id()
+                )));
+            }
+        }
+
+        let table_refs = self
+            .data
+            .tables
+            .iter()
+            .filter(|t| is_live(t.owner) && t.kind.element_type == ValType::RefFunc)
+            .flat_map(|t| t.elements.iter())
+            .any(|el| is_owned_func(el.addr()));
+        let global_refs = self
+            .data
+            .globals
+            .iter()
+            .filter(|g| is_live(g.owner) && g.ty.ty == ValType::RefFunc)
+            .any(|g| is_owned_func(g.value.get().unwrap_ref()));
+        if table_refs || global_refs {
+            return Err(Error::Other(format!(
+                "a function of module instance {addr} is still referenced by another instance"
+            )));
+        }
+
+        for func in self.data.funcs.iter_mut().filter(|f| owned(f.owner)) {
+            func.func = Function::Host(Rc::new(HostFunction {
+                ty: func.func.ty().clone(),
+                func: Box::new(|_, _| Err(Error::Other("function belongs to a dropped module instance".to_string()))),
+            }));
+        }
+        for table in self.data.tables.iter_mut().filter(|t| owned(t.owner)) {
+            table.elements = Vec::new();
+        }
+        for memory in self.data.memories.iter_mut().filter(|m| owned(m.owner)) {
+            memory.data = Vec::new();
+            memory.page_count = 0;
+        }
+        for elem in self.data.elements.iter_mut().filter(|e| owned(e.owner)) {
+            elem.items = None;
+        }
+        for data in self.data.datas.iter_mut().filter(|d| owned(d.owner)) {
+            data.data = None;
+        }
+
+        self.module_instances[addr as usize] = None;
+        Ok(())
+    }
+
+    /// Remove all module instances and everything they own, keeping the store's configuration
+    ///
+    /// The store's id and settings like its limits, fuel and hooks are kept, and
+    /// instantiating a module afterwards works as it would in a new store. Call counts are reset.
+    ///
+    /// This invalidates all module instances and handles created before the reset,
+    /// using them with this store afterwards returns [`Error::InvalidStore`].
+    pub fn reset(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+        self.module_instances.clear();
+        self.data = StoreData::default();
+        self.reset_call_counts();
+    }
+
+    /// Set the maximum number of nested function calls
+    ///
+    /// Calls beyond this depth trap with [`Trap::CallStackOverflow`]. Defaults to 1024.
+    /// Host functions calling back into WebAssembly start with a fresh call stack.
+    ///
+    /// The interpreter caps this at 65536 frames, deeper calls trap with `internal` set.
+    pub fn set_max_call_depth(&mut self, depth: usize) -> &mut Self {
+        self.max_call_depth = depth;
+        self
+    }
+
+    /// Get the maximum number of nested function calls
+    pub fn max_call_depth(&self) -> usize {
+        self.max_call_depth
+    }
+
+    /// Enable fuel metering and set the remaining fuel
+    ///
+    /// Every executed instruction consumes one unit of fuel. Once it runs out, execution
+    /// traps with [`Trap::OutOfFuel`]; add more fuel before calling into the store again.
+    pub fn set_fuel(&mut self, fuel: u64) -> &mut Self {
+        self.fuel = Some(fuel);
+        self
+    }
+
+    /// Get the remaining fuel, or `None` if fuel metering is disabled
+    pub fn fuel_remaining(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    /// Disable fuel metering
+    pub fn disable_fuel(&mut self) -> &mut Self {
+        self.fuel = None;
+        self
+    }
+
+    /// Replace NaNs produced by float arithmetic with the canonical NaN
+    ///
+    /// The bit patterns of NaN results otherwise depend on the host's floating point unit.
+    /// Enable this if results need to be reproducible bit for bit across platforms.
+    pub fn set_canonicalize_nans(&mut self, enabled: bool) -> &mut Self {
+        self.canonicalize_nans = enabled;
+        self
+    }
+
+    /// Check whether NaN canonicalization is enabled
+    pub fn canonicalize_nans(&self) -> bool {
+        self.canonicalize_nans
+    }
+
+    /// Only allow executions with reproducible results and bounded resource usage
+    ///
+    /// Enables [NaN canonicalization](Store::set_canonicalize_nans) and requires [fuel](Store::set_fuel):
+    /// calling a function without fuel metering fails, and running out of fuel traps with [`Trap::OutOfFuel`].
+    /// Given the same fuel, a call then produces identical results on every machine, except that
+    /// `memory.grow` can still fail when the host runs out of memory, see [`Store::set_max_memory_pages`].
+    pub fn set_deterministic(&mut self, enabled: bool) -> &mut Self {
+        self.deterministic = enabled;
+        self.canonicalize_nans = enabled;
+        self
+    }
+
+    /// Check whether deterministic mode is enabled, see [`Store::set_deterministic`]
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// Limit the number of pages any memory in this store can grow to
+    ///
+    /// Applies on top of the maximum a module declares, `memory.grow` returns -1 once either is reached.
+    /// Memories are still created with their declared initial size.
+    pub fn set_max_memory_pages(&mut self, pages: u32) -> &mut Self {
+        self.max_memory_pages = Some(pages);
+        self
+    }
+
+    /// Get the memory size limit set with [`Store::set_max_memory_pages`]
+    pub fn max_memory_pages(&self) -> Option<u32> {
+        self.max_memory_pages
+    }
+
+    /// Limit the number of elements any table in this store can grow to
+    ///
+    /// Applies on top of the maximum a module declares, `table.grow` returns -1 once either is reached.
+    /// Tables are still created with their declared initial size.
+    pub fn set_max_table_elements(&mut self, elements: u32) -> &mut Self {
+        self.max_table_elements = Some(elements);
+        self
+    }
+
+    /// Get the table size limit set with [`Store::set_max_table_elements`]
+    pub fn max_table_elements(&self) -> Option<u32> {
+        self.max_table_elements
+    }
+
+    /// Attach the wasm call stack to traps, see [`Trap::backtrace`](crate::Trap::backtrace)
+    ///
+    /// Disabled by default, since walking the call stack makes every trap more expensive.
+    pub fn capture_backtraces(&mut self, enabled: bool) -> &mut Self {
+        self.capture_backtraces = enabled;
+        self
+    }
+
+    /// Count how often each function is called, see [`Store::call_counts`]
+    ///
+    /// Every call of a wasm or host function is counted, whether it comes from the host or from wasm code.
+    /// Disabling call counting discards the counts collected so far.
+    pub fn enable_call_counting(&mut self, enabled: bool) -> &mut Self {
+        if !enabled {
+            self.call_counts = None;
+        } else if self.call_counts.is_none() {
+            self.call_counts = Some(BTreeMap::new());
+        }
+        self
+    }
+
+    /// Get the number of calls of each function by address, or `None` if call counting is disabled
+    ///
+    /// Counts add up across calls into the store until they are reset with [`Store::reset_call_counts`].
+    /// Functions that were never called are left out.
+    pub fn call_counts(&self) -> Option<&BTreeMap<FuncAddr, u64>> {
+        self.call_counts.as_ref()
+    }
+
+    /// Reset the counts collected with [`Store::enable_call_counting`] to zero
+    pub fn reset_call_counts(&mut self) {
+        if let Some(counts) = self.call_counts.as_mut() {
+            counts.clear();
+        }
+    }
+
+    #[inline]
+    pub(crate) fn count_call(&mut self, addr: FuncAddr) {
+        if let Some(counts) = self.call_counts.as_mut() {
+            *counts.entry(addr).or_default() += 1;
+        }
+    }
+
+    /// Call `hook` with the function address, instruction index and instruction before every instruction is executed
+    ///
+    /// Meant for building profilers, coverage tools or instruction histograms. Instruction indices
+    /// are the same as in [`Trap::backtrace`](crate::Trap::backtrace) and refer to tinywasm's own
+    /// bytecode, see [`Store::instruction_offset`] to map them back to the wasm binary.
+    #[cfg(feature = "trace")]
+    pub fn set_trace_hook(&mut self, hook: TraceHook) -> &mut Self {
+        self.trace_hook = Some(hook);
+        self
+    }
+
+    /// Remove the hook set with [`Store::set_trace_hook`], returning it
+    #[cfg(feature = "trace")]
+    pub fn take_trace_hook(&mut self) -> Option<TraceHook> {
+        self.trace_hook.take()
     }
 
     /// Create a new store with the given runtime
@@ -83,7 +465,24 @@ impl PartialEq for Store {
 impl Default for Store {
     fn default() -> Self {
         let id = STORE_ID.fetch_add(1, Ordering::Relaxed);
-        Self { id, module_instances: Vec::new(), data: StoreData::default(), runtime: Runtime::Default }
+        Self {
+            id,
+            generation: 0,
+            module_instances: Vec::new(),
+            max_call_depth: MAX_CALL_STACK_SIZE,
+            fuel: None,
+            canonicalize_nans: false,
+            deterministic: false,
+            capture_backtraces: false,
+            max_memory_pages: None,
+            max_table_elements: None,
+            memory_budget: None,
+            call_counts: None,
+            #[cfg(feature = "trace")]
+            trace_hook: None,
+            data: StoreData::default(),
+            runtime: Runtime::Default,
+        }
     }
 }
 
@@ -107,13 +506,18 @@ impl Store {
         self.id
     }
 
+    /// The number of times the store was reset, see [`Store::reset`]
+    pub(crate) fn generation(&self) -> u32 {
+        self.generation
+    }
+
     pub(crate) fn next_module_instance_idx(&self) -> ModuleInstanceAddr {
         self.module_instances.len() as ModuleInstanceAddr
     }
 
     pub(crate) fn add_instance(&mut self, instance: ModuleInstance) {
         assert!(instance.id() == self.module_instances.len() as ModuleInstanceAddr);
-        self.module_instances.push(instance);
+        self.module_instances.push(Some(instance));
     }
 
     #[cold]
@@ -208,16 +612,27 @@ impl Store {
     }
 
     /// Set the global at the actual index in the store
+    ///
+    /// Fails if the global is immutable.
     #[doc(hidden)]
-    pub fn set_global_val(&mut self, addr: MemAddr, value: TinyWasmValue) {
-        self.data.globals[addr as usize].value.set(value);
+    pub fn set_global_val(&mut self, addr: MemAddr, value: TinyWasmValue) -> Result<()> {
+        let global = &self.data.globals[addr as usize];
+        if unlikely(!global.ty.mutable) {
+            return Err(Error::Other(format!("global {addr} is immutable")));
+        }
+        global.value.set(value);
+        Ok(())
     }
 }
 
 // Linking related functions
 impl Store {
     /// Add functions to the store, returning their addresses in the store
-    pub(crate) fn init_funcs(&mut self, funcs: Vec<WasmFunction>, idx: ModuleInstanceAddr) -> Result<Vec<FuncAddr>> {
+    pub(crate) fn init_funcs(
+        &mut self,
+        funcs: Vec<Arc<WasmFunction>>,
+        idx: ModuleInstanceAddr,
+    ) -> Result<Vec<FuncAddr>> {
         let func_count = self.data.funcs.len();
         let mut func_addrs = Vec::with_capacity(func_count);
         for (i, func) in funcs.into_iter().enumerate() {
@@ -243,10 +658,7 @@ impl Store {
         let mem_count = self.data.memories.len();
         let mut mem_addrs = Vec::with_capacity(mem_count);
         for (i, mem) in memories.into_iter().enumerate() {
-            if let MemoryArch::I64 = mem.arch() {
-                return Err(Error::UnsupportedFeature("64-bit memories".to_string()));
-            }
-            self.data.memories.push(MemoryInstance::new(mem, idx));
+            self.data.memories.push(MemoryInstance::new(mem, idx)?);
             mem_addrs.push((i + mem_count) as MemAddr);
         }
         Ok(mem_addrs)
@@ -284,7 +696,7 @@ impl Store {
                 })?)
             }
             ElementItem::Expr(ConstInstruction::RefFunc(None)) => None,
-            ElementItem::Expr(ConstInstruction::RefExtern(None)) => None,
+            ElementItem::Expr(ConstInstruction::RefExtern(None) | ConstInstruction::RefNullI31) => None,
             ElementItem::Expr(ConstInstruction::GlobalGet(addr)) => {
                 let addr = globals.get(*addr as usize).copied().ok_or_else(|| {
                     Error::Other(format!("global {addr} not found. This should have been caught by the validator"))
@@ -324,8 +736,8 @@ impl Store {
                 ElementKind::Declared => None, // a. Execute the instruction elm.drop i
 
                 // this one is active, so we need to initialize it (essentially a `table.init` instruction)
-                ElementKind::Active { offset, table } => {
-                    let offset = self.eval_i32_const(offset)?;
+                ElementKind::Active { ref offset, table } => {
+                    let offset = self.eval_const(offset, global_addrs, func_addrs)?.unwrap_32() as i32;
                     let table_addr = table_addrs
                         .get(table as usize)
                         .copied()
@@ -349,7 +761,7 @@ impl Store {
                 }
             };
 
-            self.data.elements.push(ElementInstance::new(element.kind, idx, items));
+            self.data.elements.push(ElementInstance::new(element.kind.clone(), idx, items));
             elem_addrs.push((i + elem_count) as Addr);
         }
 
@@ -361,6 +773,8 @@ impl Store {
     pub(crate) fn init_datas(
         &mut self,
         mem_addrs: &[MemAddr],
+        global_addrs: &[Addr],
+        func_addrs: &[FuncAddr],
         datas: Vec<Data>,
         idx: ModuleInstanceAddr,
     ) -> Result<(Box<[Addr]>, Option<Trap>)> {
@@ -373,12 +787,16 @@ impl Store {
                         return Err(Error::Other(format!("memory {mem_addr} not found for data segment {i}")));
                     };
 
-                    let offset = self.eval_i32_const(offset)?;
-                    let Some(mem) = self.data.memories.get_mut(*mem_addr as usize) else {
+                    let Some(is_64) = self.data.memories.get(*mem_addr as usize).map(MemoryInstance::is_64) else {
                         return Err(Error::Other(format!("memory {mem_addr} not found for data segment {i}")));
                     };
 
-                    match mem.store(offset as usize, data.data.len(), &data.data) {
+                    // offsets into 64-bit memories are i64 constants
+                    let offset = self.eval_const(&offset, global_addrs, func_addrs)?;
+                    let offset = if is_64 { offset.unwrap_64() } else { offset.unwrap_32() as u64 };
+
+                    let mem = self.get_mem_mut(*mem_addr);
+                    match mem.store(usize::try_from(offset).unwrap_or(usize::MAX), data.data.len(), &data.data) {
                         Ok(()) => None,
                         Err(Error::Trap(trap)) => return Ok((data_addrs.into_boxed_slice(), Some(trap))),
                         Err(e) => return Err(e),
@@ -406,10 +824,7 @@ impl Store {
     }
 
     pub(crate) fn add_mem(&mut self, mem: MemoryType, idx: ModuleInstanceAddr) -> Result<MemAddr> {
-        if let MemoryArch::I64 = mem.arch() {
-            return Err(Error::UnsupportedFeature("64-bit memories".to_string()));
-        }
-        self.data.memories.push(MemoryInstance::new(mem, idx));
+        self.data.memories.push(MemoryInstance::new(mem, idx)?);
         Ok(self.data.memories.len() as MemAddr - 1)
     }
 
@@ -418,21 +833,66 @@ impl Store {
         Ok(self.data.funcs.len() as FuncAddr - 1)
     }
 
-    /// Evaluate a constant expression, only supporting i32 globals and i32.const
-    pub(crate) fn eval_i32_const(&self, const_instr: tinywasm_types::ConstInstruction) -> Result<i32> {
-        use tinywasm_types::ConstInstruction::*;
-        let val = match const_instr {
-            I32Const(i) => i,
-            GlobalGet(addr) => self.data.globals[addr as usize].value.get().unwrap_32() as i32,
-            _ => return Err(Error::Other("expected i32".to_string())),
-        };
-        Ok(val)
-    }
-
     /// Evaluate a constant expression
+    ///
+    /// Besides single constants, the arithmetic of the extended-const proposal is supported.
     pub(crate) fn eval_const(
         &self,
-        const_instr: &tinywasm_types::ConstInstruction,
+        expr: &[ConstInstruction],
+        module_global_addrs: &[Addr],
+        module_func_addrs: &[FuncAddr],
+    ) -> Result<TinyWasmValue> {
+        // most expressions are a single instruction, evaluate them without allocating a stack
+        if let [instr] = expr {
+            return self.eval_const_instr(instr, module_global_addrs, module_func_addrs);
+        }
+
+        let mut stack: Vec<TinyWasmValue> = Vec::with_capacity(expr.len());
+        for instr in expr {
+            use ConstInstruction::*;
+            let val = match instr {
+                I32Add | I32Sub | I32Mul => {
+                    let (b, a) = (stack.pop(), stack.pop());
+                    let (Some(TinyWasmValue::Value32(a)), Some(TinyWasmValue::Value32(b))) = (a, b) else {
+                        return Err(Error::Other("invalid operands for const instruction".to_string()));
+                    };
+                    TinyWasmValue::Value32(match instr {
+                        I32Add => a.wrapping_add(b),
+                        I32Sub => a.wrapping_sub(b),
+                        _ => a.wrapping_mul(b),
+                    })
+                }
+                I64Add | I64Sub | I64Mul => {
+                    let (b, a) = (stack.pop(), stack.pop());
+                    let (Some(TinyWasmValue::Value64(a)), Some(TinyWasmValue::Value64(b))) = (a, b) else {
+                        return Err(Error::Other("invalid operands for const instruction".to_string()));
+                    };
+                    TinyWasmValue::Value64(match instr {
+                        I64Add => a.wrapping_add(b),
+                        I64Sub => a.wrapping_sub(b),
+                        _ => a.wrapping_mul(b),
+                    })
+                }
+                RefI31 => {
+                    let Some(TinyWasmValue::Value32(value)) = stack.pop() else {
+                        return Err(Error::Other("invalid operands for const instruction".to_string()));
+                    };
+                    TinyWasmValue::ValueRef(I31Ref::new(value as i32).get_u())
+                }
+                instr => self.eval_const_instr(instr, module_global_addrs, module_func_addrs)?,
+            };
+            stack.push(val);
+        }
+
+        match stack[..] {
+            [val] => Ok(val),
+            _ => Err(Error::Other("const expression must produce exactly one value".to_string())),
+        }
+    }
+
+    fn eval_const_instr(
+        &self,
+        const_instr: &ConstInstruction,
         module_global_addrs: &[Addr],
         module_func_addrs: &[FuncAddr],
     ) -> Result<TinyWasmValue> {
@@ -442,6 +902,7 @@ impl Store {
             F64Const(f) => (*f).into(),
             I32Const(i) => (*i).into(),
             I64Const(i) => (*i).into(),
+            V128Const(i) => TinyWasmValue::Value128(*i),
             GlobalGet(addr) => {
                 let addr = module_global_addrs.get(*addr as usize).ok_or_else(|| {
                     Error::Other(format!("global {addr} not found. This should have been caught by the validator"))
@@ -452,7 +913,7 @@ impl Store {
                 global.value.get()
             }
             RefFunc(None) => TinyWasmValue::ValueRef(None),
-            RefExtern(None) => TinyWasmValue::ValueRef(None),
+            RefExtern(None) | RefNullI31 => TinyWasmValue::ValueRef(None),
             RefFunc(Some(idx)) => {
                 TinyWasmValue::ValueRef(Some(*module_func_addrs.get(*idx as usize).ok_or_else(|| {
                     Error::Other(format!("function {idx} not found. This should have been caught by the validator"))
@@ -0,0 +1,77 @@
+use alloc::{format, vec::Vec};
+use tinywasm_types::*;
+
+use super::{MemorySnapshot, TableElement};
+use crate::interpreter::TinyWasmValue;
+use crate::{Error, Result, Store};
+
+/// A copy of the mutable state of a module instance
+///
+/// Created with [`Store::snapshot_instance`] and restored with [`Store::restore_instance`].
+#[derive(Debug, Clone)]
+pub struct InstanceSnapshot {
+    store_id: usize,
+    instance: ModuleInstanceAddr,
+    memories: Vec<(MemAddr, MemorySnapshot)>,
+    tables: Vec<(TableAddr, Vec<TableElement>)>,
+    globals: Vec<(GlobalAddr, TinyWasmValue)>,
+}
+
+impl InstanceSnapshot {
+    /// Get the address of the snapshotted module instance
+    pub fn instance(&self) -> ModuleInstanceAddr {
+        self.instance
+    }
+}
+
+impl Store {
+    /// Take a snapshot of the memories, tables and mutable globals owned by a module instance
+    ///
+    /// Items imported from other instances and the state of passive data and element
+    /// segments are not included. Restoring a snapshot is usually much cheaper than
+    /// instantiating the module again, e.g. when running the same module against many inputs.
+    pub fn snapshot_instance(&self, addr: ModuleInstanceAddr) -> Result<InstanceSnapshot> {
+        let instance =
+            self.get_module_instance(addr).ok_or_else(|| Error::Other(format!("module instance {addr} not found")))?;
+
+        let memories = instance.0.mem_addrs.iter().filter(|&&m| self.get_mem(m).owner == addr);
+        let tables = instance.0.table_addrs.iter().filter(|&&t| self.get_table(t).owner == addr);
+        let globals = instance.0.global_addrs.iter().map(|&g| (g, self.get_global(g)));
+
+        Ok(InstanceSnapshot {
+            store_id: self.id(),
+            instance: addr,
+            memories: memories.map(|&m| (m, self.get_mem(m).snapshot())).collect(),
+            tables: tables.map(|&t| (t, self.get_table(t).elements.clone())).collect(),
+            globals: globals
+                .filter(|(_, g)| g.owner == addr && g.ty.mutable)
+                .map(|(a, g)| (a, g.value.get()))
+                .collect(),
+        })
+    }
+
+    /// Reset the memories, tables and mutable globals of a module instance to a snapshot
+    ///
+    /// Memories and tables get back the exact size they had when the snapshot was taken.
+    pub fn restore_instance(&mut self, snapshot: &InstanceSnapshot) -> Result<()> {
+        if snapshot.store_id != self.id() {
+            return Err(Error::InvalidStore);
+        }
+        if self.get_module_instance(snapshot.instance).is_none() {
+            return Err(Error::Other(format!("module instance {} not found", snapshot.instance)));
+        }
+
+        for (addr, memory) in &snapshot.memories {
+            self.get_mem_mut(*addr).restore(memory)?;
+        }
+        for (addr, elements) in &snapshot.tables {
+            let table = self.get_table_mut(*addr);
+            table.elements.clear();
+            table.elements.extend_from_slice(elements);
+        }
+        for (addr, value) in &snapshot.globals {
+            self.set_global_val(*addr, *value)?;
+        }
+        Ok(())
+    }
+}
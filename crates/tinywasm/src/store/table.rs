@@ -12,17 +12,17 @@ const MAX_TABLE_SIZE: u32 = 10_000_000;
 pub(crate) struct TableInstance {
     pub(crate) elements: Vec<TableElement>,
     pub(crate) kind: TableType,
-    pub(crate) _owner: ModuleInstanceAddr, // index into store.module_instances
+    pub(crate) owner: ModuleInstanceAddr, // index into store.module_instances
 }
 
 impl TableInstance {
     pub(crate) fn new(kind: TableType, owner: ModuleInstanceAddr) -> Self {
-        Self { elements: vec![TableElement::Uninitialized; kind.size_initial as usize], kind, _owner: owner }
+        Self { elements: vec![TableElement::Uninitialized; kind.size_initial as usize], kind, owner }
     }
 
     #[inline(never)]
     #[cold]
-    fn trap_oob(&self, addr: usize, len: usize) -> Error {
+    pub(crate) fn trap_oob(&self, addr: usize, len: usize) -> Error {
         Error::Trap(crate::Trap::TableOutOfBounds { offset: addr, len, max: self.elements.len() })
     }
 
@@ -32,12 +32,12 @@ impl TableInstance {
         Ok(match self.kind.element_type {
             ValType::RefFunc => WasmValue::RefFunc(FuncRef::new(val)),
             ValType::RefExtern => WasmValue::RefExtern(ExternRef::new(val)),
+            ValType::RefI31 => WasmValue::RefI31(val.map_or(I31Ref::null(), |v| I31Ref::new(v as i32))),
             _ => Err(Error::UnsupportedFeature("non-ref table".into()))?,
         })
     }
 
-    pub(crate) fn fill(&mut self, func_addrs: &[u32], addr: usize, len: usize, val: TableElement) -> Result<()> {
-        let val = val.map(|addr| self.resolve_func_ref(func_addrs, addr));
+    pub(crate) fn fill(&mut self, addr: usize, len: usize, val: TableElement) -> Result<()> {
         let end = addr.checked_add(len).ok_or_else(|| self.trap_oob(addr, len))?;
         if end > self.elements.len() {
             return Err(self.trap_oob(addr, len));
@@ -105,13 +105,14 @@ impl TableInstance {
         Ok(())
     }
 
-    pub(crate) fn grow(&mut self, n: i32, init: TableElement) -> Result<()> {
+    /// Grow the table by `n` elements, failing if it would exceed its declared maximum or `max_elements`
+    pub(crate) fn grow(&mut self, n: i32, init: TableElement, max_elements: Option<u32>) -> Result<()> {
         if n < 0 {
             return Err(Error::Trap(crate::Trap::TableOutOfBounds { offset: 0, len: 1, max: self.elements.len() }));
         }
 
         let len = n as usize + self.elements.len();
-        let max = self.kind.size_max.unwrap_or(MAX_TABLE_SIZE) as usize;
+        let max = self.kind.size_max.unwrap_or(MAX_TABLE_SIZE).min(max_elements.unwrap_or(u32::MAX)) as usize;
         if len > max {
             return Err(Error::Trap(crate::Trap::TableOutOfBounds { offset: len, len: 1, max: self.elements.len() }));
         }
@@ -124,16 +125,6 @@ impl TableInstance {
         self.elements.len() as i32
     }
 
-    fn resolve_func_ref(&self, func_addrs: &[u32], addr: Addr) -> Addr {
-        if self.kind.element_type != ValType::RefFunc {
-            return addr;
-        }
-
-        *func_addrs
-            .get(addr as usize)
-            .expect("error initializing table: function not found. This should have been caught by the validator")
-    }
-
     pub(crate) fn init(&mut self, offset: i32, init: &[TableElement]) -> Result<()> {
         let offset = offset as usize;
         let end = offset.checked_add(init.len()).ok_or({
@@ -172,13 +163,6 @@ impl TableElement {
             TableElement::Initialized(addr) => Some(*addr),
         }
     }
-
-    pub(crate) fn map(self, f: impl FnOnce(Addr) -> Addr) -> Self {
-        match self {
-            TableElement::Uninitialized => TableElement::Uninitialized,
-            TableElement::Initialized(addr) => TableElement::Initialized(f(addr)),
-        }
-    }
 }
 
 #[cfg(test)]
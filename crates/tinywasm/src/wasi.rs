@@ -0,0 +1,382 @@
+//! A minimal shim for `wasi_snapshot_preview1`
+//!
+//! Provides enough of WASI to run simple programs compiled for `wasm32-wasip1` (e.g. "hello world"):
+//! standard I/O, arguments, environment variables, clocks, randomness and `proc_exit`.
+//! There is no filesystem access, writing to or reading from any other file descriptor fails with `EBADF`.
+//!
+//! ## Example
+//! ```rust
+//! # fn main() -> tinywasm::Result<()> {
+//! use tinywasm::wasi::{self, WasiCtx};
+//! use tinywasm::{Imports, Module, Store};
+//!
+//! let wasm = wat::parse_str(r#"
+//!     (module
+//!         (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+//!         (memory (export "memory") 1)
+//!         (data (i32.const 0) "\10\00\00\00\06\00\00\00") ;; iovec { buf: 16, len: 6 }
+//!         (data (i32.const 16) "hello\n")
+//!         (func (export "run") (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8))))
+//!     )
+//! "#).unwrap();
+//!
+//! let output = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+//! let sink = output.clone();
+//!
+//! let mut imports = Imports::new();
+//! wasi::add_to_imports(&mut imports, WasiCtx::new().with_stdout(move |bytes| sink.borrow_mut().extend_from_slice(bytes)))?;
+//!
+//! let mut store = Store::default();
+//! let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, Some(imports))?;
+//! instance.exported_func::<(), ()>(&store, "run")?.call(&mut store, ())?;
+//! assert_eq!(output.borrow().as_slice(), b"hello\n");
+//! # Ok(())
+//! # }
+//! ```
+
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt::Debug;
+
+use crate::{Error, Extern, FuncContext, Imports, MemoryRefMut, Result};
+
+/// The module name WASI functions are imported from
+pub const MODULE: &str = "wasi_snapshot_preview1";
+
+/// WASI error codes returned by the shim
+///
+/// See <https://github.com/WebAssembly/WASI/blob/main/legacy/preview1/docs.md#variant-cases>
+pub mod errno {
+    /// No error occurred
+    pub const SUCCESS: i32 = 0;
+    /// Bad file descriptor
+    pub const BADF: i32 = 8;
+    /// Bad address, e.g. a buffer that doesn't fit into memory
+    pub const FAULT: i32 = 21;
+    /// Invalid argument
+    pub const INVAL: i32 = 28;
+    /// Function not supported
+    pub const NOSYS: i32 = 52;
+}
+
+/// WASI clock ids, see [`WasiCtx::with_clock`]
+pub mod clock {
+    /// Wall clock time, in nanoseconds since the Unix epoch
+    pub const REALTIME: u32 = 0;
+    /// Time since an arbitrary point that never jumps backwards, in nanoseconds
+    pub const MONOTONIC: u32 = 1;
+    /// CPU time of the process
+    pub const PROCESS_CPUTIME: u32 = 2;
+    /// CPU time of the thread
+    pub const THREAD_CPUTIME: u32 = 3;
+}
+
+type Sink = Box<dyn FnMut(&[u8])>;
+type Source = Box<dyn FnMut(&mut [u8]) -> usize>;
+type Clock = Box<dyn FnMut(u32) -> Option<u64>>;
+type Random = Box<dyn FnMut(&mut [u8])>;
+
+/// The state backing the WASI functions
+///
+/// By default, there are no arguments or environment variables, `stdin` is empty and random numbers
+/// are unavailable. With the `std` feature, `stdout` and `stderr` are forwarded to the host process
+/// and the realtime and monotonic clocks are read from [`std::time`], otherwise output is discarded
+/// and no clock is available.
+pub struct WasiCtx {
+    args: Vec<String>,
+    env: Vec<String>, // `KEY=VALUE` pairs
+    stdin: Option<Source>,
+    stdout: Sink,
+    stderr: Sink,
+    clock: Clock,
+    random: Option<Random>,
+}
+
+impl Debug for WasiCtx {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WasiCtx").field("args", &self.args).field("env", &self.env).finish_non_exhaustive()
+    }
+}
+
+impl Default for WasiCtx {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WasiCtx {
+    /// Create a new context with the default configuration
+    pub fn new() -> Self {
+        Self {
+            args: Vec::new(),
+            env: Vec::new(),
+            stdin: None,
+            stdout: default_sink(1),
+            stderr: default_sink(2),
+            clock: default_clock(),
+            random: None,
+        }
+    }
+
+    /// Set the arguments returned by `args_get`, the first one is usually the program name
+    pub fn with_args(mut self, args: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Add an environment variable returned by `environ_get`
+    pub fn with_env(mut self, key: &str, value: &str) -> Self {
+        self.env.push(alloc::format!("{key}={value}"));
+        self
+    }
+
+    /// Read `stdin` from `read`, which fills the buffer and returns the number of bytes read (0 at the end)
+    pub fn with_stdin(mut self, read: impl FnMut(&mut [u8]) -> usize + 'static) -> Self {
+        self.stdin = Some(Box::new(read));
+        self
+    }
+
+    /// Pass everything written to `stdout` to `write`
+    pub fn with_stdout(mut self, write: impl FnMut(&[u8]) + 'static) -> Self {
+        self.stdout = Box::new(write);
+        self
+    }
+
+    /// Pass everything written to `stderr` to `write`
+    pub fn with_stderr(mut self, write: impl FnMut(&[u8]) + 'static) -> Self {
+        self.stderr = Box::new(write);
+        self
+    }
+
+    /// Read clocks from `clock`, which gets a [clock id](clock) and returns the time in nanoseconds,
+    /// or `None` if the clock isn't supported
+    pub fn with_clock(mut self, clock: impl FnMut(u32) -> Option<u64> + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Fill the buffers passed to `random_get` with `random`
+    ///
+    /// This should be a cryptographically secure source of randomness.
+    pub fn with_random(mut self, random: impl FnMut(&mut [u8]) + 'static) -> Self {
+        self.random = Some(Box::new(random));
+        self
+    }
+}
+
+#[cfg(feature = "std")]
+fn default_sink(fd: i32) -> Sink {
+    use crate::std::io::Write;
+    Box::new(move |bytes| {
+        // like a closed pipe, output that can't be written is dropped
+        let _ = match fd {
+            1 => crate::std::io::stdout().write_all(bytes),
+            _ => crate::std::io::stderr().write_all(bytes),
+        };
+    })
+}
+
+#[cfg(not(feature = "std"))]
+fn default_sink(_fd: i32) -> Sink {
+    Box::new(|_| {})
+}
+
+#[cfg(feature = "std")]
+fn default_clock() -> Clock {
+    use crate::std::time::{Instant, SystemTime};
+    let start = Instant::now();
+    Box::new(move |id| match id {
+        clock::REALTIME => SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).ok().map(|d| d.as_nanos() as u64),
+        clock::MONOTONIC => Some(start.elapsed().as_nanos() as u64),
+        _ => None,
+    })
+}
+
+#[cfg(not(feature = "std"))]
+fn default_clock() -> Clock {
+    Box::new(|_| None)
+}
+
+/// Define the WASI functions backed by `ctx` in `imports`
+///
+/// Defines `args_get`, `args_sizes_get`, `environ_get`, `environ_sizes_get`, `fd_read`, `fd_write`,
+/// `clock_time_get`, `random_get` and `proc_exit`. Calling `proc_exit` stops execution with [`Error::ProcExit`].
+/// The module has to export its memory as `memory`.
+pub fn add_to_imports(imports: &mut Imports, ctx: WasiCtx) -> Result<()> {
+    let ctx = Rc::new(RefCell::new(ctx));
+
+    let wasi = ctx.clone();
+    imports.define(
+        MODULE,
+        "args_sizes_get",
+        Extern::typed_func(move |mut ctx: FuncContext<'_>, (count_ptr, size_ptr): (i32, i32)| {
+            Ok(status(write_sizes(&mut memory(&mut ctx)?, &wasi.borrow().args, count_ptr, size_ptr)))
+        }),
+    )?;
+
+    let wasi = ctx.clone();
+    imports.define(
+        MODULE,
+        "args_get",
+        Extern::typed_func(move |mut ctx: FuncContext<'_>, (argv, argv_buf): (i32, i32)| {
+            Ok(status(write_strings(&mut memory(&mut ctx)?, &wasi.borrow().args, argv, argv_buf)))
+        }),
+    )?;
+
+    let wasi = ctx.clone();
+    imports.define(
+        MODULE,
+        "environ_sizes_get",
+        Extern::typed_func(move |mut ctx: FuncContext<'_>, (count_ptr, size_ptr): (i32, i32)| {
+            Ok(status(write_sizes(&mut memory(&mut ctx)?, &wasi.borrow().env, count_ptr, size_ptr)))
+        }),
+    )?;
+
+    let wasi = ctx.clone();
+    imports.define(
+        MODULE,
+        "environ_get",
+        Extern::typed_func(move |mut ctx: FuncContext<'_>, (environ, environ_buf): (i32, i32)| {
+            Ok(status(write_strings(&mut memory(&mut ctx)?, &wasi.borrow().env, environ, environ_buf)))
+        }),
+    )?;
+
+    let wasi = ctx.clone();
+    imports.define(
+        MODULE,
+        "fd_write",
+        Extern::typed_func(move |mut ctx: FuncContext<'_>, (fd, iovs, iovs_len, nwritten): (i32, i32, i32, i32)| {
+            let mut wasi = wasi.borrow_mut();
+            let sink = match fd {
+                1 => &mut wasi.stdout,
+                2 => &mut wasi.stderr,
+                _ => return Ok(errno::BADF),
+            };
+
+            let mut memory = memory(&mut ctx)?;
+            let mut written: u32 = 0;
+            for i in 0..iovs_len as u32 {
+                let Some(data) = read_iovec(&memory, iovs, i).and_then(|(buf, len)| memory.load(buf, len).ok()) else {
+                    return Ok(errno::FAULT);
+                };
+                sink(data);
+                written = written.wrapping_add(data.len() as u32);
+            }
+            Ok(status(memory.store_as(nwritten as u32 as usize, written).ok()))
+        }),
+    )?;
+
+    let wasi = ctx.clone();
+    imports.define(
+        MODULE,
+        "fd_read",
+        Extern::typed_func(move |mut ctx: FuncContext<'_>, (fd, iovs, iovs_len, nread): (i32, i32, i32, i32)| {
+            if fd != 0 {
+                return Ok(errno::BADF);
+            }
+
+            let mut wasi = wasi.borrow_mut();
+            let mut memory = memory(&mut ctx)?;
+            let mut read: u32 = 0;
+            if let Some(stdin) = wasi.stdin.as_mut() {
+                for i in 0..iovs_len as u32 {
+                    let Some((buf, len)) = read_iovec(&memory, iovs, i) else {
+                        return Ok(errno::FAULT);
+                    };
+                    let Some(data) = guest_slice(&mut memory, buf, len) else {
+                        return Ok(errno::FAULT);
+                    };
+                    let n = stdin(data).min(len);
+                    read = read.wrapping_add(n as u32);
+                    if n < len {
+                        break;
+                    }
+                }
+            }
+            Ok(status(memory.store_as(nread as u32 as usize, read).ok()))
+        }),
+    )?;
+
+    let wasi = ctx.clone();
+    imports.define(
+        MODULE,
+        "clock_time_get",
+        Extern::typed_func(move |mut ctx: FuncContext<'_>, (id, _precision, time_ptr): (i32, i64, i32)| {
+            let Some(time) = (wasi.borrow_mut().clock)(id as u32) else {
+                return Ok(errno::INVAL);
+            };
+            Ok(status(memory(&mut ctx)?.store_as(time_ptr as u32 as usize, time).ok()))
+        }),
+    )?;
+
+    let wasi = ctx;
+    imports.define(
+        MODULE,
+        "random_get",
+        Extern::typed_func(move |mut ctx: FuncContext<'_>, (buf, len): (i32, i32)| {
+            let mut wasi = wasi.borrow_mut();
+            let Some(random) = wasi.random.as_mut() else {
+                return Ok(errno::NOSYS);
+            };
+            let mut memory = memory(&mut ctx)?;
+            let Some(data) = guest_slice(&mut memory, buf as u32 as usize, len as u32 as usize) else {
+                return Ok(errno::FAULT);
+            };
+            random(data);
+            Ok(errno::SUCCESS)
+        }),
+    )?;
+
+    imports.define(
+        MODULE,
+        "proc_exit",
+        Extern::typed_func(|_: FuncContext<'_>, code: i32| -> Result<()> { Err(Error::ProcExit(code)) }),
+    )?;
+
+    Ok(())
+}
+
+fn memory<'a>(ctx: &'a mut FuncContext<'_>) -> Result<MemoryRefMut<'a>> {
+    ctx.exported_memory_mut("memory")
+}
+
+// a guest pointer that doesn't fit into memory fails the call with `EFAULT` instead of trapping
+fn status(result: Option<()>) -> i32 {
+    result.map_or(errno::FAULT, |()| errno::SUCCESS)
+}
+
+// buffers are written in place, so a guest-controlled length never makes the host allocate
+fn guest_slice<'a>(memory: &'a mut MemoryRefMut<'_>, buf: usize, len: usize) -> Option<&'a mut [u8]> {
+    memory.data_mut().get_mut(buf..buf.checked_add(len)?)
+}
+
+// an iovec is a pair of u32s, the address and length of a buffer
+fn read_iovec(memory: &MemoryRefMut<'_>, iovs: i32, index: u32) -> Option<(usize, usize)> {
+    let iovec = (iovs as u32 as usize).saturating_add(index as usize * 8);
+    let buf = memory.load_as::<u32>(iovec).ok()?;
+    let len = memory.load_as::<u32>(iovec.saturating_add(4)).ok()?;
+    Some((buf as usize, len as usize))
+}
+
+// the number of strings and the size of the buffer needed to hold them, including their nul terminators
+fn write_sizes(memory: &mut MemoryRefMut<'_>, strings: &[String], count_ptr: i32, size_ptr: i32) -> Option<()> {
+    let size = strings.iter().map(|s| s.len() + 1).sum::<usize>();
+    memory.store_as(count_ptr as u32 as usize, strings.len() as u32).ok()?;
+    memory.store_as(size_ptr as u32 as usize, size as u32).ok()
+}
+
+// a pointer to each string in `ptrs`, and the nul-terminated strings one after the other in `buf`
+fn write_strings(memory: &mut MemoryRefMut<'_>, strings: &[String], ptrs: i32, buf: i32) -> Option<()> {
+    let (mut ptr, mut buf) = (ptrs as u32 as usize, buf as u32 as usize);
+    for string in strings {
+        memory.store_as(ptr, buf as u32).ok()?;
+        memory.store(buf, string.len(), string.as_bytes()).ok()?;
+        memory.store(buf + string.len(), 1, &[0]).ok()?;
+        ptr += 4;
+        buf += string.len() + 1;
+    }
+    Some(())
+}
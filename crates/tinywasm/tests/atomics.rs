@@ -0,0 +1,152 @@
+use eyre::Result;
+use tinywasm::{Error, Module, Store, Trap};
+
+const ATOMICS: &str = r#"
+(module
+  (memory (export "memory") 1 1 shared)
+  (func (export "store") (param i32 i64) (i64.atomic.store (local.get 0) (local.get 1)))
+  (func (export "load") (param i32) (result i64) (i64.atomic.load (local.get 0)))
+  (func (export "load8") (param i32) (result i32) (i32.atomic.load8_u (local.get 0)))
+  (func (export "load32") (param i32) (result i32) (i32.atomic.load (local.get 0)))
+  (func (export "store16") (param i32 i32) (i32.atomic.store16 (local.get 0) (local.get 1)))
+  (func (export "add") (param i32 i32) (result i32) (i32.atomic.rmw.add (local.get 0) (local.get 1)))
+  (func (export "add8") (param i32 i32) (result i32) (i32.atomic.rmw8.add_u (local.get 0) (local.get 1)))
+  (func (export "sub") (param i32 i64) (result i64) (i64.atomic.rmw.sub (local.get 0) (local.get 1)))
+  (func (export "xchg32") (param i32 i64) (result i64) (i64.atomic.rmw32.xchg_u (local.get 0) (local.get 1)))
+  (func (export "cmpxchg") (param i32 i32 i32) (result i32)
+    (i32.atomic.rmw.cmpxchg (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "cmpxchg16") (param i32 i32 i32) (result i32)
+    (i32.atomic.rmw16.cmpxchg_u (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "fence") atomic.fence)
+  (func (export "notify") (param i32) (result i32) (memory.atomic.notify (local.get 0) (i32.const 1)))
+  (func (export "wait") (param i32 i32 i64) (result i32)
+    (memory.atomic.wait32 (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "wait64") (param i32 i64 i64) (result i32)
+    (memory.atomic.wait64 (local.get 0) (local.get 1) (local.get 2))))
+"#;
+
+#[test]
+fn test_atomic_load_store_rmw() -> Result<()> {
+    let mut store = Store::default();
+    let instance = Module::parse_bytes(&wat::parse_str(ATOMICS)?)?.instantiate(&mut store, None)?;
+    let store_i64 = instance.exported_func::<(i32, i64), ()>(&store, "store")?;
+    let load = instance.exported_func::<i32, i64>(&store, "load")?;
+    let load8 = instance.exported_func::<i32, i32>(&store, "load8")?;
+    let load32 = instance.exported_func::<i32, i32>(&store, "load32")?;
+    let store16 = instance.exported_func::<(i32, i32), ()>(&store, "store16")?;
+
+    store_i64.call(&mut store, (8, 0x1122_3344_5566_7788))?;
+    assert_eq!(load.call(&mut store, 8)?, 0x1122_3344_5566_7788);
+    assert_eq!(load8.call(&mut store, 8)?, 0x88);
+    assert_eq!(load32.call(&mut store, 12)?, 0x1122_3344);
+    store16.call(&mut store, (8, 0xabcd_ef01u32 as i32))?;
+    assert_eq!(load.call(&mut store, 8)?, 0x1122_3344_5566_ef01);
+
+    // read-modify-write instructions return the old value, narrow ones zero-extended
+    let add = instance.exported_func::<(i32, i32), i32>(&store, "add")?;
+    let add8 = instance.exported_func::<(i32, i32), i32>(&store, "add8")?;
+    let sub = instance.exported_func::<(i32, i64), i64>(&store, "sub")?;
+    let xchg32 = instance.exported_func::<(i32, i64), i64>(&store, "xchg32")?;
+    assert_eq!(add.call(&mut store, (0, 5))?, 0);
+    assert_eq!(add.call(&mut store, (0, i32::MAX))?, 5);
+    assert_eq!(load32.call(&mut store, 0)?, i32::MIN + 4);
+    assert_eq!(add8.call(&mut store, (0, 0x1ff))?, 0x04);
+    assert_eq!(load32.call(&mut store, 0)?, i32::MIN + 3);
+    assert_eq!(sub.call(&mut store, (16, 1))?, 0);
+    assert_eq!(load.call(&mut store, 16)?, -1);
+    assert_eq!(xchg32.call(&mut store, (16, 0x1_0000_0007))?, 0xffff_ffff);
+    assert_eq!(load.call(&mut store, 16)?, -0x1_0000_0000 + 7);
+
+    // the expected value is wrapped to the width of the access before it is compared
+    let cmpxchg = instance.exported_func::<(i32, i32, i32), i32>(&store, "cmpxchg")?;
+    let cmpxchg16 = instance.exported_func::<(i32, i32, i32), i32>(&store, "cmpxchg16")?;
+    assert_eq!(cmpxchg.call(&mut store, (24, 1, 2))?, 0);
+    assert_eq!(load32.call(&mut store, 24)?, 0);
+    assert_eq!(cmpxchg.call(&mut store, (24, 0, 2))?, 0);
+    assert_eq!(load32.call(&mut store, 24)?, 2);
+    assert_eq!(cmpxchg16.call(&mut store, (24, 0x1_0002, 0x7_0003))?, 2);
+    assert_eq!(load32.call(&mut store, 24)?, 3);
+    Ok(())
+}
+
+#[test]
+fn test_atomic_traps() -> Result<()> {
+    let mut store = Store::default();
+    let instance = Module::parse_bytes(&wat::parse_str(ATOMICS)?)?.instantiate(&mut store, None)?;
+    let load = instance.exported_func::<i32, i64>(&store, "load")?;
+    let add = instance.exported_func::<(i32, i32), i32>(&store, "add")?;
+
+    for addr in [1, 4, 7] {
+        assert!(matches!(
+            load.call(&mut store, addr),
+            Err(Error::Trap(Trap::UnalignedAccess { required_align: 8, .. }))
+        ));
+    }
+    assert!(matches!(add.call(&mut store, (2, 1)), Err(Error::Trap(Trap::UnalignedAccess { addr: 2, .. }))));
+    assert!(matches!(add.call(&mut store, (0x1_0000, 1)), Err(Error::Trap(Trap::MemoryOutOfBounds { .. }))));
+    assert_eq!(instance.exported_memory(&mut store, "memory")?.load_vec(0, 4)?, [0; 4]);
+
+    // with a single thread nothing is ever waiting to be woken up
+    instance.exported_func::<(), ()>(&store, "fence")?.call(&mut store, ())?;
+    assert_eq!(instance.exported_func::<i32, i32>(&store, "notify")?.call(&mut store, 0)?, 0);
+    Ok(())
+}
+
+#[test]
+fn test_atomic_wait() -> Result<()> {
+    let mut store = Store::default();
+    let instance = Module::parse_bytes(&wat::parse_str(ATOMICS)?)?.instantiate(&mut store, None)?;
+    let store_i64 = instance.exported_func::<(i32, i64), ()>(&store, "store")?;
+
+    // a wait returns "not-equal" if the value changed and "timed-out" for finite timeouts,
+    // waiting forever for a value that's already there can never be woken up
+    let wait = instance.exported_func::<(i32, i32, i64), i32>(&store, "wait")?;
+    let wait64 = instance.exported_func::<(i32, i64, i64), i32>(&store, "wait64")?;
+    store_i64.call(&mut store, (0, 0x1_0000_0007))?;
+    assert_eq!(wait.call(&mut store, (0, 1, -1))?, 1);
+    assert_eq!(wait.call(&mut store, (0, 7, 0))?, 2);
+    assert_eq!(wait.call(&mut store, (0, 7, 1_000_000))?, 2);
+    assert!(matches!(wait.call(&mut store, (0, 7, -1)), Err(Error::Trap(Trap::Deadlock))));
+    assert_eq!(wait64.call(&mut store, (0, 7, -1))?, 1);
+    assert_eq!(wait64.call(&mut store, (0, 0x1_0000_0007, 5))?, 2);
+    assert!(matches!(wait.call(&mut store, (2, 0, 0)), Err(Error::Trap(Trap::UnalignedAccess { .. }))));
+    assert!(matches!(wait.call(&mut store, (0x1_0000, 0, 0)), Err(Error::Trap(Trap::MemoryOutOfBounds { .. }))));
+    Ok(())
+}
+
+#[test]
+fn test_atomic_wait_unshared() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"(module
+            (memory 1)
+            (func (export "wait") (param i32) (result i32) (memory.atomic.wait32 (local.get 0) (i32.const 1) (i64.const 0)))
+            (func (export "notify") (result i32) (memory.atomic.notify (i32.const 0) (i32.const 1))))"#,
+    )?;
+    let mut store = Store::default();
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, None)?;
+    let wait = instance.exported_func::<i32, i32>(&store, "wait")?;
+    assert!(matches!(wait.call(&mut store, 0), Err(Error::Trap(Trap::NotSharedMemory))));
+    assert_eq!(instance.exported_func::<(), i32>(&store, "notify")?.call(&mut store, ())?, 0);
+    Ok(())
+}
+
+#[test]
+fn test_shared_memory_import() -> Result<()> {
+    use tinywasm::types::{MemoryArch, MemoryType};
+    use tinywasm::{Extern, Imports};
+
+    let wasm = wat::parse_str(r#"(module (import "env" "memory" (memory 1 1 shared)))"#)?;
+    let module = Module::parse_bytes(&wasm)?;
+    let ty = MemoryType::new(MemoryArch::I32, 1, Some(1), None);
+
+    // sharedness has to match, like the rest of the memory type
+    let mut store = Store::default();
+    let mut imports = Imports::new();
+    imports.define("env", "memory", Extern::memory(ty))?;
+    assert!(matches!(module.clone().instantiate(&mut store, Some(imports)), Err(Error::Linker(_))));
+
+    let mut imports = Imports::new();
+    imports.define("env", "memory", Extern::memory(ty.with_shared(true)))?;
+    module.instantiate(&mut store, Some(imports))?;
+    Ok(())
+}
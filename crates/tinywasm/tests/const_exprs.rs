@@ -0,0 +1,58 @@
+use eyre::Result;
+use tinywasm::{types::WasmValue, Extern, Imports, Module, Store};
+
+#[test]
+fn test_v128_global_init() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (global $g v128 (v128.const i32x4 1 2 3 4))
+            (func (export "get") (result v128) global.get $g)
+        )
+        "#,
+    )?;
+
+    let module = Module::parse_bytes(&wasm)?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, None)?;
+
+    let get = instance.exported_func_untyped(&store, "get")?;
+    let [WasmValue::V128(value)] = get.call(&mut store, &[])?[..] else { panic!("expected a single v128 result") };
+    let lanes: Vec<u32> = value.to_le_bytes().chunks(4).map(|c| u32::from_le_bytes(c.try_into().unwrap())).collect();
+    assert_eq!(lanes, [1, 2, 3, 4]);
+    Ok(())
+}
+
+#[test]
+fn test_extended_const() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "env" "base" (global $base i32))
+            (global $sum i32 (i32.add (i32.const 1) (i32.const 2)))
+            (global $offset i32 (i32.mul (i32.sub (global.get $base) (i32.const 1)) (i32.const 4)))
+            (global $wide i64 (i64.mul (i64.sub (i64.const 0) (i64.const 3)) (i64.add (i64.const 1) (i64.const 1))))
+            (global $wrap i32 (i32.add (i32.const 0x7fffffff) (i32.const 1)))
+            (memory (export "memory") 1)
+            (data (i32.add (global.get $base) (i32.const 2)) "\2a")
+            (func (export "sum") (result i32) global.get $sum)
+            (func (export "offset") (result i32) global.get $offset)
+            (func (export "wide") (result i64) global.get $wide)
+            (func (export "wrap") (result i32) global.get $wrap)
+        )
+        "#,
+    )?;
+
+    let mut imports = Imports::new();
+    imports.define("env", "base", Extern::global(WasmValue::I32(10), false))?;
+    let module = Module::parse_bytes(&wasm)?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, Some(imports))?;
+
+    assert_eq!(instance.exported_func::<(), i32>(&store, "sum")?.call(&mut store, ())?, 3);
+    assert_eq!(instance.exported_func::<(), i32>(&store, "offset")?.call(&mut store, ())?, 36);
+    assert_eq!(instance.exported_func::<(), i64>(&store, "wide")?.call(&mut store, ())?, -6);
+    assert_eq!(instance.exported_func::<(), i32>(&store, "wrap")?.call(&mut store, ())?, i32::MIN);
+    assert_eq!(instance.exported_memory(&mut store, "memory")?.load(12, 1)?, [42]);
+    Ok(())
+}
@@ -0,0 +1,451 @@
+use eyre::Result;
+use tinywasm::types::{FuncType, ValType};
+use tinywasm::{try_into_array, Error, Extern, FuncContext, Imports, Module, ModuleInstance, Store, Trap};
+
+fn instantiate(wat: &str) -> Result<(Store, ModuleInstance)> {
+    let module = Module::parse_bytes(&wat::parse_str(wat)?)?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, None)?;
+    Ok((store, instance))
+}
+
+#[test]
+fn test_implicit_return_multi_value() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (func $pair (param i32) (result i32 i64 f32)
+                local.get 0
+                local.get 0
+                i64.extend_i32_s
+                f32.const 1.5)
+            (func $early (param i32) (result i32 i64)
+                i32.const 100
+                local.get 0
+                if
+                    i32.const 7
+                    i32.const 8
+                    i64.const 9
+                    return
+                end
+                drop
+                i32.const 10
+                i64.const 11)
+            (func (export "pair") (param i32) (result i32 i64 f32)
+                local.get 0
+                call $pair)
+            (func (export "nested") (param i32) (result i32)
+                i32.const 1000
+                local.get 0
+                call $early
+                i32.wrap_i64
+                i32.add
+                i32.add)
+        )
+        "#,
+    )?;
+
+    let pair = instance.exported_func::<i32, (i32, i64, f32)>(&store, "pair")?;
+    assert_eq!(pair.call(&mut store, -3)?, (-3, -3, 1.5));
+
+    // the callee leaves extra operands below its results, the caller must not see them
+    let nested = instance.exported_func::<i32, i32>(&store, "nested")?;
+    assert_eq!(nested.call(&mut store, 1)?, 1000 + 8 + 9);
+    assert_eq!(nested.call(&mut store, 0)?, 1000 + 10 + 11);
+    Ok(())
+}
+
+#[test]
+fn test_results_into_array() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (func (export "four") (result i32 i32 i32 i32)
+                i32.const 1
+                i32.const 2
+                i32.const -3
+                i32.const 4)
+        )
+        "#,
+    )?;
+
+    let res = instance.exported_func_untyped(&store, "four")?.call(&mut store, &[])?;
+    assert_eq!(try_into_array::<i32, 4>(&res)?, [1, 2, -3, 4]);
+    assert!(try_into_array::<i32, 3>(&res).is_err());
+    assert!(try_into_array::<i64, 4>(&res).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_if_without_else_skips_then_branch() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (func (export "guarded") (param i32) (result i32)
+                i32.const 42
+                (if (local.get 0) (then unreachable))
+                i32.const 1
+                i32.add)
+            (func (export "guarded_block") (param i32) (result i32)
+                (block (result i32)
+                    i32.const 7
+                    (if (local.get 0) (then i32.const 1 drop unreachable))))
+        )
+        "#,
+    )?;
+
+    let guarded = instance.exported_func::<i32, i32>(&store, "guarded")?;
+    assert_eq!(guarded.call(&mut store, 0)?, 43);
+    assert!(matches!(guarded.call(&mut store, 1), Err(Error::Trap(Trap::Unreachable))));
+    assert_eq!(guarded.call(&mut store, 0)?, 43);
+
+    let guarded_block = instance.exported_func::<i32, i32>(&store, "guarded_block")?;
+    assert_eq!(guarded_block.call(&mut store, 0)?, 7);
+    assert!(matches!(guarded_block.call(&mut store, -1), Err(Error::Trap(Trap::Unreachable))));
+    Ok(())
+}
+
+#[test]
+fn test_invalid_result_count_rejected() -> Result<()> {
+    let wasm = wat::parse_str("(module (func (result i32 i32) i32.const 1))")?;
+    assert!(Module::parse_bytes(&wasm).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_max_call_depth() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (func $depth (export "depth") (param i32) (result i32)
+                (if (result i32) (i32.eqz (local.get 0))
+                    (then (i32.const 0))
+                    (else (i32.add (i32.const 1) (call $depth (i32.sub (local.get 0) (i32.const 1)))))))
+        )
+        "#,
+    )?;
+
+    let mut store = Store::default();
+    assert_eq!(store.max_call_depth(), 1024);
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, None)?;
+    let depth = instance.exported_func::<i32, i32>(&store, "depth")?;
+
+    let overflow = |res: tinywasm::Result<i32>| match res {
+        Err(Error::Trap(Trap::CallStackOverflow { max_depth, internal })) => Some((max_depth, internal)),
+        _ => None,
+    };
+
+    // the initial call occupies one frame, so n recursive calls need n + 1 frames
+    assert_eq!(depth.call(&mut store, 1023)?, 1023);
+    assert_eq!(overflow(depth.call(&mut store, 1024)), Some((1024, false)));
+
+    store.set_max_call_depth(10);
+    assert_eq!(depth.call(&mut store, 9)?, 9);
+    assert_eq!(overflow(depth.call(&mut store, 10)), Some((10, false)));
+    assert_eq!(overflow(depth.call(&mut store, 100_000)), Some((10, false)));
+
+    // limits above the interpreter's own cap are clamped to it
+    store.set_max_call_depth(usize::MAX);
+    assert_eq!(depth.call(&mut store, 65535)?, 65535);
+    let res = depth.call(&mut store, 100_000);
+    assert!(res.as_ref().is_err_and(|e| e.to_string().contains("internal interpreter limit")));
+    assert_eq!(overflow(res), Some((65536, true)));
+    Ok(())
+}
+
+#[test]
+fn test_return_call_indirect() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (type $state (func (param i32 i64) (result i64)))
+            (import "host" "done" (func $done (param i32 i64) (result i64)))
+            (table $dispatch 4 funcref)
+            (elem (table $dispatch) (i32.const 0) func $done $even $odd $bad)
+
+            ;; alternates between $even and $odd until the counter hits zero, then hands off to the host
+            (func $even (type $state)
+                (if (i32.eqz (local.get 0))
+                    (then (return_call_indirect $dispatch (type $state) (local.get 0) (local.get 1) (i32.const 0))))
+                (return_call_indirect $dispatch (type $state)
+                    (i32.sub (local.get 0) (i32.const 1)) (i64.add (local.get 1) (i64.const 2)) (i32.const 2)))
+            (func $odd (type $state)
+                (if (i32.eqz (local.get 0))
+                    (then (return_call_indirect $dispatch (type $state) (local.get 0) (local.get 1) (i32.const 0))))
+                (return_call_indirect $dispatch (type $state)
+                    (i32.sub (local.get 0) (i32.const 1)) (i64.add (local.get 1) (i64.const 1)) (i32.const 1)))
+            (func $bad (param i32) (result i64) (i64.const -1))
+
+            (func (export "run") (param i32) (result i64)
+                (return_call $even (local.get 0) (i64.const 0)))
+            (func $nested (param i32) (result i64)
+                (block (result i64)
+                    (i64.const 7)
+                    (return_call_indirect $dispatch (type $state) (i32.const 3) (i64.const 0) (local.get 0))))
+            (func (export "nested") (param i32) (result i64)
+                (i64.add (i64.const 1000) (call $nested (local.get 0))))
+        )
+        "#,
+    )?;
+
+    let mut imports = Imports::new();
+    imports.define(
+        "host",
+        "done",
+        Extern::typed_func(|_: FuncContext<'_>, (n, acc): (i32, i64)| Ok(acc * 10 + n as i64)),
+    )?;
+    let mut store = Store::default();
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, Some(imports))?;
+
+    // far deeper than the call depth limit, so every hop has to reuse the frame
+    let run = instance.exported_func::<i32, i64>(&store, "run")?;
+    assert_eq!(run.call(&mut store, 100_000)?, 1_500_000);
+    assert_eq!(run.call(&mut store, 3)?, 50);
+
+    // operands left in the tail-calling frame must not leak into its caller
+    let nested = instance.exported_func::<i32, i64>(&store, "nested")?;
+    assert_eq!(nested.call(&mut store, 1)?, 1050);
+    assert_eq!(nested.call(&mut store, 0)?, 1003);
+
+    assert!(matches!(nested.call(&mut store, 3), Err(Error::Trap(Trap::IndirectCallTypeMismatch { .. }))));
+    assert!(matches!(nested.call(&mut store, 4), Err(Error::Trap(Trap::UndefinedElement { .. }))));
+    assert_eq!(run.call(&mut store, 4)?, 60);
+    Ok(())
+}
+
+#[test]
+fn test_trap_backtrace() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (func $inner (unreachable))
+            (func $outer (nop) (call $inner))
+            (func (export "run") (call $outer))
+        )
+        "#,
+    )?;
+
+    let run = instance.exported_func::<(), ()>(&store, "run")?;
+    let Err(Error::Trap(trap)) = run.call(&mut store, ()) else { panic!("expected a trap") };
+    assert!(matches!(trap, Trap::Unreachable));
+    assert_eq!(trap.backtrace(), None);
+
+    store.capture_backtraces(true);
+    let Err(Error::Trap(trap)) = run.call(&mut store, ()) else { panic!("expected a trap") };
+    assert!(matches!(trap.inner(), Trap::Unreachable));
+    assert_eq!(trap.message(), "unreachable");
+
+    let backtrace = trap.backtrace().unwrap().iter();
+    let frames: Vec<_> = backtrace.map(|&(addr, offset)| (store.func_name(addr), offset)).collect();
+    assert_eq!(frames, [(Some("inner"), 0), (Some("outer"), 1), (Some("run"), 0)]);
+    Ok(())
+}
+
+#[test]
+fn test_trap_instruction_offset() -> Result<()> {
+    let wasm = wat::parse_str(r#"(module (func (export "run") (nop) (i32.const 1) (drop) (unreachable)))"#)?;
+    // nop, i32.const 1, drop, unreachable, end
+    let body = [0x01, 0x41, 0x01, 0x1a, 0x00, 0x0b];
+    let unreachable = wasm.windows(body.len()).position(|w| w == body).unwrap() + 4;
+
+    for instruction_offsets in [false, true] {
+        let parser = tinywasm::parser::Parser::new().with_instruction_offsets(instruction_offsets);
+        let module = Module::from(parser.parse_module_bytes(&wasm)?);
+        let mut store = Store::default();
+        store.capture_backtraces(true);
+        let instance = module.instantiate(&mut store, None)?;
+
+        let run = instance.exported_func::<(), ()>(&store, "run")?;
+        let Err(Error::Trap(trap)) = run.call(&mut store, ()) else { panic!("expected a trap") };
+        let (func, instr) = trap.backtrace().unwrap()[0];
+        let offset = store.instruction_offset(func, instr);
+        assert_eq!(offset, instruction_offsets.then_some(unreachable));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_multi_value_blocks() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (type $split (func (param i32) (result i32 i32)))
+
+            ;; branching out of a block keeps exactly its results and drops the rest of the operands
+            (func (export "block") (param i32) (result i32 i32)
+                (i32.const 100)
+                (local.get 0)
+                (block (type $split) (param i32) (result i32 i32)
+                    (i32.const 1)
+                    (i32.add)
+                    (i32.const 7)
+                    (i32.const 8)
+                    (i32.const 9)
+                    (br 0))
+                (local.set 0)
+                (i32.add)
+                (local.get 0))
+
+            ;; br_if only branches when the condition is true, the results stay on the stack either way
+            (func (export "br_if") (param i32 i32) (result i32 i32)
+                (local.get 0)
+                (block (param i32) (result i32 i32)
+                    (i32.const 2)
+                    (local.get 1)
+                    (br_if 0)
+                    (drop)
+                    (i32.const 3)))
+
+            ;; a loop label takes its params, so branching back re-enters with the new values
+            (func (export "loop") (param i32) (result i32 i32)
+                (i32.const 0)
+                (local.get 0)
+                (loop $l (param i32 i32) (result i32 i32)
+                    (local.set 0)
+                    (i32.const 1)
+                    (i32.add)
+                    (local.get 0)
+                    (i32.const 1)
+                    (i32.sub)
+                    (local.tee 0)
+                    (local.get 0)
+                    (br_if $l)))
+
+            (func (export "if") (param i32 i32) (result i32 i32)
+                (local.get 1)
+                (if (type $split) (param i32) (result i32 i32) (local.get 0)
+                    (then (i32.const 1))
+                    (else (i32.const 2))))
+
+            (func (export "br_table") (param i32) (result i32 i32)
+                (block $a (result i32 i32)
+                    (block $b (result i32 i32)
+                        (i32.const 10)
+                        (i32.const 20)
+                        (local.get 0)
+                        (br_table $a $b $a))
+                    (i32.add)
+                    (i32.const 0)))
+        )
+        "#,
+    )?;
+
+    let block = instance.exported_func::<i32, (i32, i32)>(&store, "block")?;
+    assert_eq!(block.call(&mut store, 1)?, (108, 9));
+
+    let br_if = instance.exported_func::<(i32, i32), (i32, i32)>(&store, "br_if")?;
+    assert_eq!(br_if.call(&mut store, (5, 1))?, (5, 2));
+    assert_eq!(br_if.call(&mut store, (5, 0))?, (5, 3));
+
+    let looped = instance.exported_func::<i32, (i32, i32)>(&store, "loop")?;
+    assert_eq!(looped.call(&mut store, 3)?, (3, 0));
+
+    let if_ = instance.exported_func::<(i32, i32), (i32, i32)>(&store, "if")?;
+    assert_eq!(if_.call(&mut store, (1, 5))?, (5, 1));
+    assert_eq!(if_.call(&mut store, (0, 5))?, (5, 2));
+
+    let br_table = instance.exported_func::<i32, (i32, i32)>(&store, "br_table")?;
+    assert_eq!(br_table.call(&mut store, 0)?, (10, 20));
+    assert_eq!(br_table.call(&mut store, 1)?, (30, 0));
+    assert_eq!(br_table.call(&mut store, 2)?, (10, 20));
+    Ok(())
+}
+
+#[test]
+fn test_call_indirect_traps() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "host" "log" (func $log (param i64)))
+            (type $unary (func (param i32) (result i32)))
+            (type $unary_again (func (param i32) (result i32)))
+            (table 5 funcref)
+            (elem (i32.const 0) $inc $binary $log $inc_again)
+            (func $inc (type $unary) (i32.add (local.get 0) (i32.const 1)))
+            (func $inc_again (type $unary_again) (i32.add (local.get 0) (i32.const 2)))
+            (func $binary (param i32 f32) (result i32) (local.get 0))
+            (func (export "call") (param i32) (result i32)
+                (call_indirect (type $unary) (i32.const 10) (local.get 0))))
+        "#,
+    )?;
+    let mut imports = Imports::new();
+    imports.define("host", "log", Extern::typed_func(|_: FuncContext<'_>, _: i64| Ok(())))?;
+    let mut store = Store::default();
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, Some(imports))?;
+    let call = instance.exported_func::<i32, i32>(&store, "call")?;
+
+    let unary = FuncType { params: Box::new([ValType::I32]), results: Box::new([ValType::I32]) };
+    assert_eq!(call.call(&mut store, 0)?, 11);
+    // structurally equal types match even if they are declared separately
+    assert_eq!(call.call(&mut store, 3)?, 12);
+
+    let Err(Error::Trap(Trap::IndirectCallTypeMismatch { expected, actual })) = call.call(&mut store, 1) else {
+        panic!("expected a type mismatch")
+    };
+    assert_eq!(expected, unary);
+    assert_eq!(actual, FuncType { params: Box::new([ValType::I32, ValType::F32]), results: Box::new([ValType::I32]) });
+
+    let Err(Error::Trap(Trap::IndirectCallTypeMismatch { expected, actual })) = call.call(&mut store, 2) else {
+        panic!("expected a type mismatch for the host function")
+    };
+    assert_eq!(expected, unary);
+    assert_eq!(actual, FuncType { params: Box::new([ValType::I64]), results: Box::new([]) });
+
+    assert!(matches!(call.call(&mut store, 4), Err(Error::Trap(Trap::UninitializedElement { index: 4 }))));
+    assert!(matches!(call.call(&mut store, 5), Err(Error::Trap(Trap::UndefinedElement { index: 5 }))));
+    assert!(matches!(call.call(&mut store, -1), Err(Error::Trap(Trap::UndefinedElement { index: 0xffff_ffff }))));
+    Ok(())
+}
+
+#[test]
+fn test_call_counts() -> Result<()> {
+    let module = Module::parse_bytes(&wat::parse_str(
+        r#"
+        (module
+            (import "env" "log" (func $log (param i32)))
+            (table funcref (elem $square))
+            (func $square (export "square") (param i32) (result i32) (i32.mul (local.get 0) (local.get 0)))
+            (func $tail (param i32) (result i32) (return_call $square (local.get 0)))
+            (func (export "run") (param i32) (result i32)
+                (call $log (local.get 0))
+                (drop (call_indirect (param i32) (result i32) (local.get 0) (i32.const 0)))
+                (call $tail (local.get 0))))
+        "#,
+    )?)?;
+
+    let mut store = Store::default();
+    let mut imports = Imports::new();
+    imports.define("env", "log", Extern::typed_func(|_, _: i32| Ok(())))?;
+    let instance = module.instantiate(&mut store, Some(imports))?;
+    let run = instance.exported_func::<i32, i32>(&store, "run")?;
+    let square = instance.exported_func::<i32, i32>(&store, "square")?;
+    let addr = |name| match instance.export_addr(name) {
+        Some(tinywasm::types::ExternVal::Func(addr)) => addr,
+        _ => panic!("{name} is an exported function"),
+    };
+    let (run_addr, square_addr) = (addr("run"), addr("square"));
+
+    assert_eq!(store.call_counts(), None);
+    run.call(&mut store, 3)?;
+    assert_eq!(store.call_counts(), None);
+
+    store.enable_call_counting(true);
+    assert_eq!(run.call(&mut store, 3)?, 9);
+    assert_eq!(run.call(&mut store, 4)?, 16);
+    square.call(&mut store, 2)?;
+
+    // counts add up across calls: `run` calls the host function, `$square` (twice) and `$tail`
+    let counts = store.call_counts().expect("call counting is enabled");
+    assert_eq!(counts.len(), 4);
+    assert_eq!(counts[&run_addr], 2);
+    assert_eq!(counts[&square_addr], 5);
+    assert_eq!(counts.values().sum::<u64>(), 2 + 5 + 2 + 2);
+
+    store.enable_call_counting(true);
+    assert_eq!(store.call_counts().map(|c| c.len()), Some(4), "enabling again keeps the counts");
+    store.reset_call_counts();
+    assert_eq!(store.call_counts().map(|c| c.len()), Some(0));
+    store.enable_call_counting(false);
+    assert_eq!(store.call_counts(), None);
+    Ok(())
+}
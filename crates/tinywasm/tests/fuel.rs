@@ -0,0 +1,75 @@
+use eyre::Result;
+use tinywasm::{Error, Module, Store, Trap};
+
+const LOOPS: &str = r#"
+(module
+    (func (export "count") (param i32) (result i32)
+        (local i32)
+        (block
+            (loop
+                (br_if 1 (i32.ge_u (local.get 1) (local.get 0)))
+                (local.set 1 (i32.add (local.get 1) (i32.const 1)))
+                (br 0)))
+        (local.get 1))
+    (func (export "spin") (loop (br 0)))
+)
+"#;
+
+#[test]
+fn test_fuel() -> Result<()> {
+    let mut store = Store::default();
+    let instance = Module::parse_bytes(&wat::parse_str(LOOPS)?)?.instantiate(&mut store, None)?;
+    let count = instance.exported_func::<i32, i32>(&store, "count")?;
+    let spin = instance.exported_func::<(), ()>(&store, "spin")?;
+    assert_eq!(store.fuel_remaining(), None);
+
+    // fuel usage is deterministic and grows with the amount of work done
+    store.set_fuel(1_000_000);
+    count.call(&mut store, 10)?;
+    let small = 1_000_000 - store.fuel_remaining().unwrap();
+    store.set_fuel(1_000_000);
+    count.call(&mut store, 10)?;
+    assert_eq!(1_000_000 - store.fuel_remaining().unwrap(), small);
+    store.set_fuel(1_000_000);
+    count.call(&mut store, 100)?;
+    assert!(1_000_000 - store.fuel_remaining().unwrap() > small * 5);
+
+    store.set_fuel(small - 1);
+    assert!(matches!(count.call(&mut store, 10), Err(Error::Trap(Trap::OutOfFuel))));
+    assert_eq!(store.fuel_remaining(), Some(0));
+    store.set_fuel(small);
+    assert_eq!(count.call(&mut store, 10)?, 10);
+    assert_eq!(store.fuel_remaining(), Some(0));
+
+    store.set_fuel(10_000);
+    assert!(matches!(spin.call(&mut store, ()), Err(Error::Trap(Trap::OutOfFuel))));
+
+    store.disable_fuel();
+    assert_eq!(count.call(&mut store, 1000)?, 1000);
+    assert_eq!(store.fuel_remaining(), None);
+    Ok(())
+}
+
+#[test]
+fn test_deterministic() -> Result<()> {
+    const NAN: &str = r#"(module (func (export "nan") (result f32) (f32.div (f32.const 0) (f32.const 0))))"#;
+
+    let mut store = Store::default();
+    let loops = Module::parse_bytes(&wat::parse_str(LOOPS)?)?.instantiate(&mut store, None)?;
+    let nan = Module::parse_bytes(&wat::parse_str(NAN)?)?.instantiate(&mut store, None)?;
+    let spin = loops.exported_func::<(), ()>(&store, "spin")?;
+    let nan = nan.exported_func::<(), f32>(&store, "nan")?;
+
+    store.set_deterministic(true);
+    assert!(store.is_deterministic() && store.canonicalize_nans());
+    assert!(matches!(nan.call(&mut store, ()), Err(Error::Other(_))), "calls without fuel are rejected");
+
+    store.set_fuel(10_000);
+    assert_eq!(nan.call(&mut store, ())?.to_bits(), f32::NAN.to_bits());
+    assert!(matches!(spin.call(&mut store, ()), Err(Error::Trap(Trap::OutOfFuel))));
+
+    store.set_deterministic(false).disable_fuel();
+    assert!(!store.is_deterministic() && !store.canonicalize_nans());
+    assert!(nan.call(&mut store, ())?.is_nan());
+    Ok(())
+}
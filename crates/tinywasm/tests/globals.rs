@@ -0,0 +1,72 @@
+use eyre::Result;
+use tinywasm::types::WasmValue;
+use tinywasm::{Error, Module, Store};
+
+#[test]
+fn test_exported_globals() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (global $counter (export "counter") (mut i32) (i32.const 1))
+            (global (export "ratio") f64 (f64.const 2.5))
+            (func (export "inc") (global.set $counter (i32.add (global.get $counter) (i32.const 1))))
+        )
+        "#,
+    )?;
+
+    // instantiate another module first so that module-local and store global addresses differ
+    let mut store = Store::default();
+    Module::parse_bytes(&wat::parse_str("(module (global i64 (i64.const 0)))")?)?.instantiate(&mut store, None)?;
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, None)?;
+
+    assert_eq!(instance.exported_global(&store, "counter")?, WasmValue::I32(1));
+    assert_eq!(instance.exported_global(&store, "ratio")?, WasmValue::F64(2.5));
+
+    instance.exported_func::<(), ()>(&store, "inc")?.call(&mut store, ())?;
+    assert_eq!(instance.exported_global(&store, "counter")?, WasmValue::I32(2));
+
+    instance.set_exported_global(&mut store, "counter", WasmValue::I32(40))?;
+    instance.exported_func::<(), ()>(&store, "inc")?.call(&mut store, ())?;
+    assert_eq!(instance.exported_global(&store, "counter")?, WasmValue::I32(41));
+
+    let is_other = |res: tinywasm::Result<()>| matches!(res, Err(Error::Other(_)));
+    assert!(is_other(instance.set_exported_global(&mut store, "ratio", WasmValue::F64(3.0))));
+    assert!(is_other(instance.set_exported_global(&mut store, "counter", WasmValue::I64(1))));
+    assert!(is_other(instance.set_exported_global(&mut store, "inc", WasmValue::I32(1))));
+    assert!(instance.exported_global(&store, "missing").is_err());
+    assert_eq!(instance.exported_global(&store, "counter")?, WasmValue::I32(41));
+    assert_eq!(instance.exported_global(&store, "ratio")?, WasmValue::F64(2.5));
+    Ok(())
+}
+
+#[test]
+fn test_exported_memory_in_shared_store() -> Result<()> {
+    let wasm = wat::parse_str(r#"(module (memory (export "memory") 1) (data (i32.const 0) "\2a"))"#)?;
+    let mut store = Store::default();
+    Module::parse_bytes(&wat::parse_str(r#"(module (memory 2) (memory 1))"#)?)?.instantiate(&mut store, None)?;
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, None)?;
+
+    assert_eq!(instance.exported_memory(&mut store, "memory")?.load(0, 1)?, [42]);
+    assert_eq!(instance.exported_memory_mut(&mut store, "memory")?.load(0, 1)?, [42]);
+    Ok(())
+}
+
+#[test]
+fn test_global_set_on_immutable_global() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"(module (global (export "g") (mut i32) (i32.const 1)) (func (export "set") (global.set 0 (i32.const 2))))"#,
+    )?;
+
+    // make the global immutable after validation, as if a broken module had slipped through
+    let mut module = Module::parse_bytes(&wasm)?.into_inner();
+    module.globals[0].ty.mutable = false;
+
+    let mut store = Store::default();
+    let instance = Module::from(module).instantiate(&mut store, None)?;
+    let set = instance.exported_func::<(), ()>(&store, "set")?;
+    assert!(matches!(set.call(&mut store, ()), Err(Error::Other(_))));
+    assert_eq!(instance.exported_global(&store, "g")?, WasmValue::I32(1));
+    assert!(instance.set_exported_global(&mut store, "g", WasmValue::I32(3)).is_err());
+    assert_eq!(instance.exported_global(&store, "g")?, WasmValue::I32(1));
+    Ok(())
+}
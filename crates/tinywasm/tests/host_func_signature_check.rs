@@ -2,7 +2,7 @@ use eyre::Result;
 use std::fmt::Write;
 use tinywasm::{
     types::{FuncType, ValType, WasmValue},
-    Extern, FuncContext, Imports, Module, Store,
+    Error, Extern, FromWasmValueTuple, FuncContext, HostFnReturnMismatch, Imports, LinkingError, Module, Store,
 };
 use tinywasm_types::ExternRef;
 
@@ -60,6 +60,67 @@ fn test_return_invalid_type() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_return_mismatch_detail() -> Result<()> {
+    let func_ty = FuncType { params: Box::new([]), results: Box::new([ValType::I32, ValType::F64]) };
+    let module = proxy_module(&func_ty);
+
+    let call_with = |result: &'static [WasmValue]| {
+        let mut store = Store::default();
+        let mut imports = Imports::new();
+        imports.define("host", "hfn", Extern::func(&func_ty, move |_: FuncContext<'_>, _| Ok(result.to_vec())))?;
+        let instance = module.clone().instantiate(&mut store, Some(imports))?;
+        match instance.exported_func_untyped(&store, "call_hfn")?.call(&mut store, &[]) {
+            Err(Error::InvalidHostFnReturn { mismatch, .. }) => Ok(mismatch),
+            res => eyre::bail!("expected an invalid host function return, got {res:?}"),
+        }
+    };
+
+    let too_few = call_with(&[WasmValue::I32(0)])?;
+    assert_eq!(too_few, HostFnReturnMismatch::Arity { expected: 2, actual: 1 });
+
+    let too_many = call_with(&[WasmValue::I32(0), WasmValue::F64(0.0), WasmValue::I32(0)])?;
+    assert_eq!(too_many, HostFnReturnMismatch::Arity { expected: 2, actual: 3 });
+
+    let wrong_type = call_with(&[WasmValue::I32(0), WasmValue::F32(0.0)])?;
+    assert_eq!(wrong_type, HostFnReturnMismatch::Type { index: 1, expected: ValType::F64, actual: ValType::F32 });
+    assert_eq!(wrong_type.to_string(), "result 1 has type F32, expected F64");
+    Ok(())
+}
+
+#[test]
+fn test_arg_mismatch_detail() -> Result<()> {
+    let wasm =
+        wat::parse_str(r#"(module (func (export "f") (param i32 i64) (result i32) (i32.wrap_i64 (local.get 1))))"#)?;
+    let mut store = Store::default();
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, None)?;
+
+    fn arg_mismatch<T>(res: tinywasm::Result<T>) -> Result<(Vec<ValType>, Vec<ValType>)> {
+        match res {
+            Err(Error::HostFnArgMismatch { expected, actual }) => Ok((expected.to_vec(), actual.to_vec())),
+            Ok(_) => eyre::bail!("expected an argument mismatch"),
+            Err(e) => eyre::bail!("expected an argument mismatch, got {e}"),
+        }
+    }
+
+    let untyped = instance.exported_func_untyped(&store, "f")?;
+    let (expected, actual) = arg_mismatch(untyped.call(&mut store, &[WasmValue::I32(1)]))?;
+    assert_eq!((expected, actual), (vec![ValType::I32, ValType::I64], vec![ValType::I32]));
+
+    let typed = instance.exported_func::<(i32, f32), i32>(&store, "f")?;
+    let (expected, actual) = arg_mismatch(typed.call(&mut store, (1, 2.0)))?;
+    assert_eq!((expected, actual), (vec![ValType::I32, ValType::I64], vec![ValType::I32, ValType::F32]));
+
+    // results are converted the same way
+    let typed = instance.exported_func::<(i32, i64), f64>(&store, "f")?;
+    assert_eq!(arg_mismatch(typed.call(&mut store, (1, 2)))?, (vec![ValType::F64], vec![ValType::I32]));
+
+    let values = [WasmValue::I32(1), WasmValue::I64(2)];
+    let (expected, actual) = arg_mismatch(<(i32, u8, i32)>::from_wasm_value_tuple(&values))?;
+    assert_eq!((expected, actual), (vec![ValType::I32, ValType::I32, ValType::I32], vec![ValType::I32, ValType::I64]));
+    Ok(())
+}
+
 #[test]
 fn test_linking_invalid_untyped_func() -> Result<()> {
     // try to import host functions with function types no matching those expected by modules
@@ -117,6 +178,41 @@ fn test_linking_invalid_typed_func() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_linking_invalid_global() -> Result<()> {
+    // try to import globals with a type or mutability not matching those expected by modules
+    let globals = [("i32", false), ("(mut i32)", true), ("i64", false), ("(mut i64)", true)];
+    let values = [WasmValue::I32(0), WasmValue::I32(0), WasmValue::I64(0), WasmValue::I64(0)];
+
+    for (wat_ty, _) in globals {
+        let module =
+            Module::parse_bytes(&wat::parse_str(format!("(module (import \"host\" \"g\" (global {wat_ty})))"))?)?;
+        for ((tried_ty, mutable), value) in globals.iter().zip(values) {
+            let should_succeed = *tried_ty == wat_ty;
+
+            // defined by the host
+            let mut store = Store::default();
+            let mut imports = Imports::new();
+            imports.define("host", "g", Extern::global(value, *mutable))?;
+            let link_res = module.clone().instantiate(&mut store, Some(imports));
+            assert_eq!(link_res.is_ok(), should_succeed, "importing {tried_ty} as {wat_ty}");
+            if !should_succeed {
+                assert!(matches!(link_res, Err(Error::Linker(LinkingError::IncompatibleImportType { .. }))));
+            }
+
+            // exported by another module
+            let init = if value == WasmValue::I32(0) { "i32.const 0" } else { "i64.const 0" };
+            let exporter = format!("(module (global (export \"g\") {tried_ty} ({init})))");
+            let exporter = Module::parse_bytes(&wat::parse_str(exporter)?)?.instantiate(&mut store, None)?;
+            let mut imports = Imports::new();
+            imports.link_module("host", exporter.id())?;
+            let link_res = module.clone().instantiate(&mut store, Some(imports));
+            assert_eq!(link_res.is_ok(), should_succeed, "linking {tried_ty} as {wat_ty}");
+        }
+    }
+    Ok(())
+}
+
 fn to_name(ty: &ValType) -> &str {
     match ty {
         ValType::I32 => "i32",
@@ -126,6 +222,7 @@ fn to_name(ty: &ValType) -> &str {
         ValType::V128 => "v128",
         ValType::RefFunc => "funcref",
         ValType::RefExtern => "externref",
+        ValType::RefI31 => "i31ref",
     }
 }
 
@@ -0,0 +1,88 @@
+use eyre::Result;
+use tinywasm::parser::{ParseError, Parser};
+use tinywasm::types::{I31Ref, WasmValue};
+use tinywasm::{Error, Module, Store, Trap};
+
+const I31: &str = r#"
+(module
+  (global $g i31ref (ref.i31 (i32.const -7)))
+  (func (export "get_s") (param i32) (result i32) (i31.get_s (ref.i31 (local.get 0))))
+  (func (export "get_u") (param i32) (result i32) (i31.get_u (ref.i31 (local.get 0))))
+  (func (export "new") (param i32) (result i31ref) (ref.i31 (local.get 0)))
+  (func (export "global") (result i32) (i31.get_s (global.get $g)))
+  (func (export "null") (result i32)
+    (local $r i31ref)
+    (i31.get_u (local.get $r))))
+"#;
+
+fn parse_gc(wat: &str) -> Result<Module, Error> {
+    let wasm = wat::parse_str(wat).expect("valid wat");
+    Ok(Parser::new().with_gc(true).parse_module_bytes(wasm)?.into())
+}
+
+#[test]
+fn test_i31_round_trip() -> Result<()> {
+    let mut store = Store::default();
+    let instance = parse_gc(I31)?.instantiate(&mut store, None)?;
+    let get_s = instance.exported_func::<i32, i32>(&store, "get_s")?;
+    let get_u = instance.exported_func::<i32, i32>(&store, "get_u")?;
+
+    // the top bit is dropped, bit 30 becomes the sign bit
+    for (value, signed, unsigned) in [
+        (5, 5, 5),
+        (-1, -1, 0x7fff_ffff),
+        (-0x4000_0000, -0x4000_0000, 0x4000_0000),
+        (0x4000_0000, -0x4000_0000, 0x4000_0000),
+        (i32::MIN, 0, 0),
+        (i32::MAX, -1, 0x7fff_ffff),
+    ] {
+        assert_eq!(get_s.call(&mut store, value)?, signed, "i31.get_s of {value}");
+        assert_eq!(get_u.call(&mut store, value)?, unsigned, "i31.get_u of {value}");
+    }
+
+    let new = instance.exported_func_untyped(&store, "new")?;
+    let [WasmValue::RefI31(i31)] = new.call(&mut store, &[WasmValue::I32(-5)])?[..] else {
+        panic!("expected an i31 reference");
+    };
+    assert_eq!((i31.get_s(), i31.get_u()), (Some(-5), Some(0x7fff_fffb)));
+    assert_eq!(i31, I31Ref::new(-5));
+
+    assert_eq!(instance.exported_func::<(), i32>(&store, "global")?.call(&mut store, ())?, -7);
+    let null = instance.exported_func::<(), i32>(&store, "null")?.call(&mut store, ());
+    assert!(matches!(null, Err(Error::Trap(Trap::NullI31Reference))));
+    Ok(())
+}
+
+#[test]
+fn test_gc_struct_unsupported() -> Result<()> {
+    let structs = r#"(module (type $point (struct (field i32))) (func (result i32) (i32.const 0)))"#;
+    let arrays = r#"(module (type $bytes (array i8)) (func (param (ref null $bytes))))"#;
+    let ops =
+        r#"(module (type $t (func)) (func (result i32) (ref.eq (ref.i31 (i32.const 0)) (ref.i31 (i32.const 0)))))"#;
+
+    for wat in [structs, arrays] {
+        match parse_gc(wat) {
+            Err(Error::ParseError(ParseError::UnsupportedFeature { feature, .. })) => {
+                assert_eq!(feature, "gc: struct/array")
+            }
+            res => panic!("expected struct/array to be unsupported, got {res:?}"),
+        }
+    }
+
+    match parse_gc(ops) {
+        Err(Error::ParseError(ParseError::UnsupportedFeature { feature, .. })) => assert_eq!(feature, "gc: RefEq"),
+        res => panic!("expected ref.eq to be unsupported, got {res:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_gc_disabled_by_default() -> Result<()> {
+    assert!(matches!(Module::parse_bytes(&wat::parse_str(I31)?), Err(Error::ParseError(_))));
+
+    // with the GC proposal, data segment offsets could read globals the module defines itself
+    let data = r#"(module (memory 1) (global i32 (i32.const 0)) (data (global.get 0) "a"))"#;
+    assert!(matches!(Module::parse_bytes(&wat::parse_str(data)?), Err(Error::ParseError(_))));
+    assert!(parse_gc(data).is_ok());
+    Ok(())
+}
@@ -0,0 +1,633 @@
+use std::{cell::RefCell, rc::Rc};
+
+use eyre::Result;
+use tinywasm::types::{FuncType, MemoryArch, MemoryType, TableType, ValType, WasmValue};
+use tinywasm::{Error, Extern, FuncContext, Function, Imports, LinkingError, Module, Store};
+
+#[test]
+fn test_wrap_all() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "env" "double" (func $double (param i32) (result i32)))
+            (import "env" "inc" (func $inc (param i32) (result i32)))
+            (func (export "run") (param i32) (result i32)
+                local.get 0
+                call $double
+                call $inc)
+        )
+        "#,
+    )?;
+
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut imports = Imports::new();
+    let inner_log = log.clone();
+    imports
+        .define(
+            "env",
+            "double",
+            Extern::typed_func(move |_: FuncContext<'_>, x: i32| {
+                inner_log.borrow_mut().push("double".to_string());
+                Ok(x * 2)
+            }),
+        )?
+        .define("env", "inc", Extern::typed_func(|_: FuncContext<'_>, x: i32| Ok(x + 1)))?;
+
+    let wrapper_log = log.clone();
+    imports.wrap_all(move |name, ext| {
+        let Extern::Function(Function::Host(func)) = ext else { return ext };
+        let (name, log) = (name.name().to_string(), wrapper_log.clone());
+        Extern::func(&func.ty().clone(), move |ctx, args| {
+            log.borrow_mut().push(format!("enter {name}"));
+            let res = func.call(ctx, args);
+            log.borrow_mut().push(format!("exit {name}"));
+            res
+        })
+    });
+
+    let module = Module::parse_bytes(&wasm)?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, Some(imports))?;
+    let run = instance.exported_func::<i32, i32>(&store, "run")?;
+    assert_eq!(run.call(&mut store, 5)?, 11);
+    assert_eq!(*log.borrow(), ["enter double", "double", "exit double", "enter inc", "exit inc"]);
+    Ok(())
+}
+
+#[test]
+fn test_wrap_all_signature_mismatch() -> Result<()> {
+    let wasm = wat::parse_str(r#"(module (import "env" "f" (func (param i32))))"#)?;
+
+    let mut imports = Imports::new();
+    imports.define("env", "f", Extern::typed_func(|_: FuncContext<'_>, _: i32| Ok(())))?;
+    imports.wrap_all(|_, _| Extern::typed_func(|_: FuncContext<'_>, _: i64| Ok(())));
+
+    let module = Module::parse_bytes(&wasm)?;
+    let mut store = Store::default();
+    assert!(matches!(module.instantiate(&mut store, Some(imports)), Err(tinywasm::Error::Linker(_))));
+    Ok(())
+}
+
+#[test]
+fn test_write_to_guest_out_of_bounds() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "host" "write" (func $write (param i32)))
+            (memory (export "memory") 1)
+            (func (export "run") (param i32) (call $write (local.get 0)))
+        )
+        "#,
+    )?;
+
+    let mut imports = Imports::new();
+    imports.define(
+        "host",
+        "write",
+        Extern::typed_func(|mut ctx: FuncContext<'_>, ptr: i32| ctx.write_to_guest(ptr as u32, &[0xaa; 8])),
+    )?;
+
+    let module = Module::parse_bytes(&wasm)?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, Some(imports))?;
+    let run = instance.exported_func::<i32, ()>(&store, "run")?;
+
+    run.call(&mut store, 65528)?;
+    assert!(matches!(
+        run.call(&mut store, 65532),
+        Err(tinywasm::Error::Trap(tinywasm::Trap::MemoryOutOfBounds { .. }))
+    ));
+    let memory = instance.exported_memory(&mut store, "memory")?;
+    assert_eq!(memory.load(65520, 16)?, [[0; 8], [0xaa; 8]].concat());
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Handle(i32);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Size(i64);
+
+tinywasm::wasm_newtype!(Handle(i32), Size(i64));
+
+#[test]
+fn test_newtype_typed_func() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "host" "size" (func $size (param i32) (result i64)))
+            (func (export "size_of") (param i32) (result i64) (call $size (local.get 0)))
+            (func (export "both") (param i32 i64) (result i64 i32) (local.get 1) (local.get 0))
+        )
+        "#,
+    )?;
+
+    let mut imports = Imports::new();
+    imports.define("host", "size", Extern::typed_func(|_: FuncContext<'_>, h: Handle| Ok(Size(h.0 as i64 * 10))))?;
+
+    let module = Module::parse_bytes(&wasm)?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, Some(imports))?;
+
+    let size_of = instance.exported_func::<Handle, Size>(&store, "size_of")?;
+    assert_eq!(size_of.call(&mut store, Handle(7))?, Size(70));
+
+    let both = instance.exported_func::<(Handle, Size), (Size, Handle)>(&store, "both")?;
+    assert_eq!(both.call(&mut store, (Handle(1), Size(2)))?, (Size(2), Handle(1)));
+    Ok(())
+}
+
+#[test]
+fn test_narrow_integer_and_bool_typed_func() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "host" "pick" (func $pick (param i32 i32 i32) (result i32)))
+            (func (export "pick") (param i32 i32 i32) (result i32)
+                (call $pick (local.get 0) (local.get 1) (local.get 2)))
+            (func (export "id32") (param i32) (result i32) (local.get 0))
+            (func (export "id64") (param i64) (result i64) (local.get 0))
+        )
+        "#,
+    )?;
+
+    let mut imports = Imports::new();
+    let pick = Extern::typed_func(|_: FuncContext<'_>, (flag, a, b): (bool, u8, u16)| {
+        Ok(if flag { a as u32 } else { b as u32 })
+    });
+    imports.define("host", "pick", pick)?;
+
+    let module = Module::parse_bytes(&wasm)?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, Some(imports))?;
+
+    let pick = instance.exported_func::<(bool, u8, u16), u32>(&store, "pick")?;
+    assert_eq!(pick.call(&mut store, (true, 255, 1000))?, 255);
+    assert_eq!(pick.call(&mut store, (false, 255, 1000))?, 1000);
+
+    // values outside the host's parameter types are rejected instead of being truncated
+    let pick = instance.exported_func::<(i32, i32, i32), i32>(&store, "pick")?;
+    assert!(pick.call(&mut store, (2, 0, 0)).is_err());
+    assert!(pick.call(&mut store, (1, 256, 0)).is_err());
+    assert!(pick.call(&mut store, (0, 0, -1)).is_err());
+
+    // same-width unsigned integers round-trip through their signed representation
+    let id32 = instance.exported_func::<u32, u32>(&store, "id32")?;
+    assert_eq!(id32.call(&mut store, u32::MAX)?, u32::MAX);
+    let id64 = instance.exported_func::<u64, u64>(&store, "id64")?;
+    assert_eq!(id64.call(&mut store, u64::MAX)?, u64::MAX);
+    let id32 = instance.exported_func::<i32, bool>(&store, "id32")?;
+    assert!(id32.call(&mut store, 1)?);
+    assert!(id32.call(&mut store, 3).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_v128_typed_func() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "host" "swap" (func $swap (param v128) (result v128)))
+            (func (export "swap_add") (param v128 v128) (result v128)
+                (i32x4.add (call $swap (local.get 0)) (local.get 1)))
+        )
+        "#,
+    )?;
+
+    let mut imports = Imports::new();
+    let swap = Extern::typed_func(|_: FuncContext<'_>, v: u128| Ok(v.rotate_left(64)));
+    imports.define("host", "swap", swap)?;
+
+    let module = Module::parse_bytes(&wasm)?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, Some(imports))?;
+
+    let swap_add = instance.exported_func::<(u128, u128), u128>(&store, "swap_add")?;
+    assert_eq!(swap_add.call(&mut store, (1, 1 << 96))?, (1 << 64) | (1 << 96));
+    Ok(())
+}
+
+#[test]
+fn test_nine_argument_typed_func() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "host" "mix" (func $mix (param i32 i64 f32 f64 i32 i64 f32 f64 i32) (result i64 f64)))
+            (func (export "mix") (param i32 i64 f32 f64 i32 i64 f32 f64 i32) (result i64 f64)
+                (call $mix (local.get 0) (local.get 1) (local.get 2) (local.get 3) (local.get 4)
+                    (local.get 5) (local.get 6) (local.get 7) (local.get 8)))
+        )
+        "#,
+    )?;
+
+    type Args = (i32, i64, f32, f64, i32, i64, f32, f64, i32);
+    let mut imports = Imports::new();
+    let mix = Extern::typed_func(|_: FuncContext<'_>, (a, b, c, d, e, f, g, h, i): Args| {
+        Ok((a as i64 + b + e as i64 + f + i as i64, c as f64 + d + g as f64 + h))
+    });
+    imports.define("host", "mix", mix)?;
+
+    let module = Module::parse_bytes(&wasm)?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, Some(imports))?;
+
+    let mix = instance.exported_func::<Args, (i64, f64)>(&store, "mix")?;
+    assert_eq!(mix.call(&mut store, (1, 2, 0.5, 1.5, 3, 4, 2.0, 4.0, 5))?, (15, 8.0));
+    Ok(())
+}
+
+#[test]
+fn test_func_with_memory() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "host" "sum" (func $sum (param i32 i32) (result i64)))
+            (memory 1)
+            (data (i32.const 65533) "\01\02\03")
+            (func (export "sum") (param i32 i32) (result i64) (call $sum (local.get 0) (local.get 1)))
+        )
+        "#,
+    )?;
+
+    let mut imports = Imports::new();
+    let sum = Extern::func_with_memory(|_, data: &[u8]| Ok(data.iter().map(|b| *b as i64).sum::<i64>()));
+    imports.define("host", "sum", sum)?;
+
+    let module = Module::parse_bytes(&wasm)?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, Some(imports))?;
+    let sum = instance.exported_func::<(i32, i32), i64>(&store, "sum")?;
+
+    assert_eq!(sum.call(&mut store, (65533, 3))?, 6);
+    assert_eq!(sum.call(&mut store, (65536, 0))?, 0);
+    let oob = |res: tinywasm::Result<i64>| {
+        matches!(res, Err(tinywasm::Error::Trap(tinywasm::Trap::MemoryOutOfBounds { .. })))
+    };
+    assert!(oob(sum.call(&mut store, (65533, 4))));
+    assert!(oob(sum.call(&mut store, (-1, 1))));
+    assert!(oob(sum.call(&mut store, (0, -1))));
+    Ok(())
+}
+
+#[test]
+fn test_exported_memories_mut() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "host" "copy" (func $copy (param i32 i32 i32)))
+            (memory $a (export "a") 1)
+            (memory $b (export "b") 1)
+            (data (memory $a) (i32.const 8) "hello")
+            (func (export "copy") (param i32 i32 i32) (call $copy (local.get 0) (local.get 1) (local.get 2)))
+        )
+        "#,
+    )?;
+
+    let mut imports = Imports::new();
+    let copy = Extern::typed_func(|mut ctx: FuncContext<'_>, (src, dst, len): (i32, i32, i32)| {
+        let (a, mut b) = ctx.exported_memories_mut("a", "b")?;
+        b.store(dst as usize, len as usize, a.load(src as usize, len as usize)?)
+    });
+    imports.define("host", "copy", copy)?;
+
+    let module = Module::parse_bytes(&wasm)?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, Some(imports))?;
+    instance.exported_func::<(i32, i32, i32), ()>(&store, "copy")?.call(&mut store, (8, 100, 5))?;
+
+    assert_eq!(instance.exported_memory(&mut store, "b")?.load(100, 5)?, b"hello");
+    assert_eq!(instance.exported_memory(&mut store, "a")?.load(100, 5)?, [0; 5]);
+    assert!(instance.exported_memories_mut(&mut store, "a", "a").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_func_context_memory() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "host" "sum" (func $sum (param i32 i32) (result i32)))
+            (import "host" "missing" (func $missing (result i32)))
+            (memory (export "memory") 1)
+            (global (export "not_memory") i32 (i32.const 0))
+            (data (i32.const 4) "\01\02\03")
+            (func (export "sum") (result i32) (call $sum (i32.const 4) (i32.const 3)))
+            (func (export "missing") (result i32) (call $missing))
+        )
+        "#,
+    )?;
+
+    let mut imports = Imports::new();
+    let sum = Extern::typed_func(|mut ctx: FuncContext<'_>, (ptr, len): (i32, i32)| {
+        let bytes = ctx.memory("memory")?.load_vec(ptr as usize, len as usize)?;
+        Ok(bytes.iter().map(|&b| b as i32).sum::<i32>())
+    });
+    let missing = Extern::typed_func(|mut ctx: FuncContext<'_>, ()| {
+        assert!(ctx.memory("not_memory").is_err());
+        Ok(ctx.memory("other").is_err() as i32)
+    });
+    imports.define("host", "sum", sum)?.define("host", "missing", missing)?;
+
+    let module = Module::parse_bytes(&wasm)?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, Some(imports))?;
+    assert_eq!(instance.exported_func::<(), i32>(&store, "sum")?.call(&mut store, ())?, 6);
+    assert_eq!(instance.exported_func::<(), i32>(&store, "missing")?.call(&mut store, ())?, 1);
+    Ok(())
+}
+
+#[test]
+fn test_imports_alias() -> Result<()> {
+    let provider = wat::parse_str(
+        r#"
+        (module
+            (func (export "add") (param i32 i32) (result i32) (i32.add (local.get 0) (local.get 1)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "\2a")
+        )
+        "#,
+    )?;
+    let consumer = wat::parse_str(
+        r#"
+        (module
+            (import "math" "sum" (func $sum (param i32 i32) (result i32)))
+            (import "env" "mem" (memory 1))
+            (func (export "run") (result i32) (call $sum (i32.load8_u (i32.const 0)) (i32.const 1)))
+        )
+        "#,
+    )?;
+
+    let mut store = Store::default();
+    let provider = Module::parse_bytes(&provider)?.instantiate(&mut store, None)?;
+
+    let mut imports = Imports::new();
+    imports.alias(&store, provider.id(), &[("add", "math", "sum"), ("memory", "env", "mem")])?;
+    let consumer = Module::parse_bytes(&consumer)?.instantiate(&mut store, Some(imports))?;
+    assert_eq!(consumer.exported_func::<(), i32>(&store, "run")?.call(&mut store, ())?, 43);
+
+    // the exports are checked when the alias is defined
+    let mut imports = Imports::new();
+    assert!(imports.alias(&store, provider.id(), &[("add", "math", "sum"), ("sub", "math", "sub")]).is_err());
+    assert!(imports.alias(&store, 100, &[("add", "math", "sum")]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_linking_reports_all_errors() -> Result<()> {
+    let module = Module::parse_bytes(&wat::parse_str(
+        r#"
+        (module
+            (import "env" "a" (func))
+            (import "env" "b" (global i32))
+            (import "env" "c" (memory 1))
+            (import "env" "d" (func (param i32)))
+        )
+        "#,
+    )?)?;
+
+    let mut imports = Imports::new();
+    imports.define("env", "b", Extern::global(WasmValue::I64(0), false))?.define(
+        "env",
+        "c",
+        Extern::memory(MemoryType::new(MemoryArch::I32, 1, None, None)),
+    )?;
+
+    let mut store = Store::default();
+    let Err(Error::Linker(LinkingError::Multiple(errors))) = module.clone().instantiate(&mut store, Some(imports))
+    else {
+        panic!("expected multiple linking errors");
+    };
+    let names: Vec<_> = errors
+        .iter()
+        .map(|err| match err {
+            LinkingError::UnknownImport { name, .. } => ("unknown", name.as_str()),
+            LinkingError::IncompatibleImportType { name, .. } => ("incompatible", name.as_str()),
+            LinkingError::Multiple(_) => panic!("errors are not nested"),
+        })
+        .collect();
+    assert_eq!(names, [("unknown", "a"), ("incompatible", "b"), ("unknown", "d")]);
+
+    // a single failure is reported as is
+    let mut imports = Imports::new();
+    imports
+        .define("env", "a", Extern::typed_func(|_, ()| Ok(())))?
+        .define("env", "b", Extern::global(WasmValue::I32(0), false))?
+        .define("env", "c", Extern::memory(MemoryType::new(MemoryArch::I32, 1, None, None)))?;
+    let res = module.instantiate(&mut store, Some(imports));
+    assert!(matches!(res, Err(Error::Linker(LinkingError::UnknownImport { .. }))));
+    Ok(())
+}
+
+#[test]
+fn test_linking_table_and_memory_limits() -> Result<()> {
+    // (provided min, provided max, required min, required max, should link)
+    let cases = [
+        (1, Some(2), 1, Some(2), true),
+        (2, None, 1, None, true),
+        (1, Some(1), 1, Some(2), true),
+        (1, Some(2), 2, Some(2), false),
+        (1, Some(3), 1, Some(2), false),
+        (1, None, 1, Some(2), false),
+    ];
+
+    let limits = |min: u32, max: Option<u32>| format!("{min} {}", max.map_or(String::new(), |max| max.to_string()));
+    for (min, max, required_min, required_max, should_link) in cases {
+        let provided = limits(min, max);
+        let required = limits(required_min, required_max);
+
+        for kind in ["table", "memory"] {
+            let elem = if kind == "table" { "funcref" } else { "" };
+            let importer = format!(r#"(module (import "env" "{kind}" ({kind} {required} {elem})))"#);
+            let importer = Module::parse_bytes(&wat::parse_str(importer)?)?;
+
+            // defined by the host
+            let value = match kind {
+                "table" => {
+                    Extern::table(TableType::new(ValType::RefFunc, min, max), WasmValue::default_for(ValType::RefFunc))
+                }
+                _ => Extern::memory(MemoryType::new(MemoryArch::I32, min.into(), max.map(u64::from), None)),
+            };
+            let mut store = Store::default();
+            let mut imports = Imports::new();
+            imports.define("env", kind, value)?;
+            let res = importer.clone().instantiate(&mut store, Some(imports));
+            assert_eq!(res.is_ok(), should_link, "{kind} {provided} as {required}");
+            if !should_link {
+                assert!(matches!(res, Err(Error::Linker(LinkingError::IncompatibleImportType { .. }))));
+            }
+
+            // exported by another module
+            let exporter = format!(r#"(module ({kind} (export "{kind}") {provided} {elem}))"#);
+            let exporter = Module::parse_bytes(&wat::parse_str(exporter)?)?.instantiate(&mut store, None)?;
+            let mut imports = Imports::new();
+            imports.link_module("env", exporter.id())?;
+            let res = importer.instantiate(&mut store, Some(imports));
+            assert_eq!(res.is_ok(), should_link, "exported {kind} {provided} as {required}");
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_host_func_trap() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "env" "check" (func $check (param i32)))
+            (global $progress (export "progress") (mut i32) (i32.const 0))
+            (func $inner (param i32)
+                (global.set $progress (i32.const 1))
+                (call $check (local.get 0))
+                (global.set $progress (i32.const 2)))
+            (func (export "run") (param i32) (result i32)
+                (call $inner (local.get 0))
+                (i32.const 42)))
+        "#,
+    )?;
+
+    let mut imports = Imports::new();
+    let check = Extern::typed_func(|_: FuncContext<'_>, value: i32| match value {
+        0 => Err(FuncContext::trap(tinywasm::Trap::Unreachable)),
+        1 => Err(tinywasm::Trap::DivisionByZero.into()),
+        _ => Ok(()),
+    });
+    imports.define("env", "check", check)?;
+
+    let mut store = Store::default();
+    store.capture_backtraces(true);
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, Some(imports))?;
+    let run = instance.exported_func::<i32, i32>(&store, "run")?;
+
+    let Err(Error::Trap(trap)) = run.call(&mut store, 0) else { panic!("expected a trap") };
+    assert!(matches!(trap.inner(), tinywasm::Trap::Unreachable));
+    // the trap unwinds both wasm frames, the code after the host call never runs
+    assert_eq!(trap.backtrace().map(<[_]>::len), Some(2));
+    assert_eq!(instance.exported_global(&store, "progress")?, WasmValue::I32(1));
+
+    let Err(Error::Trap(trap)) = run.call(&mut store, 1) else { panic!("expected a trap") };
+    assert!(matches!(trap.inner(), tinywasm::Trap::DivisionByZero));
+
+    assert_eq!(run.call(&mut store, 2)?, 42);
+    assert_eq!(instance.exported_global(&store, "progress")?, WasmValue::I32(2));
+    Ok(())
+}
+
+#[test]
+fn test_memory_with_data() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "env" "heap" (memory 1))
+            (func (export "load") (param i32) (result i32) (i32.load8_u (local.get 0))))
+        "#,
+    )?;
+
+    let ty = MemoryType::new(MemoryArch::I32, 1, None, None);
+    assert!(matches!(Extern::memory(ty), Extern::Memory { .. }));
+    assert!(matches!(Extern::memory_with_data(ty, &[1])?, Extern::MemoryWithData { ref data, .. } if data[..] == [1]));
+
+    let mut imports = Imports::new();
+    imports.define("env", "heap", Extern::memory_with_data(ty, &[1, 2, 3])?)?;
+
+    let mut store = Store::default();
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, Some(imports))?;
+    let load = instance.exported_func::<i32, i32>(&store, "load")?;
+    assert_eq!(load.call(&mut store, 0)?, 1);
+    assert_eq!(load.call(&mut store, 2)?, 3);
+    assert_eq!(load.call(&mut store, 3)?, 0);
+
+    // the data has to fit into the initial pages, even if the memory could grow
+    let ty = MemoryType::new(MemoryArch::I32, 1, Some(2), None);
+    let res = Extern::memory_with_data(ty, &[0; 0x10001]);
+    assert!(matches!(res, Err(Error::Trap(tinywasm::Trap::MemoryOutOfBounds { len: 0x10001, max: 0x10000, .. }))));
+    assert!(Extern::memory_with_data(ty, &[0; 0x10000]).is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_host_error_downcast() -> Result<()> {
+    #[derive(Debug, PartialEq)]
+    struct QuotaExceeded(u32);
+    impl std::fmt::Display for QuotaExceeded {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "quota of {} exceeded", self.0)
+        }
+    }
+    impl std::error::Error for QuotaExceeded {}
+
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "env" "charge" (func $charge (param i32)))
+            (func (export "run") (param i32) (call $charge (local.get 0))))
+        "#,
+    )?;
+
+    let mut imports = Imports::new();
+    let charge = Extern::typed_func(|_: FuncContext<'_>, amount: i32| match amount {
+        ..=10 => Ok(()),
+        _ => Err(Error::host(QuotaExceeded(10))),
+    });
+    imports.define("env", "charge", charge)?;
+
+    let mut store = Store::default();
+    store.capture_backtraces(true);
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, Some(imports))?;
+    let run = instance.exported_func::<i32, ()>(&store, "run")?;
+    run.call(&mut store, 5)?;
+
+    // the error passes through the wasm code unchanged
+    let err = run.call(&mut store, 11).expect_err("the host function fails");
+    assert_eq!(err.to_string(), "host error: quota of 10 exceeded");
+    assert_eq!(err.downcast_ref::<QuotaExceeded>(), Some(&QuotaExceeded(10)));
+    assert!(std::error::Error::source(&err).is_some_and(|source| source.is::<QuotaExceeded>()));
+
+    let err = err.downcast::<std::fmt::Error>().expect_err("not a fmt::Error");
+    assert_eq!(err.downcast::<QuotaExceeded>().ok(), Some(QuotaExceeded(10)));
+    assert!(Error::Trap(tinywasm::Trap::Unreachable).downcast_ref::<QuotaExceeded>().is_none());
+    Ok(())
+}
+
+#[test]
+fn test_func_with_type() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "env" "sum2" (func $sum2 (param i32 i32) (result i32)))
+            (import "env" "sum3" (func $sum3 (param i32 i32 i32) (result i32 i32)))
+            (func (export "run") (result i32)
+                (call $sum2 (i32.const 1) (i32.const 2))
+                (call $sum3 (i32.const 3) (i32.const 4) (i32.const 5))
+                i32.add
+                i32.add))
+        "#,
+    )?;
+
+    // one implementation serves imports with different signatures, answering with as many sums as requested
+    let sum = |_: FuncContext<'_>, ty: &FuncType, args: &[WasmValue]| {
+        let sum: i32 = args.iter().map(|arg| arg.as_i32().expect("i32 params")).sum();
+        Ok(ty.results.iter().map(|_| WasmValue::I32(sum)).collect())
+    };
+    let sum2 = FuncType { params: Box::new([ValType::I32; 2]), results: Box::new([ValType::I32]) };
+    let sum3 = FuncType { params: Box::new([ValType::I32; 3]), results: Box::new([ValType::I32; 2]) };
+
+    let mut imports = Imports::new();
+    imports.define("env", "sum2", Extern::func_with_type(&sum2, sum))?.define(
+        "env",
+        "sum3",
+        Extern::func_with_type(&sum3, sum),
+    )?;
+
+    let mut store = Store::default();
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, Some(imports))?;
+    assert_eq!(instance.exported_func::<(), i32>(&store, "run")?.call(&mut store, ())?, 3 + 12 + 12);
+
+    // results are still checked against the type
+    let wrong = Extern::func_with_type(&sum2, |_, _, _| Ok(Vec::new()));
+    let mut imports = Imports::new();
+    imports.define("env", "sum2", wrong)?.define("env", "sum3", Extern::func_with_type(&sum3, sum))?;
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, Some(imports))?;
+    let res = instance.exported_func::<(), i32>(&store, "run")?.call(&mut store, ());
+    assert!(matches!(res, Err(Error::InvalidHostFnReturn { .. })));
+    Ok(())
+}
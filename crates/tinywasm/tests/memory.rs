@@ -0,0 +1,522 @@
+use eyre::Result;
+use tinywasm::types::ExternVal;
+use tinywasm::{Error, MemoryStringExt, Module, ModuleInstance, Store, Trap};
+
+fn instantiate(wat: &str) -> Result<(Store, ModuleInstance)> {
+    let module = Module::parse_bytes(&wat::parse_str(wat)?)?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, None)?;
+    Ok((store, instance))
+}
+
+const BULK: &str = r#"
+(module
+    (memory (export "memory") 1)
+    (data $d "\01\02\03\04")
+    (func (export "copy") (param i32 i32 i32) (memory.copy (local.get 0) (local.get 1) (local.get 2)))
+    (func (export "fill") (param i32 i32 i32) (memory.fill (local.get 0) (local.get 1) (local.get 2)))
+    (func (export "init") (param i32 i32 i32) (memory.init $d (local.get 0) (local.get 1) (local.get 2)))
+    (func (export "drop") (data.drop $d))
+)
+"#;
+
+#[test]
+fn test_memory_copy_overlapping() -> Result<()> {
+    let (mut store, instance) = instantiate(BULK)?;
+    instance.exported_memory_mut(&mut store, "memory")?.store(0, 6, &[1, 2, 3, 4, 5, 6])?;
+
+    let copy = instance.exported_func::<(i32, i32, i32), ()>(&store, "copy")?;
+    copy.call(&mut store, (2, 0, 4))?; // forward overlap
+    assert_eq!(instance.exported_memory(&mut store, "memory")?.load(0, 6)?, &[1, 2, 1, 2, 3, 4]);
+    copy.call(&mut store, (0, 2, 4))?; // backward overlap
+    assert_eq!(instance.exported_memory(&mut store, "memory")?.load(0, 6)?, &[1, 2, 3, 4, 3, 4]);
+    Ok(())
+}
+
+#[test]
+fn test_memory_fill() -> Result<()> {
+    let (mut store, instance) = instantiate(BULK)?;
+    let fill = instance.exported_func::<(i32, i32, i32), ()>(&store, "fill")?;
+    fill.call(&mut store, (1, 0x1ff, 3))?; // only the low byte of the value is used
+    assert_eq!(instance.exported_memory(&mut store, "memory")?.load(0, 5)?, &[0, 0xff, 0xff, 0xff, 0]);
+    fill.call(&mut store, (65536, 0, 0))?; // zero-length at the end of memory is fine
+    Ok(())
+}
+
+#[test]
+fn test_memory_init_and_data_drop() -> Result<()> {
+    let (mut store, instance) = instantiate(BULK)?;
+    let init = instance.exported_func::<(i32, i32, i32), ()>(&store, "init")?;
+    init.call(&mut store, (10, 1, 3))?;
+    assert_eq!(instance.exported_memory(&mut store, "memory")?.load(10, 3)?, &[2, 3, 4]);
+
+    let res = init.call(&mut store, (0, 2, 3));
+    assert!(matches!(res, Err(Error::Trap(Trap::MemoryOutOfBounds { .. }))));
+    let res = init.call(&mut store, (0, -1, 1));
+    assert!(matches!(res, Err(Error::Trap(Trap::MemoryOutOfBounds { .. }))));
+
+    instance.exported_func::<(), ()>(&store, "drop")?.call(&mut store, ())?;
+    init.call(&mut store, (0, 0, 0))?; // a dropped segment acts like an empty one
+    let res = init.call(&mut store, (0, 0, 1));
+    assert!(matches!(res, Err(Error::Trap(Trap::MemoryOutOfBounds { .. }))));
+    Ok(())
+}
+
+#[test]
+fn test_multi_memory() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (memory $a (export "a") 1)
+            (memory $b (export "b") 1 3)
+            (func (export "store") (param i32 i32) (i32.store $b (local.get 0) (local.get 1)))
+            (func (export "load_a") (param i32) (result i32) (i32.load $a (local.get 0)))
+            (func (export "load_b") (param i32) (result i32) (i32.load $b (local.get 0)))
+            (func (export "size_b") (result i32) (memory.size $b))
+            (func (export "grow_b") (param i32) (result i32) (memory.grow $b (local.get 0)))
+            (func (export "copy_b_to_a") (param i32 i32 i32) (memory.copy $a $b (local.get 0) (local.get 1) (local.get 2))))
+        "#,
+    )?;
+
+    let load_a = instance.exported_func::<i32, i32>(&store, "load_a")?;
+    let load_b = instance.exported_func::<i32, i32>(&store, "load_b")?;
+    instance.exported_func::<(i32, i32), ()>(&store, "store")?.call(&mut store, (8, 42))?;
+    assert_eq!(load_b.call(&mut store, 8)?, 42);
+    assert_eq!(load_a.call(&mut store, 8)?, 0);
+
+    // size and grow only affect the memory they name
+    assert_eq!(instance.exported_func::<i32, i32>(&store, "grow_b")?.call(&mut store, 2)?, 1);
+    assert_eq!(instance.exported_func::<(), i32>(&store, "size_b")?.call(&mut store, ())?, 3);
+    assert_eq!(instance.exported_memory_mut(&mut store, "a")?.page_count(), 1);
+
+    let copy = instance.exported_func::<(i32, i32, i32), ()>(&store, "copy_b_to_a")?;
+    copy.call(&mut store, (0, 8, 4))?;
+    assert_eq!(load_a.call(&mut store, 0)?, 42);
+    assert_eq!(load_b.call(&mut store, 0)?, 0);
+
+    // the source range is checked against the larger memory, the destination against the smaller one
+    assert!(copy.call(&mut store, (0, 0x20000, 4)).is_ok());
+    assert!(matches!(copy.call(&mut store, (0x10000, 0, 4)), Err(Error::Trap(Trap::MemoryOutOfBounds { .. }))));
+    Ok(())
+}
+
+#[test]
+fn test_store_memory_copy() -> Result<()> {
+    let mut store = Store::default();
+    let module = Module::parse_bytes(&wat::parse_str(r#"(module (memory (export "memory") 1))"#)?)?;
+    let (a, b) = (module.clone().instantiate(&mut store, None)?, module.instantiate(&mut store, None)?);
+    let addr = |instance: &ModuleInstance| match instance.export_addr("memory") {
+        Some(ExternVal::Memory(addr)) => addr,
+        _ => panic!("memory is exported"),
+    };
+    let (mem_a, mem_b) = (addr(&a), addr(&b));
+
+    a.exported_memory_mut(&mut store, "memory")?.store(0, 6, &[1, 2, 3, 4, 5, 6])?;
+    store.memory_copy(mem_b, 100, mem_a, 1, 4)?;
+    assert_eq!(b.exported_memory(&mut store, "memory")?.load(99, 6)?, &[0, 2, 3, 4, 5, 0]);
+
+    // overlapping copies within the same memory
+    store.memory_copy(mem_a, 2, mem_a, 0, 4)?;
+    assert_eq!(a.exported_memory(&mut store, "memory")?.load(0, 6)?, &[1, 2, 1, 2, 3, 4]);
+
+    // out of bounds regions copy nothing
+    for (dst, src) in [(0xfffe, 0), (0, 0xfffe)] {
+        let res = store.memory_copy(mem_b, dst, mem_a, src, 4);
+        assert!(matches!(res, Err(Error::Trap(Trap::MemoryOutOfBounds { .. }))));
+    }
+    assert_eq!(b.exported_memory(&mut store, "memory")?.load(0xfffe, 2)?, &[0, 0]);
+    assert!(matches!(store.memory_copy(mem_a, 0xfffe, mem_a, 0, 4), Err(Error::Trap(_))));
+    assert!(matches!(store.memory_copy(7, 0, mem_a, 0, 1), Err(Error::Other(_))));
+    Ok(())
+}
+
+#[test]
+fn test_bulk_memory_trap_leaves_memory_untouched() -> Result<()> {
+    let (mut store, instance) = instantiate(BULK)?;
+    let pattern: Vec<u8> = (0..16).collect();
+    instance.exported_memory_mut(&mut store, "memory")?.store(65520, 16, &pattern)?;
+    instance.exported_memory_mut(&mut store, "memory")?.store(0, 16, &pattern)?;
+
+    let copy = instance.exported_func::<(i32, i32, i32), ()>(&store, "copy")?;
+    let fill = instance.exported_func::<(i32, i32, i32), ()>(&store, "fill")?;
+    let init = instance.exported_func::<(i32, i32, i32), ()>(&store, "init")?;
+
+    let oob = |res: tinywasm::Result<()>| matches!(res, Err(Error::Trap(Trap::MemoryOutOfBounds { .. })));
+    assert!(oob(fill.call(&mut store, (65528, 0xee, 9))));
+    assert!(oob(fill.call(&mut store, (-1, 0xee, 1))));
+    assert!(oob(copy.call(&mut store, (65528, 0, 9))));
+    assert!(oob(copy.call(&mut store, (0, 65528, 9))));
+    assert!(oob(init.call(&mut store, (65534, 0, 4))));
+    assert!(oob(init.call(&mut store, (0, 2, 3))));
+
+    let memory = instance.exported_memory(&mut store, "memory")?;
+    assert_eq!(memory.load(65520, 16)?, pattern);
+    assert_eq!(memory.load(0, 16)?, pattern);
+    Ok(())
+}
+
+#[test]
+fn test_unaligned_access() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (memory 1)
+            (func (export "roundtrip") (param i32 i64) (result i64)
+                (i64.store align=1 (local.get 0) (local.get 1))
+                (i64.load align=8 (local.get 0)))
+        )
+        "#,
+    )?;
+
+    // alignment is only a hint, misaligned addresses must not trap
+    let roundtrip = instance.exported_func::<(i32, i64), i64>(&store, "roundtrip")?;
+    assert_eq!(roundtrip.call(&mut store, (3, 0x0102_0304_0506_0708))?, 0x0102_0304_0506_0708);
+    assert_eq!(roundtrip.call(&mut store, (65527, -1))?, -1);
+    Ok(())
+}
+
+#[test]
+fn test_over_aligned_access_rejected() -> Result<()> {
+    let cases = [
+        "(i32.load8_u align=2 (i32.const 0)) drop",
+        "(i32.load align=8 (i32.const 0)) drop",
+        "(i64.load32_s align=8 (i32.const 0)) drop",
+        "(i32.store16 align=4 (i32.const 0) (i32.const 0))",
+    ];
+
+    for body in cases {
+        let wasm = wat::parse_str(format!("(module (memory 1) (func {body}))"))?;
+        assert!(matches!(Module::parse_bytes(&wasm), Err(Error::ParseError(_))), "{body}");
+    }
+
+    let wasm = wat::parse_str("(module (memory 1) (func (i32.load8_u align=1 (i32.const 0)) drop))")?;
+    Module::parse_bytes(&wasm)?;
+    Ok(())
+}
+
+#[test]
+fn test_load_strings() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (memory (export "memory") 1)
+            (data (i32.const 0) "hello\00w\c3\b6rld\00\ff\fe\00")
+            (data (i32.const 65532) "tail")
+        )
+        "#,
+    )?;
+
+    let memory = instance.exported_memory(&mut store, "memory")?;
+    assert_eq!(memory.load_str_until_nul(0)?, "hello");
+    assert_eq!(memory.load_str_until_nul(6)?, "wörld");
+    assert_eq!(memory.load_str_until_nul(5)?, "");
+    assert_eq!(memory.load_utf8(6, 6)?, "wörld");
+    assert_eq!(memory.load_string(0, 5)?, "hello");
+
+    assert!(matches!(memory.load_str_until_nul(13), Err(Error::InvalidUtf8(_))));
+    assert!(matches!(memory.load_utf8(8, 2), Err(Error::InvalidUtf8(_))));
+    assert!(matches!(memory.load_utf8(65535, 2), Err(Error::Trap(Trap::MemoryOutOfBounds { .. }))));
+
+    // no terminator before the end of memory
+    assert!(matches!(memory.load_str_until_nul(65532), Err(Error::Trap(Trap::MemoryOutOfBounds { .. }))));
+    assert!(matches!(memory.load_str_until_nul(65537), Err(Error::Trap(Trap::MemoryOutOfBounds { .. }))));
+    Ok(())
+}
+
+#[test]
+fn test_typed_load_store() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "i64.load") (param i32) (result i64) (i64.load (local.get 0)))
+            (func (export "f32.store") (param i32 f32) (f32.store (local.get 0) (local.get 1)))
+        )
+        "#,
+    )?;
+
+    let mut memory = instance.exported_memory_mut(&mut store, "memory")?;
+    memory.store_as(8, 0x0102_0304_0506_0708_i64)?;
+    memory.store_as(16, -2_i16)?;
+    memory.store_as(65532, 1.5_f32)?;
+    assert_eq!(memory.load(8, 8)?, [8, 7, 6, 5, 4, 3, 2, 1]);
+    assert_eq!(memory.load_as::<u32>(12)?, 0x0102_0304);
+    assert_eq!(memory.load_as::<u16>(16)?, 0xfffe);
+    assert_eq!(memory.load_as::<u8>(9)?, 7);
+    assert_eq!(memory.load_as::<f32>(65532)?, 1.5);
+
+    let oob = |res: tinywasm::Result<()>| matches!(res, Err(Error::Trap(Trap::MemoryOutOfBounds { .. })));
+    assert!(oob(memory.store_as(65533, 0_u32)));
+    assert!(oob(memory.store_as(usize::MAX, 0_u8)));
+    assert!(memory.load_as::<u64>(65529).is_err());
+    assert_eq!(memory.load_as::<u32>(65532)?, 1.5_f32.to_bits()); // failed stores leave memory untouched
+
+    // the layout matches what the guest sees
+    let i64_load = instance.exported_func::<i32, i64>(&store, "i64.load")?;
+    assert_eq!(i64_load.call(&mut store, 8)?, 0x0102_0304_0506_0708);
+    instance.exported_func::<(i32, f32), ()>(&store, "f32.store")?.call(&mut store, (0, -0.25))?;
+    assert_eq!(instance.exported_memory(&mut store, "memory")?.load_as::<f32>(0)?, -0.25);
+    Ok(())
+}
+
+#[test]
+fn test_memory_data_slices() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"(module
+            (memory (export "memory") 1)
+            (memory (export "small") 3 3 (pagesize 1))
+            (func (export "sum") (param i32 i32) (result i32) (local $sum i32)
+                (block (loop
+                    (br_if 1 (i32.eqz (local.get 1)))
+                    (local.set $sum (i32.add (local.get $sum) (i32.load8_u (local.get 0))))
+                    (local.set 0 (i32.add (local.get 0) (i32.const 1)))
+                    (local.set 1 (i32.sub (local.get 1) (i32.const 1)))
+                    (br 0)))
+                (local.get $sum))
+        )"#,
+    )?;
+
+    let buffer: Vec<u8> = (0..=255).collect();
+    let mut memory = instance.exported_memory_mut(&mut store, "memory")?;
+    assert_eq!(memory.data().len(), 65536);
+    memory.data_mut()[1024..1280].copy_from_slice(&buffer);
+    assert_eq!(memory.data()[1024..1280], buffer[..]);
+
+    let sum = instance.exported_func::<(i32, i32), i32>(&store, "sum")?;
+    assert_eq!(sum.call(&mut store, (1024, 256))?, (0..=255).sum::<i32>());
+
+    assert_eq!(instance.exported_memory(&mut store, "memory")?.data()[1025], 1);
+    assert_eq!(instance.exported_memory(&mut store, "small")?.data().len(), 3);
+    Ok(())
+}
+
+#[test]
+fn test_snapshot_restore() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (memory (export "memory") 1 8)
+            (table (export "table") 1 funcref)
+            (global $counter (export "counter") (mut i32) (i32.const 0))
+            (data (i32.const 0) "\2a")
+            (func $f)
+            (elem declare func $f)
+            (func (export "table_size") (result i32) (table.size))
+            (func (export "run")
+                (i32.store8 (i32.const 0) (i32.add (i32.load8_u (i32.const 0)) (i32.const 1)))
+                (drop (memory.grow (i32.const 2)))
+                (drop (table.grow (ref.func $f) (i32.const 3)))
+                (global.set $counter (i32.add (global.get $counter) (i32.const 1))))
+        )
+        "#,
+    )?;
+
+    let snapshot = store.snapshot_instance(instance.id())?;
+    let memory = instance.exported_memory(&mut store, "memory")?.snapshot();
+    assert_eq!(memory.page_count(), 1);
+
+    let run = instance.exported_func::<(), ()>(&store, "run")?;
+    run.call(&mut store, ())?;
+    run.call(&mut store, ())?;
+    assert_eq!(instance.exported_memory_mut(&mut store, "memory")?.page_count(), 5);
+    assert_eq!(instance.exported_memory(&mut store, "memory")?.load(0, 1)?, [0x2c]);
+    assert_eq!(instance.exported_global(&store, "counter")?.as_i32(), Some(2));
+
+    store.restore_instance(&snapshot)?;
+    assert_eq!(instance.exported_memory_mut(&mut store, "memory")?.page_count(), 1);
+    assert_eq!(instance.exported_memory(&mut store, "memory")?.load(0, 1)?, [0x2a]);
+    assert!(instance.exported_memory(&mut store, "memory")?.load(65536, 1).is_err());
+    assert_eq!(instance.exported_global(&store, "counter")?.as_i32(), Some(0));
+
+    let table_size = instance.exported_func::<(), i32>(&store, "table_size")?;
+    assert_eq!(table_size.call(&mut store, ())?, 1);
+
+    // replaying gives the same result as the first run
+    run.call(&mut store, ())?;
+    assert_eq!(table_size.call(&mut store, ())?, 4);
+    assert_eq!(instance.exported_memory(&mut store, "memory")?.load(0, 1)?, [0x2b]);
+    assert_eq!(instance.exported_global(&store, "counter")?.as_i32(), Some(1));
+    assert_eq!(instance.exported_memory_mut(&mut store, "memory")?.page_count(), 3);
+
+    // memory snapshots can be restored on their own
+    let mut mem = instance.exported_memory_mut(&mut store, "memory")?;
+    mem.restore(&memory)?;
+    assert_eq!((mem.page_count(), mem.load(0, 1)?[0]), (1, 0x2a));
+
+    let (mut other_store, other) = instantiate(r#"(module (memory (export "memory") 2))"#)?;
+    assert!(other.exported_memory_mut(&mut other_store, "memory")?.restore(&memory).is_err());
+    assert!(matches!(other_store.restore_instance(&snapshot), Err(Error::InvalidStore)));
+    Ok(())
+}
+
+#[test]
+fn test_call_with_memory_budget() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"(module
+            (memory (export "memory") 1 8)
+            (func (export "grow") (param i32) (result i32) (memory.grow (local.get 0)))
+        )"#,
+    )?;
+
+    let grow = instance.exported_func::<i32, i32>(&store, "grow")?;
+    assert_eq!(grow.call_with_memory_budget(&mut store, 1, 2 * 65536)?, 1);
+    assert_eq!(grow.call_with_memory_budget(&mut store, 2, 2 * 65536)?, 2);
+
+    let res = grow.call_with_memory_budget(&mut store, 2, 65536);
+    assert!(matches!(res, Err(Error::Trap(Trap::MemoryBudgetExceeded { requested: 131072, remaining: 65536 }))));
+    assert_eq!(instance.exported_memory_mut(&mut store, "memory")?.page_count(), 4);
+
+    // growing past the declared maximum fails as usual instead of exceeding the budget
+    assert_eq!(grow.call_with_memory_budget(&mut store, 5, 65536)?, -1);
+    assert_eq!(grow.call_with_memory_budget(&mut store, -1, 0)?, -1);
+
+    // the budget only applies to a single call
+    assert_eq!(grow.call(&mut store, 4)?, 4);
+    assert_eq!(grow.call(&mut store, 1)?, -1);
+    Ok(())
+}
+
+#[test]
+fn test_memory_accounting() -> Result<()> {
+    let mut store = Store::default();
+    let wasm = wat::parse_str(
+        r#"(module
+            (memory (export "memory") 1)
+            (func (export "grow") (param i32) (result i32) (memory.grow (local.get 0)))
+        )"#,
+    )?;
+    let a = Module::parse_bytes(&wasm)?.instantiate(&mut store, None)?;
+    let b = Module::parse_bytes(&wat::parse_str("(module (memory 2))")?)?.instantiate(&mut store, None)?;
+    assert_eq!(store.total_memory_bytes(), 3 * 65536);
+
+    a.exported_func::<i32, i32>(&store, "grow")?.call(&mut store, 3)?;
+    assert_eq!(store.module_memory_bytes(a.id()), 4 * 65536);
+    assert_eq!(store.module_memory_bytes(b.id()), 2 * 65536);
+    assert_eq!(store.total_memory_bytes(), 6 * 65536);
+    Ok(())
+}
+
+#[test]
+fn test_max_memory_pages() -> Result<()> {
+    let mut store = Store::default();
+    store.set_max_memory_pages(4);
+    let wasm = wat::parse_str(
+        r#"(module
+            (memory $bounded (export "bounded") 1 2)
+            (memory $unbounded (export "unbounded") 1)
+            (func (export "grow_bounded") (param i32) (result i32) (memory.grow $bounded (local.get 0)))
+            (func (export "grow_unbounded") (param i32) (result i32) (memory.grow $unbounded (local.get 0)))
+        )"#,
+    )?;
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, None)?;
+    let grow_bounded = instance.exported_func::<i32, i32>(&store, "grow_bounded")?;
+    let grow_unbounded = instance.exported_func::<i32, i32>(&store, "grow_unbounded")?;
+
+    // the module's maximum is lower than the store's
+    assert_eq!(grow_bounded.call(&mut store, 2)?, -1);
+    assert_eq!(grow_bounded.call(&mut store, 1)?, 1);
+
+    // the store's maximum applies to memories without one
+    assert_eq!(grow_unbounded.call(&mut store, 4)?, -1);
+    assert_eq!(grow_unbounded.call(&mut store, 3)?, 1);
+    assert_eq!(grow_unbounded.call(&mut store, 1)?, -1);
+    assert_eq!(grow_unbounded.call(&mut store, 0)?, 4);
+    assert_eq!(instance.exported_memory_mut(&mut store, "unbounded")?.page_count(), 4);
+    Ok(())
+}
+
+#[test]
+fn test_max_memory_pages_memory64() -> Result<()> {
+    let mut store = Store::default();
+    store.set_max_memory_pages(4);
+    let wasm = wat::parse_str(
+        r#"(module
+            (memory (export "memory") i64 1)
+            (func (export "grow") (param i64) (result i64) (memory.grow (local.get 0)))
+        )"#,
+    )?;
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, None)?;
+    let grow = instance.exported_func::<i64, i64>(&store, "grow")?;
+
+    // deltas that overflow the page count are over the cap, not wrapped around below it
+    assert_eq!(grow.call(&mut store, i64::MAX)?, -1);
+    assert_eq!(grow.call(&mut store, i64::MAX - 1)?, -1);
+    assert_eq!(grow.call(&mut store, 3)?, 1);
+    assert_eq!(grow.call(&mut store, 1)?, -1);
+    Ok(())
+}
+
+#[test]
+fn test_memory64() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"(module
+            (memory (export "memory") i64 1 3)
+            (data (i64.const 8) "\2a")
+            (func (export "load") (param i64) (result i64) (i64.load (local.get 0)))
+            (func (export "load_offset") (param i64) (result i32) (i32.load8_u offset=0xffffffff (local.get 0)))
+            (func (export "store") (param i64 i64) (i64.store (local.get 0) (local.get 1)))
+            (func (export "size") (result i64) (memory.size))
+            (func (export "grow") (param i64) (result i64) (memory.grow (local.get 0)))
+            (func (export "fill") (param i64 i32 i64) (memory.fill (local.get 0) (local.get 1) (local.get 2)))
+            (func (export "copy") (param i64 i64 i64) (memory.copy (local.get 0) (local.get 1) (local.get 2)))
+        )"#,
+    )?;
+    let load = instance.exported_func::<i64, i64>(&store, "load")?;
+    let store_i64 = instance.exported_func::<(i64, i64), ()>(&store, "store")?;
+    let size = instance.exported_func::<(), i64>(&store, "size")?;
+    let grow = instance.exported_func::<i64, i64>(&store, "grow")?;
+
+    assert_eq!(load.call(&mut store, 8)?, 42);
+    store_i64.call(&mut store, (16, -2))?;
+    assert_eq!(load.call(&mut store, 16)?, -2);
+
+    // addresses beyond 32 bits are bounds checked instead of being truncated
+    fn oob<T>(res: tinywasm::Result<T>) -> bool {
+        matches!(res, Err(Error::Trap(Trap::MemoryOutOfBounds { .. })))
+    }
+    assert!(oob(load.call(&mut store, 1 << 32)));
+    assert!(oob(load.call(&mut store, -1)));
+    assert!(oob(store_i64.call(&mut store, ((1 << 32) + 16, 0))));
+    assert!(oob(instance.exported_func::<i64, i32>(&store, "load_offset")?.call(&mut store, i64::MAX)));
+
+    assert_eq!(size.call(&mut store, ())?, 1);
+    assert_eq!(grow.call(&mut store, 1)?, 1);
+    assert_eq!(grow.call(&mut store, 2)?, -1);
+    assert_eq!(grow.call(&mut store, -1)?, -1); // the delta is unsigned
+    assert_eq!(grow.call(&mut store, 1 << 40)?, -1);
+    assert_eq!(size.call(&mut store, ())?, 2);
+
+    instance.exported_func::<(i64, i32, i64), ()>(&store, "fill")?.call(&mut store, (65536, 7, 4))?;
+    instance.exported_func::<(i64, i64, i64), ()>(&store, "copy")?.call(&mut store, (0, 65536, 4))?;
+    assert_eq!(instance.exported_memory(&mut store, "memory")?.load(0, 5)?, &[7, 7, 7, 7, 0]);
+    let fill = instance.exported_func::<(i64, i32, i64), ()>(&store, "fill")?;
+    assert!(oob(fill.call(&mut store, (0, 0, 1 << 33))));
+    Ok(())
+}
+
+#[test]
+fn test_memory_grow_delta_is_unsigned() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"(module (memory (export "memory") 2) (func (export "grow") (param i32) (result i32) (memory.grow (local.get 0))))"#,
+    )?;
+    let grow = instance.exported_func::<i32, i32>(&store, "grow")?;
+    assert_eq!(grow.call(&mut store, -1)?, -1);
+    assert_eq!(instance.exported_memory_mut(&mut store, "memory")?.page_count(), 2);
+
+    // memories can't shrink from the host either
+    let mut memory = instance.exported_memory_mut(&mut store, "memory")?;
+    assert_eq!(memory.grow(-1), None);
+    assert_eq!(memory.grow(i32::MIN), None);
+    assert_eq!(memory.page_count(), 2);
+    assert_eq!(memory.grow(1), Some(2));
+    Ok(())
+}
+
+#[test]
+fn test_memory64_too_large_for_host() -> Result<()> {
+    // a valid module whose memory can't be allocated fails to instantiate instead of aborting the host
+    let wasm = wat::parse_str(r#"(module (memory i64 0x1000000000))"#)?;
+    let mut store = Store::default();
+    assert!(matches!(Module::parse_bytes(&wasm)?.instantiate(&mut store, None), Err(Error::Other(_))));
+    Ok(())
+}
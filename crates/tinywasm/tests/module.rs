@@ -0,0 +1,661 @@
+use eyre::Result;
+use tinywasm::parser::ParseError;
+use tinywasm::types::{ExternVal, ExternalKind, FuncRef, FuncType, ValType, WasmValue};
+use tinywasm::{Error, Extern, Imports, Module, ModuleInstance, Store};
+
+#[test]
+fn test_module_inner() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (func $a)
+            (func $init)
+            (start $init)
+        )
+        "#,
+    )?;
+
+    let module = Module::parse_bytes(&wasm)?;
+    assert_eq!(module.inner().start_func, Some(1));
+    assert_eq!(module.inner().funcs.len(), 2);
+
+    let inner = module.into_inner();
+    assert_eq!(inner.start_func, Some(1));
+    assert_eq!(Module::from(inner).inner().start_func, Some(1));
+    Ok(())
+}
+
+#[test]
+fn test_start_section() -> Result<()> {
+    // the start function copies a data segment into a table-selected slot, so it has to run
+    // after both the data and the elements have been initialized
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (memory (export "memory") 1)
+            (data (i32.const 0) "\2a")
+            (table 1 funcref)
+            (elem (i32.const 0) $dst)
+            (type $addr (func (result i32)))
+            (func $dst (result i32) (i32.const 8))
+            (func $init
+                (i32.store8 (call_indirect (type $addr) (i32.const 0)) (i32.load8_u (i32.const 0))))
+            (start $init)
+        )
+        "#,
+    )?;
+    let module = Module::parse_bytes(&wasm)?;
+
+    let mut store = Store::default();
+    let instance = module.clone().instantiate(&mut store, None)?;
+    assert_eq!(instance.exported_memory(&mut store, "memory")?.load(8, 1)?, [42]);
+
+    // instantiating the instance directly leaves running the start function to the caller
+    let instance = ModuleInstance::instantiate(&mut store, module, None)?;
+    assert_eq!(instance.exported_memory(&mut store, "memory")?.load(8, 1)?, [0]);
+    assert_eq!(instance.start(&mut store)?, Some(()));
+    assert_eq!(instance.exported_memory(&mut store, "memory")?.load(8, 1)?, [42]);
+
+    let instance = Module::parse_bytes(&wat::parse_str("(module)")?)?.instantiate(&mut store, None)?;
+    assert_eq!(instance.start(&mut store)?, None);
+    Ok(())
+}
+
+#[test]
+fn test_module_builder() -> Result<()> {
+    use tinywasm::types::{
+        FuncType, Instruction, MemoryArg, MemoryType, ModuleBuildError, TinyWasmModuleBuilder, ValType,
+    };
+
+    let mut builder = TinyWasmModuleBuilder::new();
+    let binary =
+        builder.add_func_type(FuncType { params: Box::new([ValType::I32; 2]), results: Box::new([ValType::I32]) });
+    let empty = builder.add_func_type(FuncType::default());
+    let memory = builder.add_memory(MemoryType::new(tinywasm::types::MemoryArch::I32, 1, None, None));
+
+    // the declared local comes after the two parameters
+    let add = builder.add_func(
+        binary,
+        &[ValType::I32],
+        vec![Instruction::LocalGet32(0), Instruction::LocalGet32(1), Instruction::I32Add, Instruction::LocalTee32(2)],
+    );
+    let start = builder.add_func(
+        empty,
+        &[],
+        vec![Instruction::I32Const(0), Instruction::I32Const(7), Instruction::I32Store(MemoryArg::new(0, memory))],
+    );
+    builder.add_export("add", ExternalKind::Func, add).add_export("memory", ExternalKind::Memory, memory);
+    builder.set_start_func(start);
+
+    let mut store = Store::default();
+    let instance = Module::from(builder.build()?).instantiate(&mut store, None)?;
+    assert_eq!(instance.exported_func::<(i32, i32), i32>(&store, "add")?.call(&mut store, (20, 22))?, 42);
+    assert_eq!(instance.exported_memory(&mut store, "memory")?.load(0, 4)?, 7u32.to_le_bytes());
+
+    let mut builder = TinyWasmModuleBuilder::new();
+    builder.add_func(3, &[], vec![]);
+    assert_eq!(builder.build(), Err(ModuleBuildError::UnknownType { func: 0, ty: 3 }));
+
+    let mut builder = TinyWasmModuleBuilder::new();
+    builder.add_export("memory", ExternalKind::Memory, 0);
+    assert!(matches!(builder.build(), Err(ModuleBuildError::UnknownExport { name }) if &*name == "memory"));
+
+    let mut builder = TinyWasmModuleBuilder::new();
+    let ty = builder.add_func_type(FuncType::default());
+    let func = builder.add_func(ty, &[], vec![]);
+    builder.add_export("f", ExternalKind::Func, func).add_export("f", ExternalKind::Func, func);
+    assert!(matches!(builder.build(), Err(ModuleBuildError::DuplicateExport { name }) if &*name == "f"));
+
+    let mut builder = TinyWasmModuleBuilder::new();
+    let ty = builder.add_func_type(FuncType { params: Box::new([ValType::I32]), results: Box::new([]) });
+    let func = builder.add_func(ty, &[], vec![]);
+    builder.set_start_func(func);
+    assert_eq!(builder.build(), Err(ModuleBuildError::InvalidStartFunc { func }));
+    Ok(())
+}
+
+#[test]
+fn test_parse_error_display() {
+    // a type section without its size
+    let err = Module::parse_bytes(b"\0asm\x01\0\0\0\x01").unwrap_err();
+    let message = err.to_string();
+    assert!(message.starts_with("error parsing module: "), "{message}");
+    assert!(message.ends_with(" at offset 9"), "{message}");
+    assert!(!message.contains("ParseError"), "{message}");
+}
+
+#[test]
+fn test_parse_error_offset() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"(module
+            (memory 1)
+            (func (export "store") (param i32) (i32.store (local.get 0) (i32.const 42)))
+        )"#,
+    )?;
+
+    // the sections after the 8 byte header are an id followed by the LEB128 encoded size of their contents
+    let mut last_section = 8;
+    let mut pos = 8;
+    while pos < wasm.len() {
+        last_section = pos;
+        pos += 1;
+        let (mut size, mut shift) = (0, 0);
+        loop {
+            let byte = wasm[pos];
+            pos += 1;
+            size |= ((byte & 0x7f) as usize) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        pos += size;
+    }
+
+    let truncated = &wasm[..wasm.len() - 3];
+    let Err(Error::ParseError(err)) = Module::parse_bytes(truncated) else { panic!("expected a parse error") };
+    let offset = err.offset().expect("no offset for a truncated module");
+    assert!((last_section..=truncated.len()).contains(&offset), "{offset} not in the last section at {last_section}");
+
+    // unsupported operators report the function they're in, counting imported functions
+    let wasm = wat::parse_str(
+        r#"(module
+            (import "host" "f" (func))
+            (func)
+            (func (param funcref) (drop (ref.as_non_null (local.get 0))))
+        )"#,
+    )?;
+    match Module::parse_bytes(&wasm) {
+        Err(Error::ParseError(ParseError::UnsupportedOperator { func, offset, .. })) => {
+            assert_eq!(func, Some(2));
+            assert!(offset > 8 && offset < wasm.len());
+        }
+        res => panic!("expected an unsupported operator, got {res:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_store_find_export() -> Result<()> {
+    let a = Module::parse_bytes(&wat::parse_str(r#"(module (func (export "run")) (memory (export "mem") 1))"#)?)?;
+    let b = Module::parse_bytes(&wat::parse_str(r#"(module (func) (func (export "run")))"#)?)?;
+
+    let mut store = Store::default();
+    let a = a.instantiate(&mut store, None)?;
+    let b = b.instantiate(&mut store, None)?;
+
+    let found = store.find_export("run");
+    assert_eq!(found.len(), 2);
+    assert!(matches!(found[0], (id, ExternVal::Func(addr)) if id == a.id() && addr == 0));
+    assert!(matches!(found[1], (id, ExternVal::Func(addr)) if id == b.id() && addr == 2));
+
+    assert!(matches!(store.find_export("mem")[..], [(id, ExternVal::Memory(_))] if id == a.id()));
+    assert!(store.find_export("missing").is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_is_imported() -> Result<()> {
+    let provider = Module::parse_bytes(&wat::parse_str(
+        r#"
+        (module
+            (memory (export "memory") 1)
+            (table (export "table") 1 funcref)
+            (global (export "global") i32 (i32.const 1))
+            (func (export "func"))
+        )
+        "#,
+    )?)?;
+    let consumer = Module::parse_bytes(&wat::parse_str(
+        r#"
+        (module
+            (import "provider" "memory" (memory 1))
+            (import "provider" "table" (table 1 funcref))
+            (import "provider" "global" (global i32))
+            (import "provider" "func" (func))
+            (memory 1)
+            (table 1 funcref)
+            (global i32 (i32.const 2))
+            (func)
+        )
+        "#,
+    )?)?;
+
+    let mut store = Store::default();
+    let provider = provider.instantiate(&mut store, None)?;
+    let mut imports = Imports::new();
+    imports.link_module("provider", provider.id())?;
+    let consumer = consumer.instantiate(&mut store, Some(imports))?;
+
+    assert!(!provider.is_memory_imported(0));
+    assert!(consumer.is_memory_imported(0));
+    assert!(!consumer.is_memory_imported(1));
+    assert!(consumer.is_table_imported(0));
+    assert!(!consumer.is_table_imported(1));
+    assert!(consumer.is_global_imported(0));
+    assert!(!consumer.is_global_imported(1));
+    assert!(consumer.is_func_imported(0));
+    assert!(!consumer.is_func_imported(1));
+    assert!(!consumer.is_memory_imported(2));
+    Ok(())
+}
+
+#[test]
+fn test_instance_exports() -> Result<()> {
+    let module = Module::parse_bytes(&wat::parse_str(
+        r#"
+        (module
+            (memory (export "memory") 1)
+            (global (export "global") i32 (i32.const 1))
+            (func $a (export "a"))
+            (func $b (export "b"))
+        )
+        "#,
+    )?)?;
+
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, None)?;
+
+    let exports: Vec<_> = instance.exports().collect();
+    assert_eq!(exports.len(), 4);
+    assert!(matches!(exports[0], ("memory", ExternVal::Memory(_))));
+    assert!(matches!(exports[1], ("global", ExternVal::Global(_))));
+
+    let funcs: Vec<_> =
+        instance.exports().filter(|(_, val)| val.kind() == ExternalKind::Func).map(|(name, _)| name).collect();
+    assert_eq!(funcs, ["a", "b"]);
+
+    for (name, val) in instance.exports() {
+        assert_eq!(instance.export_addr(name).map(|v| v.kind()), Some(val.kind()));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_func_type() -> Result<()> {
+    let module = Module::parse_bytes(&wat::parse_str(
+        r#"
+        (module
+            (func (export "log") (import "host" "log") (param i64))
+            (memory (export "memory") 1)
+            (func (export "add") (param i32 f64) (result f64 i32) (local.get 1) (local.get 0))
+        )
+        "#,
+    )?)?;
+
+    let mut store = Store::default();
+    let mut imports = Imports::new();
+    imports.define("host", "log", Extern::typed_func(|_, _: i64| Ok(())))?;
+    let instance = module.instantiate(&mut store, Some(imports))?;
+
+    let ty = instance.func_type(&store, "add").expect("add is exported");
+    assert_eq!(*ty.params, [ValType::I32, ValType::F64]);
+    assert_eq!(*ty.results, [ValType::F64, ValType::I32]);
+    assert_eq!(
+        instance.func_type(&store, "log"),
+        Some(&FuncType { params: Box::new([ValType::I64]), results: Box::new([]) })
+    );
+
+    assert_eq!(instance.func_type(&store, "memory"), None);
+    assert_eq!(instance.func_type(&store, "missing"), None);
+    assert_eq!(instance.func_type(&Store::default(), "add"), None);
+    Ok(())
+}
+
+#[test]
+fn test_function_signature() -> Result<()> {
+    let module = Module::parse_bytes(&wat::parse_str(
+        r#"
+        (module
+            (type $void (func))
+            (type $binop (func (param i32 i32) (result i32)))
+            (import "env" "memory" (memory 1))
+            (import "env" "log" (func $log (param i64)))
+            (func $add (type $binop) (i32.add (local.get 0) (local.get 1)))
+            (func $noop (type $void))
+        )
+        "#,
+    )?)?;
+
+    assert!(module.func_types().contains(&FuncType { params: Box::new([]), results: Box::new([]) }));
+    assert!(module.func_types().contains(&FuncType { params: Box::new([ValType::I64]), results: Box::new([]) }));
+
+    // imported functions come first, imported memories don't take up function indices
+    assert_eq!(*module.function_signature(0).expect("log is imported").params, [ValType::I64]);
+    let add = module.function_signature(1).expect("add is defined");
+    assert_eq!((&*add.params, &*add.results), (&[ValType::I32, ValType::I32][..], &[ValType::I32][..]));
+    assert_eq!(module.function_signature(2), Some(&FuncType { params: Box::new([]), results: Box::new([]) }));
+    assert_eq!(module.function_signature(3), None);
+    Ok(())
+}
+
+#[test]
+fn test_func_ref() -> Result<()> {
+    let provider = Module::parse_bytes(&wat::parse_str(
+        r#"
+        (module
+            (global $base (mut i32) (i32.const 10))
+            (func (export "add_base") (param i32) (result i32) (i32.add (global.get $base) (local.get 0)))
+            (memory (export "memory") 1))
+        "#,
+    )?)?;
+    let proxy = Module::parse_bytes(&wat::parse_str(
+        r#"
+        (module
+            (table 1 funcref)
+            (func (export "call") (param funcref i32) (result i32)
+                (table.set (i32.const 0) (local.get 0))
+                (call_indirect (param i32) (result i32) (local.get 1) (i32.const 0))))
+        "#,
+    )?)?;
+
+    let mut store = Store::default();
+    let provider = provider.instantiate(&mut store, None)?;
+    let proxy = proxy.instantiate(&mut store, None)?;
+
+    // the function runs in the instance that exported it, not the one calling it
+    let add_base = provider.func_ref(&store, "add_base")?;
+    assert!(!add_base.is_null());
+    let call = proxy.exported_func::<(FuncRef, i32), i32>(&store, "call")?;
+    assert_eq!(call.call(&mut store, (add_base, 5))?, 15);
+
+    assert!(matches!(provider.func_ref(&store, "memory"), Err(Error::Other(_))));
+    assert!(matches!(provider.func_ref(&store, "missing"), Err(Error::Other(_))));
+    assert!(matches!(provider.func_ref(&Store::default(), "add_base"), Err(Error::InvalidStore)));
+    Ok(())
+}
+
+#[test]
+fn test_drop_instance() -> Result<()> {
+    let provider = Module::parse_bytes(&wat::parse_str(
+        r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "one") (result i32) (i32.const 1))
+        )
+        "#,
+    )?)?;
+    let consumer = Module::parse_bytes(&wat::parse_str(
+        r#"
+        (module
+            (import "provider" "one" (func $one (result i32)))
+            (func (export "two") (result i32) (i32.add (call $one) (call $one)))
+        )
+        "#,
+    )?)?;
+    let holder =
+        Module::parse_bytes(&wat::parse_str(r#"(module (global (export "f") (mut funcref) (ref.null func)))"#)?)?;
+
+    let mut store = Store::default();
+    let provider_instance = provider.clone().instantiate(&mut store, None)?;
+    let mut imports = Imports::new();
+    imports.link_module("provider", provider_instance.id())?;
+    let consumer = consumer.instantiate(&mut store, Some(imports))?;
+
+    let one = provider_instance.exported_func::<(), i32>(&store, "one")?;
+    let two = consumer.exported_func::<(), i32>(&store, "two")?;
+    assert_eq!(two.call(&mut store, ())?, 2);
+
+    // the consumer still imports from the provider
+    assert!(store.drop_instance(provider_instance.id()).is_err());
+    store.drop_instance(consumer.id())?;
+    assert!(store.get_module_instance(consumer.id()).is_none());
+    assert!(two.call(&mut store, ()).is_err());
+    assert!(store.drop_instance(consumer.id()).is_err());
+
+    // a function reference stored in another instance's global keeps the provider alive
+    let holder = holder.instantiate(&mut store, None)?;
+    let Some(ExternVal::Func(one_addr)) = provider_instance.export_addr("one") else { panic!("expected a function") };
+    holder.set_exported_global(&mut store, "f", WasmValue::RefFunc(FuncRef::new(Some(one_addr))))?;
+    assert!(store.drop_instance(provider_instance.id()).is_err());
+    holder.set_exported_global(&mut store, "f", WasmValue::RefFunc(FuncRef::null()))?;
+
+    store.drop_instance(provider_instance.id())?;
+    assert!(one.call(&mut store, ()).is_err());
+    assert!(provider_instance.exported_memory(&mut store, "memory")?.load(0, 1).is_err());
+    assert!(store.find_export("one").is_empty());
+
+    // addresses of the remaining instances are unaffected
+    assert!(holder.exported_global(&store, "f")?.as_ref_func().is_some_and(|f| f.is_null()));
+    let provider_instance = provider.instantiate(&mut store, None)?;
+    assert_eq!(provider_instance.exported_func::<(), i32>(&store, "one")?.call(&mut store, ())?, 1);
+    Ok(())
+}
+
+#[test]
+fn test_store_reset() -> Result<()> {
+    let module = Module::parse_bytes(&wat::parse_str(
+        r#"
+        (module
+            (memory (export "memory") 1)
+            (global $count (mut i32) (i32.const 0))
+            (func (export "next") (result i32)
+                (global.set $count (i32.add (global.get $count) (i32.const 1)))
+                (global.get $count))
+        )
+        "#,
+    )?)?;
+
+    let mut store = Store::default();
+    store.set_max_memory_pages(4).set_max_call_depth(100);
+    let id = store.id();
+    let mut old = Vec::new();
+    for _ in 0..2 {
+        let instance = module.clone().instantiate(&mut store, None)?;
+        instance.exported_func::<(), i32>(&store, "next")?.call(&mut store, ())?;
+        old.push(instance);
+    }
+    assert_eq!(store.total_memory_bytes(), 2 * 0x10000);
+    let old_handles = old.iter().map(|i| i.exported_func_untyped(&store, "next")).collect::<Result<Vec<_>, _>>()?;
+
+    store.reset();
+    assert_eq!(store.id(), id);
+    assert_eq!((store.max_memory_pages(), store.max_call_depth()), (Some(4), 100));
+    assert_eq!(store.total_memory_bytes(), 0);
+    assert!(store.get_module_instance(0).is_none());
+
+    // instantiating again starts from scratch
+    let instance = module.instantiate(&mut store, None)?;
+    assert_eq!(instance.id(), 0);
+    assert_eq!(instance.exported_func::<(), i32>(&store, "next")?.call(&mut store, ())?, 1);
+    assert_eq!(store.total_memory_bytes(), 0x10000);
+
+    // instances and handles from before the reset don't reach the new instance, even if their addresses are valid again
+    for handle in &old_handles {
+        assert!(matches!(handle.call(&mut store, &[]), Err(Error::InvalidStore)));
+        assert!(matches!(store.call(handle, &[]), Err(Error::InvalidStore)));
+    }
+    for instance in &old {
+        assert!(matches!(instance.exported_func_untyped(&store, "next"), Err(Error::InvalidStore)));
+        assert!(matches!(instance.exported_memory(&mut store, "memory"), Err(Error::InvalidStore)));
+    }
+    assert_eq!(instance.exported_func::<(), i32>(&store, "next")?.call(&mut store, ())?, 2);
+    Ok(())
+}
+
+#[test]
+fn test_symbol_map() -> Result<()> {
+    let provider = Module::parse_bytes(&wat::parse_str(r#"(module (func (export "shared")))"#)?)?;
+    let module = Module::parse_bytes(&wat::parse_str(
+        r#"
+        (module
+            (import "provider" "shared" (func $imported))
+            (func $helper)
+            (func (export "run") (call $helper))
+            (func)
+        )
+        "#,
+    )?)?;
+
+    let mut store = Store::default();
+    let provider = provider.instantiate(&mut store, None)?;
+    let mut imports = Imports::new();
+    imports.link_module("provider", provider.id())?;
+    let instance = module.instantiate(&mut store, Some(imports))?;
+
+    let addr = |instance: &tinywasm::ModuleInstance, name| match instance.export_addr(name) {
+        Some(ExternVal::Func(addr)) => addr,
+        _ => panic!("expected a function export"),
+    };
+
+    let symbols = store.symbol_map();
+    let shared = addr(&provider, "shared");
+    let run = addr(&instance, "run");
+    assert_eq!(symbols, [(shared, "shared".to_string()), (run - 1, "helper".to_string()), (run, "run".to_string())]);
+    Ok(())
+}
+
+#[test]
+fn test_func_handle_names() -> Result<()> {
+    let module = Module::parse_bytes(&wat::parse_str(
+        r#"
+        (module
+            (func $init)
+            (func $helper (export "run"))
+            (func (export "anonymous"))
+            (start $init)
+        )
+        "#,
+    )?)?;
+
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, None)?;
+    assert_eq!(instance.exported_func_untyped(&store, "run")?.name.as_deref(), Some("helper"));
+    assert_eq!(instance.exported_func_untyped(&store, "anonymous")?.name.as_deref(), Some("anonymous"));
+    assert_eq!(instance.start_func(&store)?.and_then(|f| f.name).as_deref(), Some("init"));
+
+    // a malformed name section leaves the functions unnamed instead of failing to parse
+    let mut wasm = wat::parse_str(r#"(module (func (export "run")) (func) (start 1))"#)?;
+    wasm.extend_from_slice(&[0x00, 0x0a, 0x04, b'n', b'a', b'm', b'e', 0x01, 0x05, 0xff, 0xff, 0xff]);
+    let module = Module::parse_bytes(&wasm)?;
+    let instance = module.instantiate(&mut store, None)?;
+    assert_eq!(instance.exported_func_untyped(&store, "run")?.name.as_deref(), Some("run"));
+    assert_eq!(instance.start_func(&store)?.and_then(|f| f.name), None);
+    Ok(())
+}
+
+#[test]
+fn test_instances_share_function_bodies() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"(module (func (export "add") (param i32 i32) (result i32) (i32.add (local.get 0) (local.get 1))))"#,
+    )?;
+    let module = Module::parse_bytes(&wasm)?;
+    assert_eq!(std::sync::Arc::strong_count(&module.inner().funcs[0]), 1);
+
+    let mut store = Store::default();
+    let instances = (0..3).map(|_| module.clone().instantiate(&mut store, None)).collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(std::sync::Arc::strong_count(&module.inner().funcs[0]), 4);
+
+    for instance in &instances {
+        assert_eq!(instance.exported_func::<(i32, i32), i32>(&store, "add")?.call(&mut store, (1, 2))?, 3);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_module_is_send() -> Result<()> {
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+    assert_send::<Module>();
+    assert_sync::<Module>();
+
+    // parse on one thread, instantiate on another
+    let wasm = wat::parse_str(r#"(module (func (export "one") (result i32) (i32.const 1)))"#)?;
+    let module = std::thread::spawn(move || Module::parse_bytes(&wasm)).join().unwrap()?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, None)?;
+    assert_eq!(instance.exported_func::<(), i32>(&store, "one")?.call(&mut store, ())?, 1);
+    Ok(())
+}
+
+#[test]
+fn test_store_call_handle() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"(module (func (export "inc") (param i32) (result i32) (i32.add (local.get 0) (i32.const 1))))"#,
+    )?;
+    let mut store = Store::default();
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, None)?;
+
+    let [(_, ExternVal::Func(addr))] = store.find_export("inc")[..] else { panic!("export not found") };
+    let handle = store.func_handle(addr)?;
+    assert_eq!(handle.addr(), addr);
+    assert_eq!(handle.name.as_deref(), Some("inc"));
+    assert_eq!(handle.ty(), instance.exported_func_untyped(&store, "inc")?.ty());
+
+    let mut value = WasmValue::I32(0);
+    for _ in 0..10 {
+        value = store.call(&handle, &[value])?[0];
+    }
+    assert_eq!(value, WasmValue::I32(10));
+    assert_eq!(handle.clone().call(&mut store, &[value])?, [WasmValue::I32(11)]);
+
+    assert!(store.func_handle(addr + 1).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_call_context() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"(module
+            (func (export "add") (param i32 i32) (result i32) (i32.add (local.get 0) (local.get 1)))
+            (func (export "div") (param i32 i32) (result i32) (i32.div_s (local.get 0) (local.get 1))))"#,
+    )?;
+    let mut store = Store::default();
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, None)?;
+    let add = instance.exported_func::<(i32, i32), i32>(&store, "add")?;
+    let div = instance.exported_func_untyped(&store, "div")?;
+
+    let mut ctx = store.new_call_context();
+    let mut sum = 0;
+    for i in 0..100 {
+        sum = ctx.call_typed(&mut store, &add, (sum, i))?;
+    }
+    assert_eq!(sum, add.call(&mut store, (4900, 50))?);
+
+    // a trap leaves frames and values behind, the next call has to start from scratch
+    assert!(ctx.call(&mut store, &div, &[WasmValue::I32(1), WasmValue::I32(0)]).is_err());
+    assert_eq!(ctx.call(&mut store, &div, &[WasmValue::I32(9), WasmValue::I32(3)])?, [WasmValue::I32(3)]);
+    assert!(ctx.call(&mut store, &div, &[WasmValue::I32(1)]).is_err());
+    assert_eq!(ctx.call_typed(&mut store, &add, (1, 2))?, 3);
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "archive")]
+fn test_twasm_round_trip() -> Result<()> {
+    use tinywasm::types::TinyWasmModule;
+
+    let wasm = wat::parse_str(
+        r#"(module
+            (global $base i32 (i32.add (i32.const 8) (i32.const 8)))
+            (global $count (export "count") (mut i32) (i32.const 0))
+            (memory (export "memory") 1)
+            (data (i32.mul (i32.const 4) (i32.const 4)) "\01\02\03\04")
+            (table 2 funcref)
+            (elem (i32.const 0) $load $splat)
+            (type $ret (func (result i32)))
+            (func $load (result i32) (i32.load (global.get $base)))
+            (func $splat (result i32) (i32x4.extract_lane 3 (i32x4.splat (i32.const 7))))
+            (func $start (global.set $count (i32.const 1)))
+            (func (export "run") (param i32) (result i32)
+                (call_indirect (type $ret) (local.get 0)))
+            (start $start))"#,
+    )?;
+    let parsed = Module::parse_bytes(&wasm)?;
+
+    let twasm = parsed.inner().serialize_twasm();
+    let loaded = TinyWasmModule::from_twasm(&twasm)?;
+    assert_eq!(&loaded, parsed.inner());
+
+    let mut store = Store::default();
+    let fresh = parsed.instantiate(&mut store, None)?;
+    let cached = Module::from(loaded).instantiate(&mut store, None)?;
+    for instance in [&fresh, &cached] {
+        let run = instance.exported_func::<i32, i32>(&store, "run")?;
+        assert_eq!(run.call(&mut store, 0)?, 0x04030201);
+        assert_eq!(run.call(&mut store, 1)?, 7);
+        assert!(run.call(&mut store, 2).is_err());
+        assert_eq!(instance.exported_global(&store, "count")?, WasmValue::I32(1));
+    }
+    Ok(())
+}
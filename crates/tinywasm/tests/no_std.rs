@@ -0,0 +1,37 @@
+//! Runs the parse, instantiate and call path with the `std` feature disabled.
+//!
+//! Run with `cargo test -p tinywasm --no-default-features --features parser --test no_std`.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use tinywasm::types::WasmValue;
+use tinywasm::{Imports, Module, Store};
+
+// (module
+//   (func (export "add") (param i32 i32) (result i32)
+//     (i32.add (local.get 0) (local.get 1))))
+const ADD_WASM: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic + version
+    0x01, 0x07, 0x01, 0x60, 0x02, 0x7f, 0x7f, 0x01, 0x7f, // type section
+    0x03, 0x02, 0x01, 0x00, // function section
+    0x07, 0x07, 0x01, 0x03, b'a', b'd', b'd', 0x00, 0x00, // export section
+    0x0a, 0x09, 0x01, 0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x6a, 0x0b, // code section
+];
+
+#[test]
+fn test_no_std_parse_instantiate_call() -> tinywasm::Result<()> {
+    let module = Module::parse_bytes(ADD_WASM)?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, Some(Imports::new()))?;
+
+    let add = instance.exported_func::<(i32, i32), i32>(&store, "add")?;
+    assert_eq!(add.call(&mut store, (2, 40))?, 42);
+
+    let add = instance.exported_func_untyped(&store, "add")?;
+    let res: Vec<WasmValue> = add.call(&mut store, &[WasmValue::I32(-1), WasmValue::I32(1)])?;
+    assert_eq!(res, [WasmValue::I32(0)]);
+    Ok(())
+}
@@ -0,0 +1,297 @@
+use eyre::Result;
+use tinywasm::{Module, ModuleInstance, Store};
+
+fn instantiate(wat: &str) -> Result<(Store, ModuleInstance)> {
+    let module = Module::parse_bytes(&wat::parse_str(wat)?)?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, None)?;
+    Ok((store, instance))
+}
+
+#[test]
+fn test_sign_extension() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (func (export "i32.extend8_s") (param i32) (result i32) (i32.extend8_s (local.get 0)))
+            (func (export "i32.extend16_s") (param i32) (result i32) (i32.extend16_s (local.get 0)))
+            (func (export "i64.extend8_s") (param i64) (result i64) (i64.extend8_s (local.get 0)))
+            (func (export "i64.extend16_s") (param i64) (result i64) (i64.extend16_s (local.get 0)))
+            (func (export "i64.extend32_s") (param i64) (result i64) (i64.extend32_s (local.get 0)))
+        )
+        "#,
+    )?;
+
+    let i32_ops = [("i32.extend8_s", 0x7f, 0x7f), ("i32.extend8_s", 0x1280, -128), ("i32.extend16_s", 0x18000, -32768)];
+    for (name, input, expected) in i32_ops {
+        assert_eq!(instance.exported_func::<i32, i32>(&store, name)?.call(&mut store, input)?, expected, "{name}");
+    }
+
+    let i64_ops = [
+        ("i64.extend8_s", 0xff, -1),
+        ("i64.extend16_s", 0x7fff, 0x7fff),
+        ("i64.extend32_s", 0x1_8000_0000, -0x8000_0000),
+        ("i64.extend32_s", 0x7fff_ffff, 0x7fff_ffff),
+    ];
+    for (name, input, expected) in i64_ops {
+        assert_eq!(instance.exported_func::<i64, i64>(&store, name)?.call(&mut store, input)?, expected, "{name}");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_trunc_sat() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (func (export "i32.trunc_sat_f32_s") (param f32) (result i32) (i32.trunc_sat_f32_s (local.get 0)))
+            (func (export "i32.trunc_sat_f64_u") (param f64) (result i32) (i32.trunc_sat_f64_u (local.get 0)))
+            (func (export "i64.trunc_sat_f64_s") (param f64) (result i64) (i64.trunc_sat_f64_s (local.get 0)))
+            (func (export "i64.trunc_sat_f32_u") (param f32) (result i64) (i64.trunc_sat_f32_u (local.get 0)))
+        )
+        "#,
+    )?;
+
+    let f = instance.exported_func::<f32, i32>(&store, "i32.trunc_sat_f32_s")?;
+    for (input, expected) in [(f32::NAN, 0), (-1.9, -1), (3e9, i32::MAX), (-3e9, i32::MIN), (f32::INFINITY, i32::MAX)] {
+        assert_eq!(f.call(&mut store, input)?, expected, "{input}");
+    }
+
+    let f = instance.exported_func::<f64, i32>(&store, "i32.trunc_sat_f64_u")?;
+    for (input, expected) in [(f64::NAN, 0), (-0.9, 0), (-5.0, 0), (4294967295.9, -1), (1e10, -1), (2.5, 2)] {
+        assert_eq!(f.call(&mut store, input)?, expected, "{input}");
+    }
+
+    let f = instance.exported_func::<f64, i64>(&store, "i64.trunc_sat_f64_s")?;
+    for (input, expected) in [(-f64::NAN, 0), (1e19, i64::MAX), (-1e19, i64::MIN), (-7.5, -7)] {
+        assert_eq!(f.call(&mut store, input)?, expected, "{input}");
+    }
+
+    let f = instance.exported_func::<f32, i64>(&store, "i64.trunc_sat_f32_u")?;
+    for (input, expected) in [(f32::NAN, 0), (f32::NEG_INFINITY, 0), (1e20, -1), (16777216.0, 16777216)] {
+        assert_eq!(f.call(&mut store, input)?, expected, "{input}");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_shift_rotate_amounts() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (func (export "i32.shl") (param i32 i32) (result i32) (i32.shl (local.get 0) (local.get 1)))
+            (func (export "i32.shr_s") (param i32 i32) (result i32) (i32.shr_s (local.get 0) (local.get 1)))
+            (func (export "i32.shr_u") (param i32 i32) (result i32) (i32.shr_u (local.get 0) (local.get 1)))
+            (func (export "i32.rotl") (param i32 i32) (result i32) (i32.rotl (local.get 0) (local.get 1)))
+            (func (export "i32.rotr") (param i32 i32) (result i32) (i32.rotr (local.get 0) (local.get 1)))
+            (func (export "i64.shl") (param i64 i64) (result i64) (i64.shl (local.get 0) (local.get 1)))
+            (func (export "i64.shr_s") (param i64 i64) (result i64) (i64.shr_s (local.get 0) (local.get 1)))
+            (func (export "i64.shr_u") (param i64 i64) (result i64) (i64.shr_u (local.get 0) (local.get 1)))
+            (func (export "i64.rotl") (param i64 i64) (result i64) (i64.rotl (local.get 0) (local.get 1)))
+            (func (export "i64.rotr") (param i64 i64) (result i64) (i64.rotr (local.get 0) (local.get 1)))
+        )
+        "#,
+    )?;
+
+    // the amount is taken modulo the bit width, so shifting by the width is the identity
+    let x = 0x8000_0001u32 as i32;
+    let (half, high) = (0xc000_0000u32 as i32, 0x4000_0000);
+    let i32_ops = [
+        ("i32.shl", [(0, x), (1, 2), (32, x), (33, 2), (-1, i32::MIN)]),
+        ("i32.shr_s", [(0, x), (1, half), (32, x), (33, half), (-1, -1)]),
+        ("i32.shr_u", [(0, x), (1, high), (32, x), (33, high), (-1, 1)]),
+        ("i32.rotl", [(0, x), (1, 3), (32, x), (33, 3), (-1, half)]),
+        ("i32.rotr", [(0, x), (1, half), (32, x), (33, half), (-1, 3)]),
+    ];
+    for (name, cases) in i32_ops {
+        let f = instance.exported_func::<(i32, i32), i32>(&store, name)?;
+        for (amount, expected) in cases {
+            assert_eq!(f.call(&mut store, (x, amount))?, expected, "{name} by {amount}");
+        }
+    }
+
+    // only the low 6 bits of the amount count, even if higher bits are set
+    let x = 0x8000_0000_0000_0001u64 as i64;
+    let (half, high) = (0xc000_0000_0000_0000u64 as i64, 0x4000_0000_0000_0000);
+    let i64_ops = [
+        ("i64.shl", [(0, x), (1, 2), (64, x), (65, 2), (0x1_0000_0001, 2), (-1, i64::MIN)]),
+        ("i64.shr_s", [(0, x), (1, half), (64, x), (65, half), (0x1_0000_0001, half), (-1, -1)]),
+        ("i64.shr_u", [(0, x), (1, high), (64, x), (65, high), (0x1_0000_0001, high), (-1, 1)]),
+        ("i64.rotl", [(0, x), (1, 3), (64, x), (65, 3), (0x1_0000_0001, 3), (-1, half)]),
+        ("i64.rotr", [(0, x), (1, half), (64, x), (65, half), (0x1_0000_0001, half), (-1, 3)]),
+    ];
+    for (name, cases) in i64_ops {
+        let f = instance.exported_func::<(i64, i64), i64>(&store, name)?;
+        for (amount, expected) in cases {
+            assert_eq!(f.call(&mut store, (x, amount))?, expected, "{name} by {amount}");
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_count_bits() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (func (export "i32.clz") (param i32) (result i32) (i32.clz (local.get 0)))
+            (func (export "i32.ctz") (param i32) (result i32) (i32.ctz (local.get 0)))
+            (func (export "i32.popcnt") (param i32) (result i32) (i32.popcnt (local.get 0)))
+            (func (export "i64.clz") (param i64) (result i64) (i64.clz (local.get 0)))
+            (func (export "i64.ctz") (param i64) (result i64) (i64.ctz (local.get 0)))
+            (func (export "i64.popcnt") (param i64) (result i64) (i64.popcnt (local.get 0)))
+        )
+        "#,
+    )?;
+
+    // counting the zeros of 0 yields the bit width
+    let i32_ops = [("i32.clz", [32, 0, 31, 15]), ("i32.ctz", [32, 0, 0, 16]), ("i32.popcnt", [0, 32, 1, 1])];
+    for (name, expected) in i32_ops {
+        let f = instance.exported_func::<i32, i32>(&store, name)?;
+        for (input, expected) in [0, -1, 1, 0x1_0000].into_iter().zip(expected) {
+            assert_eq!(f.call(&mut store, input)?, expected, "{name} of {input:#x}");
+        }
+    }
+
+    let i64_ops = [("i64.clz", [64, 0, 63, 31]), ("i64.ctz", [64, 0, 0, 32]), ("i64.popcnt", [0, 64, 1, 1])];
+    for (name, expected) in i64_ops {
+        let f = instance.exported_func::<i64, i64>(&store, name)?;
+        for (input, expected) in [0, -1, 1, 0x1_0000_0000].into_iter().zip(expected) {
+            assert_eq!(f.call(&mut store, input)?, expected, "{name} of {input:#x}");
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_select_operand_order() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (func (export "i32") (param i32) (result i32) (select (i32.const 10) (i32.const 20) (local.get 0)))
+            (func (export "i64") (param i32) (result i64) (select (i64.const 10) (i64.const 20) (local.get 0)))
+            (func (export "f32") (param i32) (result f32) (select (f32.const 1.5) (f32.const 2.5) (local.get 0)))
+            (func (export "f64") (param i32) (result f64)
+                (select (result f64) (f64.const 1.5) (f64.const 2.5) (local.get 0)))
+            (func (export "v128") (param i32) (result i32)
+                (i32x4.extract_lane 0 (select (v128.const i32x4 10 0 0 0) (v128.const i32x4 20 0 0 0) (local.get 0))))
+            ;; the first operand stays below the others on the stack
+            (func (export "below") (param i32) (result i32)
+                (i32.sub (i32.const 100) (select (i32.const 10) (i32.const 20) (local.get 0))))
+        )
+        "#,
+    )?;
+
+    for (cond, expected) in [(1, 10), (0, 20), (-1, 10), (i32::MIN, 10)] {
+        assert_eq!(instance.exported_func::<i32, i32>(&store, "i32")?.call(&mut store, cond)?, expected, "{cond}");
+        assert_eq!(instance.exported_func::<i32, i64>(&store, "i64")?.call(&mut store, cond)?, expected as i64);
+        assert_eq!(instance.exported_func::<i32, i32>(&store, "v128")?.call(&mut store, cond)?, expected);
+        assert_eq!(instance.exported_func::<i32, i32>(&store, "below")?.call(&mut store, cond)?, 100 - expected);
+    }
+    assert_eq!(instance.exported_func::<i32, f32>(&store, "f32")?.call(&mut store, 1)?, 1.5);
+    assert_eq!(instance.exported_func::<i32, f32>(&store, "f32")?.call(&mut store, 0)?, 2.5);
+    assert_eq!(instance.exported_func::<i32, f64>(&store, "f64")?.call(&mut store, 1)?, 1.5);
+    assert_eq!(instance.exported_func::<i32, f64>(&store, "f64")?.call(&mut store, 0)?, 2.5);
+    Ok(())
+}
+
+#[test]
+fn test_canonicalize_nans() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (func (export "f32_add_payload") (result i32)
+                (i32.reinterpret_f32 (f32.add (f32.reinterpret_i32 (i32.const 0xffa00001)) (f32.const 1))))
+            (func (export "f32_div") (result i32)
+                (i32.reinterpret_f32 (f32.div (f32.const 0) (f32.const 0))))
+            (func (export "f64_sqrt") (result i64)
+                (i64.reinterpret_f64 (f64.sqrt (f64.const -1))))
+            (func (export "f32_demote") (result i32)
+                (i32.reinterpret_f32 (f32.demote_f64 (f64.reinterpret_i64 (i64.const 0xfff0000000000001)))))
+            (func (export "f32_neg") (result i32)
+                (i32.reinterpret_f32 (f32.neg (f32.reinterpret_i32 (i32.const 0x7fc00000)))))
+            (func (export "f32_add") (result i32)
+                (i32.reinterpret_f32 (f32.add (f32.const 1) (f32.const 2))))
+            (func (export "f32x4_mul") (result v128)
+                (f32x4.mul (v128.const f32x4 0 1 inf 2) (v128.const f32x4 inf 2 0 -nan:0x1)))
+        )
+        "#,
+    )?;
+
+    let call32 = |store: &mut Store, name| instance.exported_func::<(), i32>(store, name)?.call(store, ());
+    let call64 = |store: &mut Store, name| instance.exported_func::<(), i64>(store, name)?.call(store, ());
+    let simd = |store: &mut Store, name| -> Result<[i32; 4]> {
+        let res = instance.exported_func_untyped(store, name)?.call(store, &[])?;
+        Ok(res[0].as_i32x4().unwrap())
+    };
+
+    store.set_canonicalize_nans(true);
+    assert!(store.canonicalize_nans());
+    assert_eq!(call32(&mut store, "f32_add_payload")?, 0x7fc00000);
+    assert_eq!(call32(&mut store, "f32_div")?, 0x7fc00000);
+    assert_eq!(call64(&mut store, "f64_sqrt")?, 0x7ff8000000000000);
+    assert_eq!(call32(&mut store, "f32_demote")?, 0x7fc00000);
+    assert_eq!(simd(&mut store, "f32x4_mul")?, [0x7fc00000, 2.0f32.to_bits() as i32, 0x7fc00000, 0x7fc00000]);
+
+    // non-arithmetic ops only touch the sign bit and are left alone
+    assert_eq!(call32(&mut store, "f32_neg")?, 0xffc00000u32 as i32);
+    assert_eq!(call32(&mut store, "f32_add")?, 3.0f32.to_bits() as i32);
+
+    // without canonicalization, the payload of a NaN operand is propagated
+    store.set_canonicalize_nans(false);
+    assert_ne!(call32(&mut store, "f32_add_payload")?, 0x7fc00000);
+    Ok(())
+}
+
+#[test]
+fn test_float_min_max() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (func (export "f32_min") (param i32 i32) (result i32)
+                (i32.reinterpret_f32 (f32.min (f32.reinterpret_i32 (local.get 0)) (f32.reinterpret_i32 (local.get 1)))))
+            (func (export "f32_max") (param i32 i32) (result i32)
+                (i32.reinterpret_f32 (f32.max (f32.reinterpret_i32 (local.get 0)) (f32.reinterpret_i32 (local.get 1)))))
+            (func (export "f64_min") (param i64 i64) (result i64)
+                (i64.reinterpret_f64 (f64.min (f64.reinterpret_i64 (local.get 0)) (f64.reinterpret_i64 (local.get 1)))))
+            (func (export "f64_max") (param i64 i64) (result i64)
+                (i64.reinterpret_f64 (f64.max (f64.reinterpret_i64 (local.get 0)) (f64.reinterpret_i64 (local.get 1)))))
+        )
+        "#,
+    )?;
+
+    let f32_min = instance.exported_func::<(i32, i32), i32>(&store, "f32_min")?;
+    let f32_max = instance.exported_func::<(i32, i32), i32>(&store, "f32_max")?;
+    let f64_min = instance.exported_func::<(i64, i64), i64>(&store, "f64_min")?;
+    let f64_max = instance.exported_func::<(i64, i64), i64>(&store, "f64_max")?;
+    let f32 = |x: f32| x.to_bits() as i32;
+    let f64 = |x: f64| x.to_bits() as i64;
+
+    // unlike `f32::min`, the sign of zero matters regardless of the operand order
+    for (a, b) in [(-0.0, 0.0), (0.0, -0.0)] {
+        assert_eq!(f32_min.call(&mut store, (f32(a), f32(b)))?, f32(-0.0));
+        assert_eq!(f32_max.call(&mut store, (f32(a), f32(b)))?, f32(0.0));
+        assert_eq!(f64_min.call(&mut store, (f64(a as f64), f64(b as f64)))?, f64(-0.0));
+        assert_eq!(f64_max.call(&mut store, (f64(a as f64), f64(b as f64)))?, f64(0.0));
+    }
+    assert_eq!(f32_min.call(&mut store, (f32(-1.5), f32(f32::INFINITY)))?, f32(-1.5));
+    assert_eq!(f64_max.call(&mut store, (f64(f64::NEG_INFINITY), f64(2.5)))?, f64(2.5));
+
+    // unlike `f32::max`, a NaN operand is never ignored
+    for (a, b) in [(f32::NAN, 1.0), (1.0, f32::NAN), (f32::NAN, f32::INFINITY)] {
+        assert!(f32::from_bits(f32_min.call(&mut store, (f32(a), f32(b)))? as u32).is_nan());
+        assert!(f32::from_bits(f32_max.call(&mut store, (f32(a), f32(b)))? as u32).is_nan());
+        assert!(f64::from_bits(f64_min.call(&mut store, (f64(a as f64), f64(b as f64)))? as u64).is_nan());
+        assert!(f64::from_bits(f64_max.call(&mut store, (f64(a as f64), f64(b as f64)))? as u64).is_nan());
+    }
+
+    // a signaling NaN operand results in an arithmetic NaN, i.e. one with the quiet bit set
+    let (snan32, snan64) = (0x7fa0_0001, 0x7ff4_0000_0000_0001);
+    for res in [f32_min.call(&mut store, (snan32, f32(1.0)))?, f32_max.call(&mut store, (f32(1.0), snan32))?] {
+        assert_eq!(res & 0x7fc0_0000, 0x7fc0_0000, "{res:#x}");
+    }
+    for res in [f64_min.call(&mut store, (f64(1.0), snan64))?, f64_max.call(&mut store, (snan64, f64(1.0)))?] {
+        assert_eq!(res & 0x7ff8_0000_0000_0000, 0x7ff8_0000_0000_0000, "{res:#x}");
+    }
+    Ok(())
+}
@@ -0,0 +1,35 @@
+//! Run with `cargo test -p tinywasm --features serde --test serde`.
+
+use eyre::Result;
+use tinywasm::types::{ExternRef, FuncRef, ValType, WasmValue};
+
+#[test]
+fn test_wasm_value_json() -> Result<()> {
+    let values = [
+        (WasmValue::I32(-7), r#"{"I32":-7}"#),
+        (WasmValue::I64(1 << 40), r#"{"I64":1099511627776}"#),
+        (WasmValue::F32(1.5), r#"{"F32":1.5}"#),
+        (WasmValue::F64(-0.25), r#"{"F64":-0.25}"#),
+        (WasmValue::V128(0x0102), r#"{"V128":[2,1,0,0,0,0,0,0,0,0,0,0,0,0,0,0]}"#),
+        (WasmValue::RefFunc(FuncRef::new(Some(3))), r#"{"RefFunc":3}"#),
+        (WasmValue::RefFunc(FuncRef::null()), r#"{"RefFunc":null}"#),
+        (WasmValue::RefExtern(ExternRef::null()), r#"{"RefExtern":null}"#),
+    ];
+
+    for (value, json) in values {
+        assert_eq!(serde_json::to_string(&value)?, json);
+        assert_eq!(serde_json::from_str::<WasmValue>(json)?, value);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_val_type_json() -> Result<()> {
+    assert_eq!(
+        serde_json::to_string(&[ValType::I32, ValType::V128, ValType::RefExtern])?,
+        r#"["I32","V128","RefExtern"]"#
+    );
+    assert_eq!(serde_json::from_str::<ValType>(r#""F64""#)?, ValType::F64);
+    assert!(serde_json::from_str::<WasmValue>(r#"{"V128":[1,2,3]}"#).is_err());
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use eyre::Result;
+use tinywasm::types::WasmValue;
+use tinywasm::{Error, Module, ModuleInstance, Store, Trap};
+
+fn instantiate(wat: &str) -> Result<(Store, ModuleInstance)> {
+    let module = Module::parse_bytes(&wat::parse_str(wat)?)?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, None)?;
+    Ok((store, instance))
+}
+
+fn v128(store: &mut Store, instance: &ModuleInstance, name: &str) -> Result<u128> {
+    match instance.exported_func_untyped(store, name)?.call(store, &[])?[..] {
+        [WasmValue::V128(v)] => Ok(v),
+        ref res => eyre::bail!("unexpected result for {name}: {res:?}"),
+    }
+}
+
+fn i32x4(lanes: [i32; 4]) -> u128 {
+    u128::from_le_bytes(lanes.map(i32::to_le_bytes).concat().try_into().unwrap())
+}
+
+#[test]
+fn test_simd_lanes_and_arithmetic() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (func (export "add") (result v128)
+                (i32x4.add (v128.const i32x4 1 2 3 0x7fffffff) (v128.const i32x4 10 20 30 1)))
+            (func (export "sub") (result v128)
+                (i8x16.sub (i8x16.splat (i32.const 1)) (i8x16.splat (i32.const 2))))
+            (func (export "mul") (result v128)
+                (i16x8.mul (i16x8.splat (i32.const 300)) (i16x8.splat (i32.const 300))))
+            (func (export "shl") (result v128) (i32x4.shl (v128.const i32x4 1 2 3 4) (i32.const 33)))
+            (func (export "shr_s") (result v128) (i32x4.shr_s (v128.const i32x4 -8 8 -1 0) (i32.const 2)))
+            (func (export "shr_u") (result v128) (i8x16.shr_u (i8x16.splat (i32.const 0x80)) (i32.const 7)))
+            (func (export "eq") (result v128) (i32x4.eq (v128.const i32x4 1 2 3 4) (v128.const i32x4 1 0 3 0)))
+            (func (export "lt_u") (result v128) (i32x4.lt_u (v128.const i32x4 -1 0 1 2) (i32x4.splat (i32.const 1))))
+            (func (export "replace") (result v128) (i32x4.replace_lane 2 (i32x4.splat (i32.const 7)) (i32.const -5)))
+            (func (export "shuffle") (result v128)
+                (i8x16.shuffle 16 0 17 1 18 2 19 3 20 4 21 5 22 6 23 7
+                    (v128.const i8x16 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15)
+                    (i8x16.splat (i32.const -1))))
+            (func (export "extract_s") (result i32) (i8x16.extract_lane_s 15 (i8x16.splat (i32.const 0xff))))
+            (func (export "extract_u") (result i32) (i16x8.extract_lane_u 3 (i16x8.splat (i32.const -1))))
+            (func (export "extract_f64") (result f64) (f64x2.extract_lane 1 (f64x2.splat (f64.const 2.5))))
+            (func (export "all_true") (result i32) (i32x4.all_true (v128.const i32x4 1 1 0 1)))
+        )
+        "#,
+    )?;
+
+    assert_eq!(v128(&mut store, &instance, "add")?, i32x4([11, 22, 33, i32::MIN]));
+    assert_eq!(v128(&mut store, &instance, "sub")?, u128::MAX);
+    assert_eq!(v128(&mut store, &instance, "mul")?, u128::from_le_bytes([0x90, 0x5f].repeat(8).try_into().unwrap()));
+    assert_eq!(v128(&mut store, &instance, "shl")?, i32x4([2, 4, 6, 8]));
+    assert_eq!(v128(&mut store, &instance, "shr_s")?, i32x4([-2, 2, -1, 0]));
+    assert_eq!(v128(&mut store, &instance, "shr_u")?, u128::from_le_bytes([1; 16]));
+    assert_eq!(v128(&mut store, &instance, "eq")?, i32x4([-1, 0, -1, 0]));
+    assert_eq!(v128(&mut store, &instance, "lt_u")?, i32x4([0, -1, 0, 0]));
+    assert_eq!(v128(&mut store, &instance, "replace")?, i32x4([7, 7, -5, 7]));
+    assert_eq!(
+        v128(&mut store, &instance, "shuffle")?,
+        u128::from_le_bytes([0xff, 0, 0xff, 1, 0xff, 2, 0xff, 3, 0xff, 4, 0xff, 5, 0xff, 6, 0xff, 7])
+    );
+
+    assert_eq!(instance.exported_func::<(), i32>(&store, "extract_s")?.call(&mut store, ())?, -1);
+    assert_eq!(instance.exported_func::<(), i32>(&store, "extract_u")?.call(&mut store, ())?, 0xffff);
+    assert_eq!(instance.exported_func::<(), f64>(&store, "extract_f64")?.call(&mut store, ())?, 2.5);
+    assert_eq!(instance.exported_func::<(), i32>(&store, "all_true")?.call(&mut store, ())?, 0);
+    Ok(())
+}
+
+#[test]
+fn test_simd_unsupported_instruction() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (func (export "popcnt") (result v128) (i8x16.popcnt (v128.const i64x2 0 0)))
+        )
+        "#,
+    )?;
+
+    let res = instance.exported_func_untyped(&store, "popcnt")?.call(&mut store, &[]);
+    assert!(matches!(res, Err(Error::UnsupportedFeature(msg)) if msg.contains("I8x16Popcnt")));
+    Ok(())
+}
+
+#[test]
+fn test_simd_memory() -> Result<()> {
+    let (mut store, instance) = instantiate(
+        r#"
+        (module
+            (memory (export "memory") 1)
+            (data (i32.const 0) "\01\02\03\04\05\06\07\08\09\0a\0b\0c\0d\0e\0f\10\80\ff")
+            (func (export "load") (param i32) (result v128) (v128.load offset=1 (local.get 0)))
+            (func (export "load8x8_s") (result v128) (v128.load8x8_s offset=10 (i32.const 0)))
+            (func (export "load16x4_u") (result v128) (v128.load16x4_u (i32.const 14)))
+            (func (export "load16_splat") (result v128) (v128.load16_splat (i32.const 16)))
+            (func (export "load32_zero") (result v128) (v128.load32_zero (i32.const 0)))
+            (func (export "load8_lane") (result v128) (v128.load8_lane 3 (i32.const 17) (v128.const i64x2 0 0)))
+            (func (export "store") (param i32)
+                (v128.store (local.get 0) (v128.const i32x4 -1 -1 -1 -1)))
+            (func (export "store32_lane") (param i32)
+                (v128.store32_lane 2 (local.get 0) (v128.const i32x4 1 2 0x11223344 4)))
+        )
+        "#,
+    )?;
+
+    let v = |store: &mut Store, name: &str| v128(store, &instance, name);
+    let bytes = |v: u128| v.to_le_bytes();
+
+    let load = instance.exported_func_untyped(&store, "load")?;
+    assert!(
+        matches!(load.call(&mut store, &[WasmValue::I32(0)])?[..], [WasmValue::V128(v)] if bytes(v) == *b"\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b\x0c\x0d\x0e\x0f\x10\x80")
+    );
+    assert_eq!(bytes(v(&mut store, "load8x8_s")?), [11, 0, 12, 0, 13, 0, 14, 0, 15, 0, 16, 0, 0x80, 0xff, 0xff, 0xff]);
+    assert_eq!(bytes(v(&mut store, "load16x4_u")?), [15, 16, 0, 0, 0x80, 0xff, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(bytes(v(&mut store, "load16_splat")?), [0x80, 0xff].repeat(8)[..]);
+    assert_eq!(v(&mut store, "load32_zero")?, 0x04030201);
+    assert_eq!(v(&mut store, "load8_lane")?, 0xff << 24);
+
+    // the address operand is unsigned, so the effective address can't be pulled back into bounds
+    let oob = |res: tinywasm::Result<Vec<WasmValue>>| matches!(res, Err(Error::Trap(Trap::MemoryOutOfBounds { .. })));
+    assert!(oob(load.call(&mut store, &[WasmValue::I32(65520)])));
+    assert!(oob(load.call(&mut store, &[WasmValue::I32(-1)])));
+
+    let store_v128 = instance.exported_func::<i32, ()>(&store, "store")?;
+    let store_lane = instance.exported_func::<i32, ()>(&store, "store32_lane")?;
+    store_v128.call(&mut store, 65520)?;
+    assert!(matches!(store_v128.call(&mut store, 65521), Err(Error::Trap(Trap::MemoryOutOfBounds { .. }))));
+    store_lane.call(&mut store, 65532)?;
+    assert!(matches!(store_lane.call(&mut store, 65533), Err(Error::Trap(Trap::MemoryOutOfBounds { .. }))));
+
+    let memory = instance.exported_memory(&mut store, "memory")?;
+    assert_eq!(memory.load(65516, 20)?, [[0; 4], [0xff; 4], [0xff; 4], [0xff; 4], [0x44, 0x33, 0x22, 0x11]].concat());
+    Ok(())
+}
+
+#[test]
+fn test_v128_lane_constructors() -> Result<()> {
+    let v = WasmValue::v128_from_i32x4([1, -2, 3, i32::MAX]);
+    assert_eq!(v.as_v128(), Some(i32x4([1, -2, 3, i32::MAX])));
+    assert_eq!(v.as_i32x4(), Some([1, -2, 3, i32::MAX]));
+    assert_eq!(format!("{v:?}"), "v128(i32x4[1, -2, 3, 2147483647])");
+
+    assert_eq!(WasmValue::v128_from_i8x16([-1; 16]).as_v128(), Some(u128::MAX));
+    assert_eq!(WasmValue::v128_from_i64x2([1, 2]).as_v128(), Some(1 | 2 << 64));
+    assert_eq!(WasmValue::v128_from_i16x8([1, 0, 0, 0, 0, 0, 0, -1]).as_i16x8(), Some([1, 0, 0, 0, 0, 0, 0, -1]));
+    assert_eq!(WasmValue::v128_from_f32x4([1.0, -0.5, 0.0, 2.5]).as_f32x4(), Some([1.0, -0.5, 0.0, 2.5]));
+    assert_eq!(
+        WasmValue::v128_from_f64x2([1.5, -2.0]).as_i64x2(),
+        Some([1.5f64.to_bits() as i64, (-2.0f64).to_bits() as i64])
+    );
+    assert_eq!(WasmValue::I32(1).as_i32x4(), None);
+
+    // lanes match the layout used by the interpreter
+    let (mut store, instance) =
+        instantiate(r#"(module (func (export "f") (result v128) (v128.const f32x4 1.0 -0.5 0.0 2.5)))"#)?;
+    let res = v128(&mut store, &instance, "f")?;
+    assert_eq!(WasmValue::V128(res).as_f32x4(), Some([1.0, -0.5, 0.0, 2.5]));
+    Ok(())
+}
+
+#[test]
+fn test_v128_eq_loose() {
+    let v = WasmValue::v128_from_i32x4([1, 2, 3, 4]);
+    assert!(v.eq_loose(&WasmValue::v128_from_i32x4([1, 2, 3, 4])));
+    assert!(!v.eq_loose(&WasmValue::v128_from_i32x4([1, 2, 3, 5])));
+    assert!(!v.eq_loose(&WasmValue::I32(1)));
+
+    // NaN lanes only match loosely when compared as float lanes
+    let nan = WasmValue::v128_from_f32x4([1.0, f32::NAN, 0.0, 2.0]);
+    let other_nan = WasmValue::v128_from_f32x4([1.0, f32::from_bits(0x7fc0_0001), 0.0, 2.0]);
+    assert!(!nan.eq_loose(&other_nan));
+    assert!(nan.eq_loose_f32x4(&other_nan));
+    assert!(!nan.eq_loose_f32x4(&WasmValue::v128_from_f32x4([1.0, f32::NAN, -0.0, 2.0])));
+
+    let nan = WasmValue::v128_from_f64x2([f64::NAN, 1.0]);
+    assert!(nan.eq_loose_f64x2(&WasmValue::v128_from_f64x2([-f64::NAN, 1.0])));
+    assert!(!nan.eq_loose_f64x2(&WasmValue::v128_from_f64x2([f64::NAN, 2.0])));
+    assert!(!WasmValue::F64(f64::NAN).eq_loose_f64x2(&WasmValue::F64(f64::NAN)));
+}
@@ -0,0 +1,330 @@
+use eyre::Result;
+use tinywasm::parser::Parser;
+use tinywasm::types::{
+    ConstInstruction, ElementItem, ElementKind, ExternRef, ExternalKind, FuncRef, FuncType, Instruction, TableType,
+    TinyWasmModuleBuilder, ValType, WasmValue,
+};
+use tinywasm::{Error, Module, Store, Trap};
+
+const ELEMENTS: &str = r#"
+(module
+    (table $t0 1 funcref)
+    (table $t1 2 funcref)
+    (func $a (result i32) i32.const 1)
+    (func $b (result i32) i32.const 2)
+    (elem (table $t1) (i32.const 1) func $b)
+    (elem declare func $a)
+    (elem $passive funcref (ref.func $a) (ref.null func))
+    (func (export "get_a") (result funcref) ref.func $a)
+    (func (export "call_t1") (param i32) (result i32)
+        local.get 0
+        call_indirect $t1 (result i32))
+)
+"#;
+
+#[test]
+fn test_parse_element_segments() -> Result<()> {
+    let module = Parser::new().parse_module_bytes(wat::parse_str(ELEMENTS)?)?;
+    assert_eq!(module.elements.len(), 3);
+
+    let active = &module.elements[0];
+    assert!(
+        matches!(&active.kind, ElementKind::Active { table: 1, offset } if offset[..] == [ConstInstruction::I32Const(1)])
+    );
+    assert!(matches!(active.items[..], [ElementItem::Func(1)]));
+
+    let declared = &module.elements[1];
+    assert!(matches!(declared.kind, ElementKind::Declared));
+    assert!(matches!(declared.items[..], [ElementItem::Func(0)]));
+
+    let passive = &module.elements[2];
+    assert!(matches!(passive.kind, ElementKind::Passive));
+    assert!(matches!(
+        passive.items[..],
+        [ElementItem::Expr(ConstInstruction::RefFunc(Some(0))), ElementItem::Expr(ConstInstruction::RefFunc(None))]
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_element_segments_instantiate() -> Result<()> {
+    let module = Module::parse_bytes(&wat::parse_str(ELEMENTS)?)?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, None)?;
+
+    let call_t1 = instance.exported_func::<i32, i32>(&store, "call_t1")?;
+    assert_eq!(call_t1.call(&mut store, 1)?, 2);
+    assert!(call_t1.call(&mut store, 0).is_err());
+
+    let get_a = instance.exported_func_untyped(&store, "get_a")?;
+    assert!(get_a.call(&mut store, &[])?[0].as_ref_func().is_some_and(|r| r.addr().is_some()));
+    Ok(())
+}
+
+#[test]
+fn test_reference_instructions() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (type $ret (func (result i32)))
+            (table $t 1 funcref)
+            (func $forty_two (result i32) i32.const 42)
+            (elem declare func $forty_two)
+            (func (export "is_null") (param i32) (result i32)
+                (select (result funcref) (ref.null func) (ref.func $forty_two) (local.get 0))
+                ref.is_null)
+            (func (export "call_ref") (result i32)
+                (table.set $t (i32.const 0) (ref.func $forty_two))
+                (call_indirect $t (type $ret) (i32.const 0)))
+            (func (export "extern_is_null") (param externref) (result i32)
+                (ref.is_null (local.get 0)))
+        )
+        "#,
+    )?;
+
+    // instantiate another module first so that module-local and store function addresses differ
+    let mut store = Store::default();
+    Module::parse_bytes(&wat::parse_str("(module (func) (func))")?)?.instantiate(&mut store, None)?;
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, None)?;
+
+    let is_null = instance.exported_func::<i32, i32>(&store, "is_null")?;
+    assert_eq!(is_null.call(&mut store, 1)?, 1);
+    assert_eq!(is_null.call(&mut store, 0)?, 0);
+
+    let call_ref = instance.exported_func::<(), i32>(&store, "call_ref")?;
+    assert_eq!(call_ref.call(&mut store, ())?, 42);
+
+    let extern_is_null = instance.exported_func::<ExternRef, i32>(&store, "extern_is_null")?;
+    assert_eq!(extern_is_null.call(&mut store, ExternRef::null())?, 1);
+    Ok(())
+}
+
+#[test]
+fn test_typed_select_refs() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (type $ret (func (result i32)))
+            (table $t 1 funcref)
+            (func $one (result i32) i32.const 1)
+            (func $two (result i32) i32.const 2)
+            (elem declare func $one $two)
+            (func (export "pick") (param i32) (result i32)
+                (table.set $t (i32.const 0) (select (result funcref) (ref.func $one) (ref.func $two) (local.get 0)))
+                (call_indirect $t (type $ret) (i32.const 0)))
+            (func (export "pick_extern") (param externref externref i32) (result externref)
+                (select (result externref) (local.get 0) (local.get 1) (local.get 2)))
+        )
+        "#,
+    )?;
+
+    let mut store = Store::default();
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, None)?;
+    let pick = instance.exported_func::<i32, i32>(&store, "pick")?;
+    assert_eq!(pick.call(&mut store, 1)?, 1);
+    assert_eq!(pick.call(&mut store, 0)?, 2);
+    assert_eq!(pick.call(&mut store, -1)?, 1);
+
+    let pick_extern = instance.exported_func::<(ExternRef, ExternRef, i32), ExternRef>(&store, "pick_extern")?;
+    let (a, b) = (ExternRef::new(Some(7)), ExternRef::null());
+    assert_eq!(pick_extern.call(&mut store, (a, b, 1))?, a);
+    assert_eq!(pick_extern.call(&mut store, (a, b, 0))?, b);
+
+    // references can only be selected with the typed form
+    let untyped = wat::parse_str(
+        r#"
+        (module
+            (func (param funcref funcref i32) (result funcref)
+                (select (local.get 0) (local.get 1) (local.get 2))))
+        "#,
+    )?;
+    assert!(matches!(Module::parse_bytes(&untyped), Err(Error::ParseError(_))));
+    Ok(())
+}
+
+#[test]
+fn test_table_instructions() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (table $t 2 4 funcref)
+            (table $ext 0 externref)
+            (func $one (result i32) i32.const 1)
+            (func $two (result i32) i32.const 2)
+            (elem $seg func $one $two)
+            (func (export "grow") (param i32) (result i32) (table.grow $t (ref.func $one) (local.get 0)))
+            (func (export "grow_ext") (param i32) (result i32) (table.grow $ext (ref.null extern) (local.get 0)))
+            (func (export "size") (result i32) (table.size $t))
+            (func (export "fill") (param i32 i32) (table.fill $t (local.get 0) (ref.func $two) (local.get 1)))
+            (func (export "copy") (param i32 i32 i32) (table.copy $t $t (local.get 0) (local.get 1) (local.get 2)))
+            (func (export "init") (param i32 i32 i32) (table.init $t $seg (local.get 0) (local.get 1) (local.get 2)))
+            (func (export "drop") (elem.drop $seg))
+            (func (export "call") (param i32) (result i32) (call_indirect $t (result i32) (local.get 0)))
+            (func (export "is_null") (param i32) (result i32) (ref.is_null (table.get $t (local.get 0))))
+        )
+        "#,
+    )?;
+
+    // offset the store addresses of this module's functions
+    let mut store = Store::default();
+    Module::parse_bytes(&wat::parse_str("(module (func))")?)?.instantiate(&mut store, None)?;
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, None)?;
+
+    let grow = instance.exported_func::<i32, i32>(&store, "grow")?;
+    let grow_ext = instance.exported_func::<i32, i32>(&store, "grow_ext")?;
+    let size = instance.exported_func::<(), i32>(&store, "size")?;
+    let fill = instance.exported_func::<(i32, i32), ()>(&store, "fill")?;
+    let copy = instance.exported_func::<(i32, i32, i32), ()>(&store, "copy")?;
+    let init = instance.exported_func::<(i32, i32, i32), ()>(&store, "init")?;
+    let drop = instance.exported_func::<(), ()>(&store, "drop")?;
+    let call = instance.exported_func::<i32, i32>(&store, "call")?;
+    let is_null = instance.exported_func::<i32, i32>(&store, "is_null")?;
+
+    // grow returns the previous size, or -1 past the declared maximum
+    assert_eq!(grow.call(&mut store, 1)?, 2);
+    assert_eq!(grow.call(&mut store, 2)?, -1);
+    assert_eq!(grow.call(&mut store, -1)?, -1);
+    assert_eq!(size.call(&mut store, ())?, 3);
+    assert_eq!(call.call(&mut store, 2)?, 1);
+    assert_eq!(grow_ext.call(&mut store, 5)?, 0);
+
+    let oob = |res: tinywasm::Result<()>| matches!(res, Err(Error::Trap(Trap::TableOutOfBounds { .. })));
+
+    assert_eq!(is_null.call(&mut store, 0)?, 1);
+    fill.call(&mut store, (0, 2))?;
+    assert_eq!(call.call(&mut store, 1)?, 2);
+    assert!(oob(fill.call(&mut store, (2, 2))));
+    assert!(oob(fill.call(&mut store, (-1, 1))));
+
+    copy.call(&mut store, (1, 2, 1))?;
+    assert_eq!(call.call(&mut store, 1)?, 1);
+    assert!(oob(copy.call(&mut store, (2, 0, 2))));
+
+    init.call(&mut store, (0, 1, 1))?;
+    assert_eq!(call.call(&mut store, 0)?, 2);
+    assert!(oob(init.call(&mut store, (0, 1, 2))));
+    drop.call(&mut store, ())?;
+    init.call(&mut store, (0, 0, 0))?;
+    assert!(oob(init.call(&mut store, (0, 0, 1))));
+    Ok(())
+}
+
+#[test]
+fn test_table_copy_element_type_mismatch_rejected() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (table $funcs 1 funcref)
+            (table $externs 1 externref)
+            (func (export "copy") (table.copy $externs $funcs (i32.const 0) (i32.const 0) (i32.const 1)))
+        )
+        "#,
+    )?;
+
+    assert!(matches!(Module::parse_bytes(&wasm), Err(Error::ParseError(_))));
+
+    // a module built without the validator traps instead
+    let mut builder = TinyWasmModuleBuilder::new();
+    let funcs = builder.add_table(TableType::new(ValType::RefFunc, 1, None));
+    let externs = builder.add_table(TableType::new(ValType::RefExtern, 1, None));
+    let ty = builder.add_func_type(FuncType::default());
+    let copy = builder.add_func(
+        ty,
+        &[],
+        vec![
+            Instruction::I32Const(0),
+            Instruction::I32Const(0),
+            Instruction::I32Const(1),
+            Instruction::TableCopy { from: funcs, to: externs },
+        ],
+    );
+    builder.add_export("copy", ExternalKind::Func, copy);
+
+    let mut store = Store::default();
+    let instance = Module::from(builder.build()?).instantiate(&mut store, None)?;
+    let result = instance.exported_func::<(), ()>(&store, "copy")?.call(&mut store, ());
+    assert!(matches!(
+        result,
+        Err(Error::Trap(Trap::TableTypeMismatch { expected: ValType::RefExtern, actual: ValType::RefFunc }))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_exported_table_access() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (type $cb (func (result i32)))
+            (table $callbacks (export "callbacks") 1 4 funcref)
+            (table (export "externs") 2 externref)
+            (func (export "seven") (result i32) (i32.const 7))
+            (func (export "run") (param i32) (result i32) (call_indirect (type $cb) (local.get 0))))
+        "#,
+    )?;
+    let mut store = Store::default();
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, None)?;
+    let run = instance.exported_func::<i32, i32>(&store, "run")?;
+    let seven = FuncRef::new(Some(instance.exported_func_untyped(&store, "seven")?.addr()));
+
+    let table = instance.exported_table(&store, "callbacks")?;
+    assert_eq!((table.size(), table.element_type()), (1, ValType::RefFunc));
+    assert_eq!(table.get(0), Some(WasmValue::RefFunc(FuncRef::null())));
+    assert_eq!(table.get(1), None);
+    assert!(matches!(run.call(&mut store, 0), Err(Error::Trap(Trap::UninitializedElement { .. }))));
+
+    // register a callback from the host
+    let mut table = instance.exported_table_mut(&mut store, "callbacks")?;
+    table.set(0, WasmValue::RefFunc(seven))?;
+    assert_eq!(table.get(0), Some(WasmValue::RefFunc(seven)));
+    assert_eq!(run.call(&mut store, 0)?, 7);
+
+    let mut table = instance.exported_table_mut(&mut store, "callbacks")?;
+    assert_eq!(table.grow(2, WasmValue::RefFunc(seven))?, 1);
+    assert_eq!(table.size(), 3);
+    assert!(matches!(table.grow(2, WasmValue::RefFunc(seven)), Err(Error::Trap(Trap::TableOutOfBounds { .. }))));
+    assert!(matches!(table.set(3, WasmValue::RefFunc(seven)), Err(Error::Trap(Trap::TableOutOfBounds { .. }))));
+    assert!(table.set(0, WasmValue::I32(0)).is_err());
+    assert!(table.set(0, WasmValue::RefExtern(ExternRef::null())).is_err());
+    assert!(table.set(0, WasmValue::RefFunc(FuncRef::new(Some(1000)))).is_err());
+    assert_eq!(run.call(&mut store, 2)?, 7);
+
+    let mut externs = instance.exported_table_mut(&mut store, "externs")?;
+    externs.set(1, WasmValue::RefExtern(ExternRef::new(Some(1000))))?;
+    assert_eq!(externs.get(1), Some(WasmValue::RefExtern(ExternRef::new(Some(1000)))));
+
+    assert!(instance.exported_table(&store, "seven").is_err());
+    assert!(instance.exported_table(&store, "missing").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_max_table_elements() -> Result<()> {
+    let mut store = Store::default();
+    store.set_max_table_elements(4);
+    let wasm = wat::parse_str(
+        r#"(module
+            (table $bounded 1 2 funcref)
+            (table $unbounded 1 funcref)
+            (func (export "grow_bounded") (param i32) (result i32) (table.grow $bounded (ref.null func) (local.get 0)))
+            (func (export "grow_unbounded") (param i32) (result i32) (table.grow $unbounded (ref.null func) (local.get 0)))
+            (func (export "size_unbounded") (result i32) (table.size $unbounded))
+        )"#,
+    )?;
+    let instance = Module::parse_bytes(&wasm)?.instantiate(&mut store, None)?;
+    let grow_bounded = instance.exported_func::<i32, i32>(&store, "grow_bounded")?;
+    let grow_unbounded = instance.exported_func::<i32, i32>(&store, "grow_unbounded")?;
+    let size_unbounded = instance.exported_func::<(), i32>(&store, "size_unbounded")?;
+
+    // the module's maximum is lower than the store's
+    assert_eq!(grow_bounded.call(&mut store, 2)?, -1);
+    assert_eq!(grow_bounded.call(&mut store, 1)?, 1);
+
+    // the store's maximum applies to tables without one
+    assert_eq!(grow_unbounded.call(&mut store, 4)?, -1);
+    assert_eq!(size_unbounded.call(&mut store, ())?, 1);
+    assert_eq!(grow_unbounded.call(&mut store, 3)?, 1);
+    assert_eq!(grow_unbounded.call(&mut store, 1)?, -1);
+    assert_eq!(size_unbounded.call(&mut store, ())?, 4);
+    Ok(())
+}
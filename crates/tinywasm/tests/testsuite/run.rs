@@ -185,7 +185,7 @@ impl TestSuite {
             let span = directive.span();
             use wast::WastDirective::{
                 AssertExhaustion, AssertInvalid, AssertMalformed, AssertReturn, AssertTrap, AssertUnlinkable, Invoke,
-                Module as Wat, Register,
+                Module as Wat, ModuleDefinition, Register,
             };
 
             match directive {
@@ -224,6 +224,14 @@ impl TestSuite {
                     test_group.add_result(&format!("Wat({i})"), span.linecol_in(wast_raw), result.map(|_| ()));
                 }
 
+                // only validated, e.g. because the module's memory is too large to instantiate
+                ModuleDefinition(module) => {
+                    let result = catch_unwind_silent(|| parse_module_bytes(&encode_quote_wat(module).1))
+                        .map_err(|e| eyre!("failed to parse module definition: {:?}", try_downcast_panic(e)))
+                        .and_then(|r| r.map(|_| ()));
+                    test_group.add_result(&format!("ModuleDefinition({i})"), span.linecol_in(wast_raw), result);
+                }
+
                 AssertMalformed { span, mut module, message } => {
                     let Ok(module) = module.encode() else {
                         test_group.add_result(&format!("AssertMalformed({i})"), span.linecol_in(wast_raw), Ok(()));
@@ -102,7 +102,7 @@ fn wastarg2tinywasmvalue(arg: wast::WastArg) -> Result<tinywasm_types::WasmValue
         F64(f) => WasmValue::F64(f64::from_bits(f.bits)),
         I32(i) => WasmValue::I32(i),
         I64(i) => WasmValue::I64(i),
-        V128(i) => WasmValue::V128(i128::from_le_bytes(i.to_le_bytes()).try_into().unwrap()),
+        V128(i) => WasmValue::V128(u128::from_le_bytes(i.to_le_bytes())),
         RefExtern(v) => WasmValue::RefExtern(ExternRef::new(Some(v))),
         RefNull(t) => match t {
             wast::core::HeapType::Abstract { shared: false, ty: AbstractHeapType::Func } => {
@@ -118,18 +118,19 @@ fn wastarg2tinywasmvalue(arg: wast::WastArg) -> Result<tinywasm_types::WasmValue
 }
 
 fn wast_i128_to_i128(i: wast::core::V128Pattern) -> u128 {
-    match i {
+    let value = match i {
         wast::core::V128Pattern::F32x4(f) => {
-            f.iter().fold(0, |acc, &f| (acc << 32) | nanpattern2tinywasmvalue(f).unwrap().as_f32().unwrap() as u128)
+            WasmValue::v128_from_f32x4(f.map(|f| nanpattern2tinywasmvalue(f).unwrap().as_f32().unwrap()))
         }
         wast::core::V128Pattern::F64x2(f) => {
-            f.iter().fold(0, |acc, &f| (acc << 64) | nanpattern2tinywasmvalue(f).unwrap().as_f64().unwrap() as u128)
+            WasmValue::v128_from_f64x2(f.map(|f| nanpattern2tinywasmvalue(f).unwrap().as_f64().unwrap()))
         }
-        wast::core::V128Pattern::I16x8(f) => f.iter().fold(0, |acc, &f| (acc << 16) | f as u128),
-        wast::core::V128Pattern::I32x4(f) => f.iter().fold(0, |acc, &f| (acc << 32) | f as u128),
-        wast::core::V128Pattern::I64x2(f) => f.iter().fold(0, |acc, &f| (acc << 64) | f as u128),
-        wast::core::V128Pattern::I8x16(f) => f.iter().fold(0, |acc, &f| (acc << 8) | f as u128),
-    }
+        wast::core::V128Pattern::I16x8(f) => WasmValue::v128_from_i16x8(f),
+        wast::core::V128Pattern::I32x4(f) => WasmValue::v128_from_i32x4(f),
+        wast::core::V128Pattern::I64x2(f) => WasmValue::v128_from_i64x2(f),
+        wast::core::V128Pattern::I8x16(f) => WasmValue::v128_from_i8x16(f),
+    };
+    value.as_v128().unwrap()
 }
 
 fn wastret2tinywasmvalue(ret: wast::WastRet) -> Result<tinywasm_types::WasmValue> {
@@ -0,0 +1,48 @@
+//! Tests for the instruction tracing hook.
+//!
+//! Run with `cargo test -p tinywasm --features trace --test trace`.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use tinywasm::types::{FuncAddr, Instruction};
+use tinywasm::{Module, Store};
+
+const WAT: &str = r#"
+(module
+  (func $double (param i32) (result i32)
+    (i32.mul (local.get 0) (i32.const 2)))
+  (func (export "run") (param i32) (result i32)
+    (call $double (i32.add (local.get 0) (i32.const 1)))))
+"#;
+
+#[test]
+fn test_trace_hook() -> tinywasm::Result<()> {
+    let module = Module::parse_bytes(&wat::parse_str(WAT).expect("valid wat"))?;
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, None)?;
+    let run = instance.exported_func::<i32, i32>(&store, "run")?;
+
+    let trace: Rc<RefCell<Vec<(FuncAddr, usize, Instruction)>>> = Rc::default();
+    let recorded = trace.clone();
+    store.set_trace_hook(Box::new(move |func, ip, instr| recorded.borrow_mut().push((func, ip, *instr))));
+    assert_eq!(run.call(&mut store, 20)?, 42);
+
+    let trace = trace.take();
+    assert!(!trace.is_empty());
+    assert_eq!(trace[0].1, 0, "tracing starts at the first instruction of the called function");
+
+    let funcs: Vec<FuncAddr> = trace.iter().map(|(func, ..)| *func).collect();
+    let callee = *funcs.iter().find(|&&f| f != funcs[0]).expect("the call to $double is traced");
+    assert!(trace.iter().any(|(func, _, instr)| *func == funcs[0] && matches!(instr, Instruction::Call(_))));
+    assert!(trace.iter().any(|(func, _, instr)| *func == callee && matches!(instr, Instruction::I32Mul)));
+
+    // instruction pointers increase within a function until it calls another one
+    let caller_ips: Vec<usize> = trace.iter().filter(|(f, ..)| *f == funcs[0]).map(|(_, ip, _)| *ip).collect();
+    assert!(caller_ips.windows(2).all(|w| w[0] < w[1]));
+
+    assert!(store.take_trace_hook().is_some());
+    assert_eq!(run.call(&mut store, 1)?, 4);
+    assert!(store.take_trace_hook().is_none());
+    Ok(())
+}
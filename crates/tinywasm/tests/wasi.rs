@@ -0,0 +1,215 @@
+//! Tests for the WASI shim.
+//!
+//! Run with `cargo test -p tinywasm --features wasi --test wasi`.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use eyre::Result;
+use tinywasm::wasi::{self, errno, WasiCtx};
+use tinywasm::{Error, Imports, Module, ModuleInstance, Store};
+
+const WAT: &str = r#"
+(module
+  (import "wasi_snapshot_preview1" "args_sizes_get" (func $args_sizes_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "args_get" (func $args_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "environ_sizes_get" (func $environ_sizes_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "environ_get" (func $environ_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "clock_time_get" (func $clock_time_get (param i32 i64 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "random_get" (func $random_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+  (memory (export "memory") 1)
+
+  ;; two iovecs at 0: { buf: 64, len: 3 } and { buf: 67, len: 4 }
+  (data (i32.const 0) "\40\00\00\00\03\00\00\00\43\00\00\00\04\00\00\00")
+  (data (i32.const 64) "hello\n!")
+
+  (func (export "write") (param $fd i32) (result i32)
+    (call $fd_write (local.get $fd) (i32.const 0) (i32.const 2) (i32.const 16)))
+  (func (export "read") (result i32)
+    (call $fd_read (i32.const 0) (i32.const 0) (i32.const 2) (i32.const 16)))
+  (func (export "args") (result i32)
+    (drop (call $args_sizes_get (i32.const 16) (i32.const 20)))
+    (call $args_get (i32.const 128) (i32.const 256)))
+  (func (export "environ") (result i32)
+    (drop (call $environ_sizes_get (i32.const 16) (i32.const 20)))
+    (call $environ_get (i32.const 128) (i32.const 256)))
+  (func (export "clock") (param $id i32) (result i32)
+    (call $clock_time_get (local.get $id) (i64.const 0) (i32.const 24)))
+  (func (export "random") (result i32)
+    (call $random_get (i32.const 64) (i32.const 4)))
+  (func (export "random_at") (param $buf i32) (param $len i32) (result i32)
+    (call $random_get (local.get $buf) (local.get $len)))
+  (func (export "read_iovec") (param $iovs i32) (result i32)
+    (call $fd_read (i32.const 0) (local.get $iovs) (i32.const 1) (i32.const 16)))
+  (func (export "write_at") (param $iovs i32) (param $nwritten i32) (result i32)
+    (call $fd_write (i32.const 1) (local.get $iovs) (i32.const 1) (local.get $nwritten)))
+  (func (export "clock_at") (param $ptr i32) (result i32)
+    (call $clock_time_get (i32.const 1) (i64.const 0) (local.get $ptr)))
+  (func (export "args_at") (param $count i32) (param $size i32) (result i32)
+    (call $args_sizes_get (local.get $count) (local.get $size)))
+  (func (export "environ_at") (param $environ i32) (param $buf i32) (result i32)
+    (call $environ_get (local.get $environ) (local.get $buf)))
+  (func (export "exit") (param $code i32)
+    (call $proc_exit (local.get $code))
+    unreachable))
+"#;
+
+type Output = Rc<RefCell<Vec<u8>>>;
+
+fn instantiate(ctx: WasiCtx) -> Result<(Store, ModuleInstance)> {
+    let mut imports = Imports::new();
+    wasi::add_to_imports(&mut imports, ctx)?;
+    let mut store = Store::default();
+    let instance = Module::parse_bytes(&wat::parse_str(WAT)?)?.instantiate(&mut store, Some(imports))?;
+    Ok((store, instance))
+}
+
+fn sink(output: &Output) -> impl FnMut(&[u8]) + 'static {
+    let output = output.clone();
+    move |bytes| output.borrow_mut().extend_from_slice(bytes)
+}
+
+fn load(store: &mut Store, instance: &ModuleInstance, offset: usize, len: usize) -> Result<Vec<u8>> {
+    Ok(instance.exported_memory(store, "memory")?.load_vec(offset, len)?)
+}
+
+#[test]
+fn test_wasi_fd_write() -> Result<()> {
+    let (stdout, stderr) = (Output::default(), Output::default());
+    let (mut store, instance) = instantiate(WasiCtx::new().with_stdout(sink(&stdout)).with_stderr(sink(&stderr)))?;
+    let write = instance.exported_func::<i32, i32>(&store, "write")?;
+
+    assert_eq!(write.call(&mut store, 1)?, errno::SUCCESS);
+    assert_eq!(load(&mut store, &instance, 16, 4)?, 7u32.to_le_bytes());
+    assert_eq!(write.call(&mut store, 2)?, errno::SUCCESS);
+    assert_eq!(write.call(&mut store, 3)?, errno::BADF);
+
+    assert_eq!(stdout.borrow().as_slice(), b"hello\n!");
+    assert_eq!(stderr.borrow().as_slice(), b"hello\n!");
+    Ok(())
+}
+
+#[test]
+fn test_wasi_fd_read() -> Result<()> {
+    let mut input = &b"abcdefgh"[..];
+    let ctx = WasiCtx::new().with_stdin(move |buf| {
+        let n = buf.len().min(input.len());
+        buf[..n].copy_from_slice(&input[..n]);
+        input = &input[n..];
+        n
+    });
+    let (mut store, instance) = instantiate(ctx)?;
+    let read = instance.exported_func::<(), i32>(&store, "read")?;
+
+    assert_eq!(read.call(&mut store, ())?, errno::SUCCESS);
+    assert_eq!(load(&mut store, &instance, 16, 4)?, 7u32.to_le_bytes());
+    assert_eq!(load(&mut store, &instance, 64, 7)?, b"abcdefg");
+
+    // only one byte is left, the second buffer is never reached
+    assert_eq!(read.call(&mut store, ())?, errno::SUCCESS);
+    assert_eq!(load(&mut store, &instance, 16, 4)?, 1u32.to_le_bytes());
+    assert_eq!(load(&mut store, &instance, 64, 7)?, b"hbcdefg");
+
+    assert_eq!(read.call(&mut store, ())?, errno::SUCCESS);
+    assert_eq!(load(&mut store, &instance, 16, 4)?, 0u32.to_le_bytes());
+
+    // an iovec far past the end of memory is rejected without reading into it
+    let mut memory = instance.exported_memory_mut(&mut store, "memory")?;
+    memory.store(32, 8, &[64, 0, 0, 0, 0xf0, 0xff, 0xff, 0xff])?;
+    let read_iovec = instance.exported_func::<i32, i32>(&store, "read_iovec")?;
+    assert_eq!(read_iovec.call(&mut store, 32)?, errno::FAULT);
+    Ok(())
+}
+
+#[test]
+fn test_wasi_args_and_environ() -> Result<()> {
+    let ctx = WasiCtx::new().with_args(["prog", "-v"]).with_env("HOME", "/home/wasm");
+    let (mut store, instance) = instantiate(ctx)?;
+
+    assert_eq!(instance.exported_func::<(), i32>(&store, "args")?.call(&mut store, ())?, errno::SUCCESS);
+    assert_eq!(load(&mut store, &instance, 16, 8)?, [2, 0, 0, 0, 8, 0, 0, 0]);
+    assert_eq!(load(&mut store, &instance, 128, 8)?, [0, 1, 0, 0, 5, 1, 0, 0]);
+    assert_eq!(load(&mut store, &instance, 256, 8)?, b"prog\0-v\0");
+
+    assert_eq!(instance.exported_func::<(), i32>(&store, "environ")?.call(&mut store, ())?, errno::SUCCESS);
+    assert_eq!(load(&mut store, &instance, 16, 8)?, [1, 0, 0, 0, 16, 0, 0, 0]);
+    assert_eq!(load(&mut store, &instance, 128, 4)?, [0, 1, 0, 0]);
+    assert_eq!(load(&mut store, &instance, 256, 16)?, b"HOME=/home/wasm\0");
+    Ok(())
+}
+
+#[test]
+fn test_wasi_clock_and_random() -> Result<()> {
+    let ctx =
+        WasiCtx::new().with_clock(|id| (id == wasi::clock::MONOTONIC).then_some(42)).with_random(|buf| buf.fill(7));
+    let (mut store, instance) = instantiate(ctx)?;
+    let clock = instance.exported_func::<i32, i32>(&store, "clock")?;
+
+    assert_eq!(clock.call(&mut store, wasi::clock::MONOTONIC as i32)?, errno::SUCCESS);
+    assert_eq!(load(&mut store, &instance, 24, 8)?, 42u64.to_le_bytes());
+    assert_eq!(clock.call(&mut store, wasi::clock::REALTIME as i32)?, errno::INVAL);
+
+    assert_eq!(instance.exported_func::<(), i32>(&store, "random")?.call(&mut store, ())?, errno::SUCCESS);
+    assert_eq!(load(&mut store, &instance, 64, 5)?, [7, 7, 7, 7, b'o']);
+
+    // buffers past the end of memory are rejected before anything is written
+    let random_at = instance.exported_func::<(i32, i32), i32>(&store, "random_at")?;
+    assert_eq!(random_at.call(&mut store, (64, -1))?, errno::FAULT);
+    assert_eq!(random_at.call(&mut store, (0xfffe, 4))?, errno::FAULT);
+    assert_eq!(load(&mut store, &instance, 0xfffe, 2)?, [0, 0]);
+    assert_eq!(random_at.call(&mut store, (0xfffc, 4))?, errno::SUCCESS);
+
+    // without a source of randomness, random_get is unsupported
+    let (mut store, instance) = instantiate(WasiCtx::new())?;
+    assert_eq!(instance.exported_func::<(), i32>(&store, "random")?.call(&mut store, ())?, errno::NOSYS);
+    Ok(())
+}
+
+#[test]
+fn test_wasi_guest_pointers_out_of_bounds() -> Result<()> {
+    let stdout = Output::default();
+    let ctx = WasiCtx::new()
+        .with_stdout(sink(&stdout))
+        .with_args(["prog"])
+        .with_env("HOME", "/")
+        .with_clock(|_| Some(42))
+        .with_stdin(|_| 0);
+    let (mut store, instance) = instantiate(ctx)?;
+
+    // pointers past the end of memory fail with EFAULT instead of trapping
+    let write_at = instance.exported_func::<(i32, i32), i32>(&store, "write_at")?;
+    assert_eq!(write_at.call(&mut store, (0xfffc, 16))?, errno::FAULT);
+    assert_eq!(write_at.call(&mut store, (0, 0xfffe))?, errno::FAULT);
+    instance.exported_memory_mut(&mut store, "memory")?.store(32, 8, &[0xfe, 0xff, 0, 0, 4, 0, 0, 0])?;
+    assert_eq!(write_at.call(&mut store, (32, 16))?, errno::FAULT);
+
+    let clock_at = instance.exported_func::<i32, i32>(&store, "clock_at")?;
+    assert_eq!(clock_at.call(&mut store, 0xfffc)?, errno::FAULT);
+    assert_eq!(clock_at.call(&mut store, -1)?, errno::FAULT);
+
+    let args_at = instance.exported_func::<(i32, i32), i32>(&store, "args_at")?;
+    assert_eq!(args_at.call(&mut store, (0x10000, 20))?, errno::FAULT);
+    assert_eq!(args_at.call(&mut store, (16, 0xfffe))?, errno::FAULT);
+
+    let environ_at = instance.exported_func::<(i32, i32), i32>(&store, "environ_at")?;
+    assert_eq!(environ_at.call(&mut store, (128, 0xfffc))?, errno::FAULT);
+    assert_eq!(environ_at.call(&mut store, (-1, 256))?, errno::FAULT);
+
+    let read_iovec = instance.exported_func::<i32, i32>(&store, "read_iovec")?;
+    assert_eq!(read_iovec.call(&mut store, 0xfffc)?, errno::FAULT);
+
+    // only the buffer written before `nwritten` faulted reached stdout
+    assert_eq!(stdout.borrow().as_slice(), b"hel");
+    Ok(())
+}
+
+#[test]
+fn test_wasi_proc_exit() -> Result<()> {
+    let (mut store, instance) = instantiate(WasiCtx::new())?;
+    let exit = instance.exported_func::<i32, ()>(&store, "exit")?;
+    assert!(matches!(exit.call(&mut store, 3), Err(Error::ProcExit(3))));
+    Ok(())
+}
@@ -10,7 +10,7 @@ use rkyv::{
 };
 
 const TWASM_MAGIC_PREFIX: &[u8; 4] = b"TWAS";
-const TWASM_VERSION: &[u8; 2] = b"02";
+const TWASM_VERSION: &[u8; 2] = b"03";
 #[rustfmt::skip]
 const TWASM_MAGIC: [u8; 16] = [ TWASM_MAGIC_PREFIX[0], TWASM_MAGIC_PREFIX[1], TWASM_MAGIC_PREFIX[2], TWASM_MAGIC_PREFIX[3], TWASM_VERSION[0], TWASM_VERSION[1], 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
 
@@ -102,4 +102,13 @@ mod tests {
         twasm[4] = 0;
         assert!(matches!(TinyWasmModule::from_twasm(&twasm), Err(TwasmError::InvalidVersion)));
     }
+
+    #[test]
+    fn test_old_version() {
+        // archives written before the layout changed must not be decoded with the new layout
+        let wasm = TinyWasmModule::default();
+        let mut twasm = wasm.serialize_twasm();
+        twasm[4..6].copy_from_slice(b"02");
+        assert!(matches!(TinyWasmModule::from_twasm(&twasm), Err(TwasmError::InvalidVersion)));
+    }
 }
@@ -0,0 +1,193 @@
+use crate::{
+    Export, ExternalKind, FuncAddr, FuncType, Global, GlobalAddr, Instruction, MemAddr, MemoryType, TableAddr,
+    TableType, TinyWasmModule, TypeAddr, ValType, ValueCounts, ValueCountsSmall, WasmFunction, WasmFunctionData,
+};
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use core::fmt::{Display, Formatter};
+
+/// A builder for assembling a [`TinyWasmModule`] without going through the parser
+///
+/// Function bodies use tinywasm's own instruction set, not WebAssembly's:
+/// locals are addressed per value size (see [`Instruction::LocalGet32`] and friends),
+/// with the function's parameters first. A missing trailing [`Instruction::Return`] is added by the builder.
+///
+/// Only indices are validated, the instructions themselves are trusted to be valid.
+///
+/// ```
+/// use tinywasm_types::{ExternalKind, FuncType, Instruction, TinyWasmModuleBuilder, ValType};
+///
+/// let mut builder = TinyWasmModuleBuilder::new();
+/// let ty = builder.add_func_type(FuncType { params: Box::new([]), results: Box::new([ValType::I32]) });
+/// let func = builder.add_func(ty, &[], vec![Instruction::I32Const(42)]);
+/// builder.add_export("answer", ExternalKind::Func, func);
+/// let module = builder.build().unwrap();
+/// assert_eq!(module.funcs.len(), 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct TinyWasmModuleBuilder {
+    func_types: Vec<FuncType>,
+    funcs: Vec<(TypeAddr, Box<[ValType]>, Vec<Instruction>)>,
+    exports: Vec<Export>,
+    globals: Vec<Global>,
+    table_types: Vec<TableType>,
+    memory_types: Vec<MemoryType>,
+    start_func: Option<FuncAddr>,
+}
+
+impl TinyWasmModuleBuilder {
+    /// Create an empty module builder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a function type, returning its index
+    pub fn add_func_type(&mut self, ty: FuncType) -> TypeAddr {
+        self.func_types.push(ty);
+        self.func_types.len() as TypeAddr - 1
+    }
+
+    /// Add a function of type `ty` with the given locals (excluding its parameters), returning its index
+    pub fn add_func(&mut self, ty: TypeAddr, locals: &[ValType], instructions: Vec<Instruction>) -> FuncAddr {
+        self.funcs.push((ty, Box::from(locals), instructions));
+        self.funcs.len() as FuncAddr - 1
+    }
+
+    /// Add a global, returning its index
+    pub fn add_global(&mut self, global: Global) -> GlobalAddr {
+        self.globals.push(global);
+        self.globals.len() as GlobalAddr - 1
+    }
+
+    /// Add a table, returning its index
+    pub fn add_table(&mut self, ty: TableType) -> TableAddr {
+        self.table_types.push(ty);
+        self.table_types.len() as TableAddr - 1
+    }
+
+    /// Add a memory, returning its index
+    pub fn add_memory(&mut self, ty: MemoryType) -> MemAddr {
+        self.memory_types.push(ty);
+        self.memory_types.len() as MemAddr - 1
+    }
+
+    /// Export the item of the given kind at `index` under `name`
+    pub fn add_export(&mut self, name: &str, kind: ExternalKind, index: u32) -> &mut Self {
+        self.exports.push(Export { name: Box::from(name), kind, index });
+        self
+    }
+
+    /// Set the function that is called when the module is instantiated
+    pub fn set_start_func(&mut self, func: FuncAddr) -> &mut Self {
+        self.start_func = Some(func);
+        self
+    }
+
+    /// Validate the indices used by the module and build it
+    pub fn build(self) -> Result<TinyWasmModule, ModuleBuildError> {
+        let funcs = self
+            .funcs
+            .into_iter()
+            .enumerate()
+            .map(|(func, (ty_idx, locals, mut instructions))| {
+                let ty = self
+                    .func_types
+                    .get(ty_idx as usize)
+                    .ok_or(ModuleBuildError::UnknownType { func: func as FuncAddr, ty: ty_idx })?;
+
+                let params = ValueCountsSmall::from(&ty.params);
+                let locals = ValueCounts::from(ty.params.iter().chain(locals.iter()));
+
+                if !matches!(instructions.last(), Some(Instruction::Return)) {
+                    instructions.push(Instruction::Return);
+                }
+
+                let instructions = instructions.into_boxed_slice();
+                Ok(Arc::new(WasmFunction {
+                    instructions,
+                    data: WasmFunctionData::default(),
+                    locals,
+                    params,
+                    ty: ty.clone(),
+                }))
+            })
+            .collect::<Result<Box<[_]>, _>>()?;
+
+        for (i, export) in self.exports.iter().enumerate() {
+            let count = match export.kind {
+                ExternalKind::Func => funcs.len(),
+                ExternalKind::Table => self.table_types.len(),
+                ExternalKind::Memory => self.memory_types.len(),
+                ExternalKind::Global => self.globals.len(),
+            };
+
+            if export.index as usize >= count {
+                return Err(ModuleBuildError::UnknownExport { name: export.name.clone() });
+            }
+
+            if self.exports[..i].iter().any(|e| e.name == export.name) {
+                return Err(ModuleBuildError::DuplicateExport { name: export.name.clone() });
+            }
+        }
+
+        if let Some(start) = self.start_func {
+            let Some(func) = funcs.get(start as usize) else {
+                return Err(ModuleBuildError::InvalidStartFunc { func: start });
+            };
+
+            if !func.ty.params.is_empty() || !func.ty.results.is_empty() {
+                return Err(ModuleBuildError::InvalidStartFunc { func: start });
+            }
+        }
+
+        Ok(TinyWasmModule {
+            start_func: self.start_func,
+            funcs,
+            func_types: self.func_types.into_boxed_slice(),
+            exports: self.exports.into_boxed_slice(),
+            globals: self.globals.into_boxed_slice(),
+            table_types: self.table_types.into_boxed_slice(),
+            memory_types: self.memory_types.into_boxed_slice(),
+            ..Default::default()
+        })
+    }
+}
+
+/// An error returned by [`TinyWasmModuleBuilder::build`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModuleBuildError {
+    /// A function uses a type index that was never added
+    UnknownType {
+        /// The index of the function
+        func: FuncAddr,
+        /// The unknown type index
+        ty: TypeAddr,
+    },
+    /// An export refers to an item that doesn't exist
+    UnknownExport {
+        /// The name of the export
+        name: Box<str>,
+    },
+    /// Two exports share the same name
+    DuplicateExport {
+        /// The name of the exports
+        name: Box<str>,
+    },
+    /// The start function doesn't exist or takes parameters or returns results
+    InvalidStartFunc {
+        /// The index of the start function
+        func: FuncAddr,
+    },
+}
+
+impl Display for ModuleBuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnknownType { func, ty } => write!(f, "function {func} has unknown type {ty}"),
+            Self::UnknownExport { name } => write!(f, "export {name} refers to an unknown item"),
+            Self::DuplicateExport { name } => write!(f, "duplicate export name {name}"),
+            Self::InvalidStartFunc { func } => write!(f, "invalid start function {func}"),
+        }
+    }
+}
+
+impl core::error::Error for ModuleBuildError {}
@@ -36,9 +36,20 @@ pub enum ConstInstruction {
     I64Const(i64),
     F32Const(f32),
     F64Const(f64),
+    V128Const(u128),
     GlobalGet(GlobalAddr),
     RefFunc(Option<FuncAddr>),
     RefExtern(Option<ExternAddr>),
+    // extended-const proposal
+    I32Add,
+    I32Sub,
+    I32Mul,
+    I64Add,
+    I64Sub,
+    I64Mul,
+    // gc proposal (i31 references only)
+    RefI31,
+    RefNullI31,
 }
 
 /// A WebAssembly Instruction
@@ -86,8 +97,8 @@ pub enum Instruction {
     Return,
     Call(FuncAddr),
     CallIndirect(TypeAddr, TableAddr),
-    // ReturnCall(FuncAddr),
-    // ReturnCallIndirect(TypeAddr, TableAddr),
+    ReturnCall(FuncAddr),
+    ReturnCallIndirect(TypeAddr, TableAddr),
  
     // > Parametric Instructions
     // See <https://webassembly.github.io/spec/core/binary/instructions.html#parametric-instructions>
@@ -141,6 +152,9 @@ pub enum Instruction {
     RefNull(ValType),
     RefFunc(FuncAddr),
     RefIsNull,
+
+    // > GC Instructions (i31 references only)
+    RefI31, I31GetS, I31GetU,
  
     // > Numeric Instructions
     // See <https://webassembly.github.io/spec/core/binary/instructions.html#numeric-instructions>
@@ -185,6 +199,9 @@ pub enum Instruction {
 
     // > SIMD Instructions
     Simd(SimdInstruction),
+
+    // > Atomic Instructions
+    Atomic(AtomicInstruction),
 }
 
 impl From<SimdInstruction> for Instruction {
@@ -193,6 +210,12 @@ impl From<SimdInstruction> for Instruction {
     }
 }
 
+impl From<AtomicInstruction> for Instruction {
+    fn from(instr: AtomicInstruction) -> Self {
+        Instruction::Atomic(instr)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "archive", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
 #[rustfmt::skip] 
@@ -258,6 +281,39 @@ pub enum SimdInstruction {
     F32x4DemoteF64x2Zero, F64x2PromoteLowF32x4,
 }
 
+/// Instructions from the threads proposal
+///
+/// Wasm is only ever run on a single thread, so these behave like their non-atomic counterparts,
+/// except that accesses have to be naturally aligned.
+///
+/// See <https://github.com/WebAssembly/threads/blob/main/proposals/threads/Overview.md>
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "archive", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[rustfmt::skip]
+pub enum AtomicInstruction {
+    MemoryAtomicNotify(MemoryArg), MemoryAtomicWait32(MemoryArg), MemoryAtomicWait64(MemoryArg), AtomicFence,
+
+    I32AtomicLoad(MemoryArg), I32AtomicLoad8U(MemoryArg), I32AtomicLoad16U(MemoryArg),
+    I64AtomicLoad(MemoryArg), I64AtomicLoad8U(MemoryArg), I64AtomicLoad16U(MemoryArg), I64AtomicLoad32U(MemoryArg),
+    I32AtomicStore(MemoryArg), I32AtomicStore8(MemoryArg), I32AtomicStore16(MemoryArg),
+    I64AtomicStore(MemoryArg), I64AtomicStore8(MemoryArg), I64AtomicStore16(MemoryArg), I64AtomicStore32(MemoryArg),
+
+    I32AtomicRmwAdd(MemoryArg), I32AtomicRmw8AddU(MemoryArg), I32AtomicRmw16AddU(MemoryArg),
+    I64AtomicRmwAdd(MemoryArg), I64AtomicRmw8AddU(MemoryArg), I64AtomicRmw16AddU(MemoryArg), I64AtomicRmw32AddU(MemoryArg),
+    I32AtomicRmwSub(MemoryArg), I32AtomicRmw8SubU(MemoryArg), I32AtomicRmw16SubU(MemoryArg),
+    I64AtomicRmwSub(MemoryArg), I64AtomicRmw8SubU(MemoryArg), I64AtomicRmw16SubU(MemoryArg), I64AtomicRmw32SubU(MemoryArg),
+    I32AtomicRmwAnd(MemoryArg), I32AtomicRmw8AndU(MemoryArg), I32AtomicRmw16AndU(MemoryArg),
+    I64AtomicRmwAnd(MemoryArg), I64AtomicRmw8AndU(MemoryArg), I64AtomicRmw16AndU(MemoryArg), I64AtomicRmw32AndU(MemoryArg),
+    I32AtomicRmwOr(MemoryArg), I32AtomicRmw8OrU(MemoryArg), I32AtomicRmw16OrU(MemoryArg),
+    I64AtomicRmwOr(MemoryArg), I64AtomicRmw8OrU(MemoryArg), I64AtomicRmw16OrU(MemoryArg), I64AtomicRmw32OrU(MemoryArg),
+    I32AtomicRmwXor(MemoryArg), I32AtomicRmw8XorU(MemoryArg), I32AtomicRmw16XorU(MemoryArg),
+    I64AtomicRmwXor(MemoryArg), I64AtomicRmw8XorU(MemoryArg), I64AtomicRmw16XorU(MemoryArg), I64AtomicRmw32XorU(MemoryArg),
+    I32AtomicRmwXchg(MemoryArg), I32AtomicRmw8XchgU(MemoryArg), I32AtomicRmw16XchgU(MemoryArg),
+    I64AtomicRmwXchg(MemoryArg), I64AtomicRmw8XchgU(MemoryArg), I64AtomicRmw16XchgU(MemoryArg), I64AtomicRmw32XchgU(MemoryArg),
+    I32AtomicRmwCmpxchg(MemoryArg), I32AtomicRmw8CmpxchgU(MemoryArg), I32AtomicRmw16CmpxchgU(MemoryArg),
+    I64AtomicRmwCmpxchg(MemoryArg), I64AtomicRmw8CmpxchgU(MemoryArg), I64AtomicRmw16CmpxchgU(MemoryArg), I64AtomicRmw32CmpxchgU(MemoryArg),
+}
+
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "archive", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
 #[rustfmt::skip]
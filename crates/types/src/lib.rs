@@ -9,15 +9,27 @@
 //! Types used by [`tinywasm`](https://docs.rs/tinywasm) and [`tinywasm_parser`](https://docs.rs/tinywasm_parser).
 
 extern crate alloc;
-use alloc::boxed::Box;
+use alloc::{boxed::Box, sync::Arc};
 use core::{fmt::Debug, ops::Range};
 
 // Memory defaults
 const MEM_PAGE_SIZE: u64 = 65536;
 const MAX_MEMORY_SIZE: u64 = 4294967296;
-
-const fn max_page_count(page_size: u64) -> u64 {
-    MAX_MEMORY_SIZE / page_size
+const MAX_MEMORY64_SIZE: u128 = 1 << 64;
+
+const fn max_page_count(arch: MemoryArch, page_size: u64) -> u64 {
+    match arch {
+        MemoryArch::I32 => MAX_MEMORY_SIZE / page_size,
+        // 2^64 pages of 1 byte don't fit in a u64, one less is still more than any host can allocate
+        MemoryArch::I64 => {
+            let pages = MAX_MEMORY64_SIZE / page_size as u128;
+            if pages > u64::MAX as u128 {
+                u64::MAX
+            } else {
+                pages as u64
+            }
+        }
+    }
 }
 
 // log for logging (optional).
@@ -37,8 +49,10 @@ pub(crate) mod log {
     pub(crate) use info;
 }
 
+mod builder;
 mod instructions;
 mod value;
+pub use builder::*;
 pub use instructions::*;
 pub use value::*;
 
@@ -61,7 +75,8 @@ pub struct TinyWasmModule {
     /// Optimized and validated WebAssembly functions
     ///
     /// Contains data from to the `code`, `func`, and `type` sections of the original WebAssembly module.
-    pub funcs: Box<[WasmFunction]>,
+    /// Functions are reference counted so every instance of a module shares the same function bodies.
+    pub funcs: Box<[Arc<WasmFunction>]>,
 
     /// A vector of type definitions, indexed by `TypeAddr`
     ///
@@ -102,6 +117,12 @@ pub struct TinyWasmModule {
     ///
     /// Corresponds to the `elem` section of the original WebAssembly module.
     pub elements: Box<[Element]>,
+
+    /// Function names, sorted by function index (including imported functions).
+    ///
+    /// Corresponds to the function names in the `name` custom section of the original WebAssembly module,
+    /// empty if it is missing or malformed.
+    pub func_names: Box<[(FuncAddr, Box<str>)]>,
 }
 
 /// A WebAssembly External Kind.
@@ -212,7 +233,7 @@ impl<'a, T: IntoIterator<Item = &'a ValType>> From<T> for ValueCounts {
                 ValType::I32 | ValType::F32 => counts.c32 += 1,
                 ValType::I64 | ValType::F64 => counts.c64 += 1,
                 ValType::V128 => counts.c128 += 1,
-                ValType::RefExtern | ValType::RefFunc => counts.cref += 1,
+                ValType::RefExtern | ValType::RefFunc | ValType::RefI31 => counts.cref += 1,
             }
         }
         counts
@@ -227,7 +248,7 @@ impl<'a, T: IntoIterator<Item = &'a ValType>> From<T> for ValueCountsSmall {
                 ValType::I32 | ValType::F32 => counts.c32 += 1,
                 ValType::I64 | ValType::F64 => counts.c64 += 1,
                 ValType::V128 => counts.c128 += 1,
-                ValType::RefExtern | ValType::RefFunc => counts.cref += 1,
+                ValType::RefExtern | ValType::RefFunc | ValType::RefI31 => counts.cref += 1,
             }
         }
         counts
@@ -248,6 +269,9 @@ pub struct WasmFunction {
 #[cfg_attr(feature = "archive", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
 pub struct WasmFunctionData {
     pub v128_constants: Box<[u128]>,
+    /// The offset in the wasm binary of the operator each instruction was compiled from,
+    /// empty unless [enabled in the parser](https://docs.rs/tinywasm-parser/latest/tinywasm_parser/struct.Parser.html#method.with_instruction_offsets)
+    pub instruction_offsets: Box<[u32]>,
 }
 
 /// A WebAssembly Module Export
@@ -266,7 +290,8 @@ pub struct Export {
 #[cfg_attr(feature = "archive", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
 pub struct Global {
     pub ty: GlobalType,
-    pub init: ConstInstruction,
+    /// The constant expression the global is initialized with
+    pub init: Box<[ConstInstruction]>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -302,11 +327,22 @@ pub struct MemoryType {
     page_count_initial: u64,
     page_count_max: Option<u64>,
     page_size: Option<u64>,
+    shared: bool,
 }
 
 impl MemoryType {
     pub fn new(arch: MemoryArch, page_count_initial: u64, page_count_max: Option<u64>, page_size: Option<u64>) -> Self {
-        Self { arch, page_count_initial, page_count_max, page_size }
+        Self { arch, page_count_initial, page_count_max, page_size, shared: false }
+    }
+
+    /// Mark the memory as shared, as declared with the threads proposal's `shared` keyword
+    pub fn with_shared(mut self, shared: bool) -> Self {
+        self.shared = shared;
+        self
+    }
+
+    pub fn is_shared(&self) -> bool {
+        self.shared
     }
 
     pub fn arch(&self) -> MemoryArch {
@@ -318,7 +354,11 @@ impl MemoryType {
     }
 
     pub fn page_count_max(&self) -> u64 {
-        self.page_count_max.unwrap_or_else(|| max_page_count(self.page_size()))
+        self.page_count_max.unwrap_or_else(|| max_page_count(self.arch, self.page_size()))
+    }
+
+    pub fn page_count_max_declared(&self) -> Option<u64> {
+        self.page_count_max
     }
 
     pub fn page_size(&self) -> u64 {
@@ -326,11 +366,11 @@ impl MemoryType {
     }
 
     pub fn initial_size(&self) -> u64 {
-        self.page_count_initial * self.page_size()
+        self.page_count_initial.saturating_mul(self.page_size())
     }
 
     pub fn max_size(&self) -> u64 {
-        self.page_count_max() * self.page_size()
+        self.page_count_max().saturating_mul(self.page_size())
     }
 }
 
@@ -381,7 +421,7 @@ pub struct Data {
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "archive", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
 pub enum DataKind {
-    Active { mem: MemAddr, offset: ConstInstruction },
+    Active { mem: MemAddr, offset: Box<[ConstInstruction]> },
     Passive,
 }
 
@@ -394,11 +434,11 @@ pub struct Element {
     pub ty: ValType,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "archive", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
 pub enum ElementKind {
     Passive,
-    Active { table: TableAddr, offset: ConstInstruction },
+    Active { table: TableAddr, offset: Box<[ConstInstruction]> },
     Declared,
 }
 
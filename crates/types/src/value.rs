@@ -6,6 +6,7 @@ use crate::{ConstInstruction, ExternAddr, FuncAddr};
 ///
 /// See <https://webassembly.github.io/spec/core/syntax/types.html#value-types>
 #[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum WasmValue {
     // Num types
     /// A 32-bit integer.
@@ -17,18 +18,41 @@ pub enum WasmValue {
     /// A 64-bit float.
     F64(f64),
     // /// A 128-bit vector
+    #[cfg_attr(feature = "serde", serde(with = "v128_bytes"))]
     V128(u128),
 
     RefExtern(ExternRef),
     RefFunc(FuncRef),
+    RefI31(I31Ref),
 }
 
 #[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExternRef(Option<ExternAddr>);
 
 #[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FuncRef(Option<FuncAddr>);
 
+/// An unboxed 31-bit integer reference (`i31ref`) from the GC proposal
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct I31Ref(Option<u32>);
+
+// v128 values are (de)serialized as their 16 little-endian bytes, not every format supports 128-bit integers
+#[cfg(feature = "serde")]
+mod v128_bytes {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(value: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+        value.to_le_bytes().serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+        <[u8; 16]>::deserialize(deserializer).map(u128::from_le_bytes)
+    }
+}
+
 impl Debug for ExternRef {
     fn fmt(&self, f: &mut alloc::fmt::Formatter<'_>) -> alloc::fmt::Result {
         match self.0 {
@@ -47,6 +71,15 @@ impl Debug for FuncRef {
     }
 }
 
+impl Debug for I31Ref {
+    fn fmt(&self, f: &mut alloc::fmt::Formatter<'_>) -> alloc::fmt::Result {
+        match self.get_s() {
+            Some(value) => write!(f, "i31({value})"),
+            None => write!(f, "i31(null)"),
+        }
+    }
+}
+
 impl FuncRef {
     /// Create a new `FuncRef` from a `FuncAddr`.
     /// Should only be used by the runtime.
@@ -103,6 +136,41 @@ impl ExternRef {
     }
 }
 
+impl I31Ref {
+    /// Create an `I31Ref` from the lower 31 bits of `value`, like `ref.i31`.
+    #[inline]
+    pub const fn new(value: i32) -> Self {
+        Self(Some(value as u32 & 0x7fff_ffff))
+    }
+
+    /// Create a null `I31Ref`.
+    #[inline]
+    pub const fn null() -> Self {
+        Self(None)
+    }
+
+    /// Check if the `I31Ref` is null.
+    #[inline]
+    pub const fn is_null(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// Get the value sign-extended from 31 bits, like `i31.get_s`.
+    #[inline]
+    pub const fn get_s(&self) -> Option<i32> {
+        match self.0 {
+            Some(value) => Some(((value << 1) as i32) >> 1),
+            None => None,
+        }
+    }
+
+    /// Get the value zero-extended from 31 bits, like `i31.get_u`.
+    #[inline]
+    pub const fn get_u(&self) -> Option<u32> {
+        self.0
+    }
+}
+
 impl WasmValue {
     #[doc(hidden)]
     #[inline]
@@ -112,6 +180,7 @@ impl WasmValue {
             Self::I64(i) => ConstInstruction::I64Const(*i),
             Self::F32(i) => ConstInstruction::F32Const(*i),
             Self::F64(i) => ConstInstruction::F64Const(*i),
+            Self::V128(i) => ConstInstruction::V128Const(*i),
             Self::RefFunc(i) => ConstInstruction::RefFunc(i.addr()),
             _ => unimplemented!("no const_instr for {:?}", self),
         }
@@ -128,6 +197,7 @@ impl WasmValue {
             ValType::V128 => Self::V128(0),
             ValType::RefFunc => Self::RefFunc(FuncRef::null()),
             ValType::RefExtern => Self::RefExtern(ExternRef::null()),
+            ValType::RefI31 => Self::RefI31(I31Ref::null()),
         }
     }
 
@@ -139,6 +209,7 @@ impl WasmValue {
             (Self::I64(a), Self::I64(b)) => a == b,
             (Self::RefExtern(addr), Self::RefExtern(addr2)) => addr == addr2,
             (Self::RefFunc(addr), Self::RefFunc(addr2)) => addr == addr2,
+            (Self::RefI31(a), Self::RefI31(b)) => a == b,
             (Self::F32(a), Self::F32(b)) => {
                 if a.is_nan() && b.is_nan() {
                     true // Both are NaN, treat them as equal
@@ -153,6 +224,23 @@ impl WasmValue {
                     a.to_bits() == b.to_bits()
                 }
             }
+            (Self::V128(a), Self::V128(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// Check if two `V128` values are equal as `f32x4` lanes, ignoring differences in NaN values per lane.
+    pub fn eq_loose_f32x4(&self, other: &Self) -> bool {
+        match (self.as_f32x4(), other.as_f32x4()) {
+            (Some(a), Some(b)) => a.iter().zip(b).all(|(a, b)| Self::F32(*a).eq_loose(&Self::F32(b))),
+            _ => false,
+        }
+    }
+
+    /// Check if two `V128` values are equal as `f64x2` lanes, ignoring differences in NaN values per lane.
+    pub fn eq_loose_f64x2(&self, other: &Self) -> bool {
+        match (self.as_f64x2(), other.as_f64x2()) {
+            (Some(a), Some(b)) => a.iter().zip(b).all(|(a, b)| Self::F64(*a).eq_loose(&Self::F64(b))),
             _ => false,
         }
     }
@@ -212,6 +300,53 @@ impl WasmValue {
             _ => None,
         }
     }
+
+    #[doc(hidden)]
+    pub fn as_ref_i31(&self) -> Option<I31Ref> {
+        match self {
+            Self::RefI31(ref_i31) => Some(*ref_i31),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_v128_lanes {
+    ($($lanes:literal x $t:ty => $from:ident, $as:ident;)*) => {
+        impl WasmValue {
+            $(
+                #[doc = concat!("Create a [`WasmValue::V128`] from ", stringify!($lanes), " `", stringify!($t), "` lanes, lane 0 being the least significant.")]
+                #[inline]
+                pub fn $from(lanes: [$t; $lanes]) -> Self {
+                    let mut bytes = [0u8; 16];
+                    for (chunk, lane) in bytes.chunks_exact_mut(16 / $lanes).zip(lanes) {
+                        chunk.copy_from_slice(&lane.to_le_bytes());
+                    }
+                    Self::V128(u128::from_le_bytes(bytes))
+                }
+
+                #[doc = concat!("Get the lanes of a [`WasmValue::V128`] as ", stringify!($lanes), " `", stringify!($t), "` values.")]
+                #[inline]
+                pub fn $as(&self) -> Option<[$t; $lanes]> {
+                    const WIDTH: usize = 16 / $lanes;
+                    let bytes = self.as_v128()?.to_le_bytes();
+                    Some(core::array::from_fn(|lane| {
+                        let mut buf = [0u8; WIDTH];
+                        buf.copy_from_slice(&bytes[lane * WIDTH..][..WIDTH]);
+                        <$t>::from_le_bytes(buf)
+                    }))
+                }
+            )*
+        }
+    };
+}
+
+impl_v128_lanes! {
+    16 x i8 => v128_from_i8x16, as_i8x16;
+    8 x i16 => v128_from_i16x8, as_i16x8;
+    4 x i32 => v128_from_i32x4, as_i32x4;
+    2 x i64 => v128_from_i64x2, as_i64x2;
+    4 x f32 => v128_from_f32x4, as_f32x4;
+    2 x f64 => v128_from_f64x2, as_f64x2;
 }
 
 #[cold]
@@ -224,9 +359,13 @@ impl Debug for WasmValue {
             WasmValue::I64(i) => write!(f, "i64({i})"),
             WasmValue::F32(i) => write!(f, "f32({i})"),
             WasmValue::F64(i) => write!(f, "f64({i})"),
-            WasmValue::V128(i) => write!(f, "v128({i:?})"),
+            WasmValue::V128(i) => {
+                let lanes: [i32; 4] = core::array::from_fn(|lane| (i >> (lane * 32)) as i32);
+                write!(f, "v128(i32x4{lanes:?})")
+            }
             WasmValue::RefExtern(i) => write!(f, "ref({i:?})"),
             WasmValue::RefFunc(i) => write!(f, "func({i:?})"),
+            WasmValue::RefI31(i) => write!(f, "ref({i:?})"),
         }
     }
 }
@@ -243,6 +382,7 @@ impl WasmValue {
             Self::V128(_) => ValType::V128,
             Self::RefExtern(_) => ValType::RefExtern,
             Self::RefFunc(_) => ValType::RefFunc,
+            Self::RefI31(_) => ValType::RefI31,
         }
     }
 }
@@ -250,6 +390,7 @@ impl WasmValue {
 /// Type of a WebAssembly value.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "archive", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ValType {
     /// A 32-bit integer.
     I32,
@@ -265,6 +406,8 @@ pub enum ValType {
     RefFunc,
     /// A reference to an external value.
     RefExtern,
+    /// An unboxed 31-bit integer reference.
+    RefI31,
 }
 
 impl ValType {
@@ -309,4 +452,92 @@ macro_rules! impl_conversion_for_wasmvalue {
     }
 }
 
-impl_conversion_for_wasmvalue! { i32 => I32, i64 => I64, f32 => F32, f64 => F64, u128 => V128, ExternRef => RefExtern, FuncRef => RefFunc }
+impl_conversion_for_wasmvalue! { i32 => I32, i64 => I64, f32 => F32, f64 => F64, u128 => V128, ExternRef => RefExtern, FuncRef => RefFunc, I31Ref => RefI31 }
+
+macro_rules! impl_narrow_conversion_for_wasmvalue {
+    ($($t:ty => $variant:ident),*) => {
+        $(
+            impl From<$t> for WasmValue {
+                #[inline]
+                fn from(i: $t) -> Self {
+                    Self::$variant(i.into())
+                }
+            }
+
+            // Fails if the value doesn't fit into the narrower type
+            impl TryFrom<WasmValue> for $t {
+                type Error = ();
+
+                #[inline]
+                fn try_from(value: WasmValue) -> Result<Self, Self::Error> {
+                    match value {
+                        WasmValue::$variant(i) => <$t>::try_from(i).map_err(|_| cold()),
+                        _ => {
+                            cold();
+                            Err(())
+                        }
+                    }
+                }
+            }
+        )*
+    }
+}
+
+impl_narrow_conversion_for_wasmvalue! { u16 => I32, u8 => I32 }
+
+// Unsigned integers of the same width are reinterpreted, like wasm does
+impl From<u32> for WasmValue {
+    #[inline]
+    fn from(i: u32) -> Self {
+        Self::I32(i as i32)
+    }
+}
+
+impl TryFrom<WasmValue> for u32 {
+    type Error = ();
+
+    #[inline]
+    fn try_from(value: WasmValue) -> Result<Self, Self::Error> {
+        i32::try_from(value).map(|i| i as u32)
+    }
+}
+
+impl From<u64> for WasmValue {
+    #[inline]
+    fn from(i: u64) -> Self {
+        Self::I64(i as i64)
+    }
+}
+
+impl TryFrom<WasmValue> for u64 {
+    type Error = ();
+
+    #[inline]
+    fn try_from(value: WasmValue) -> Result<Self, Self::Error> {
+        i64::try_from(value).map(|i| i as u64)
+    }
+}
+
+// Booleans are passed as i32 0 or 1, any other value is rejected
+impl From<bool> for WasmValue {
+    #[inline]
+    fn from(b: bool) -> Self {
+        Self::I32(b as i32)
+    }
+}
+
+impl TryFrom<WasmValue> for bool {
+    type Error = ();
+
+    #[inline]
+    fn try_from(value: WasmValue) -> Result<Self, Self::Error> {
+        match value {
+            WasmValue::I32(0) => Ok(false),
+            WasmValue::I32(1) => Ok(true),
+            _ => {
+                cold();
+                Err(())
+            }
+        }
+    }
+}
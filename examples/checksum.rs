@@ -0,0 +1,46 @@
+use eyre::Result;
+use tinywasm::{Extern, Imports, Module, Store};
+
+// The guest hands a region of its memory to the host as a `(ptr, len)` pair.
+const WASM: &str = r#"
+(module
+    (import "host" "checksum" (func $checksum (param i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 64) "The quick brown fox jumps over the lazy dog")
+    (func (export "checksum_message") (result i32)
+        (call $checksum (i32.const 64) (i32.const 43)))
+    (func (export "checksum") (param i32 i32) (result i32)
+        (call $checksum (local.get 0) (local.get 1)))
+)
+"#;
+
+/// Adler-32, as used by zlib
+fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), byte| {
+        let a = (a + u32::from(*byte)) % 65521;
+        (a, (b + a) % 65521)
+    });
+    (b << 16) | a
+}
+
+fn main() -> Result<()> {
+    let wasm = wat::parse_str(WASM).expect("failed to parse wat");
+    let module = Module::parse_bytes(&wasm)?;
+
+    // `func_with_memory` resolves the `(ptr, len)` arguments to a bounds-checked slice of the guest's memory
+    let mut imports = Imports::new();
+    imports.define("host", "checksum", Extern::func_with_memory(|_, data: &[u8]| Ok(adler32(data) as i32)))?;
+
+    let mut store = Store::default();
+    let instance = module.instantiate(&mut store, Some(imports))?;
+
+    let checksum_message = instance.exported_func::<(), i32>(&store, "checksum_message")?;
+    let checksum = checksum_message.call(&mut store, ())?;
+    println!("checksum: {:#010x}", checksum);
+    assert_eq!(checksum as u32, 0x5bdc0fda);
+
+    // a range reaching past the end of memory traps instead of reading garbage
+    let checksum = instance.exported_func::<(i32, i32), i32>(&store, "checksum")?;
+    assert!(checksum.call(&mut store, (65500, 100)).is_err());
+    Ok(())
+}